@@ -1,9 +1,46 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
 use std::sync::LazyLock;
-use alloy_primitives::Address;
+use alloy_primitives::{keccak256, Address, Keccak256, B256, U256};
+use alloy_sol_types::sol;
 use serde::{Deserialize, Serialize};
 use risc0_steel::config::{ChainSpec, ForkCondition};
-use revm_primitives::hardfork::SpecId;
+use risc0_steel::ethereum::{EthChainSpec, ETH_MAINNET_CHAIN_SPEC, ETH_SEPOLIA_CHAIN_SPEC};
+use risc0_steel::Commitment;
+pub use revm_primitives::hardfork::SpecId;
+
+// --- Contract Interfaces ---
+// The calls the host preflights and the guest replays, defined once for both.
+sol!(
+    interface IERC20 {
+        function balanceOf(address account) external view returns (uint256);
+        function totalSupply() external view returns (uint256);
+        function decimals() external view returns (uint8);
+        function symbol() external view returns (string);
+
+        event Transfer(address indexed from, address indexed to, uint256 value);
+    }
+
+    // https://github.com/mds1/multicall
+    interface IMulticall3 {
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
+
+        function aggregate3(Call3[] calldata calls)
+            external
+            payable
+            returns (Result[] memory returnData);
+
+        struct Result {
+            bool success;
+            bytes returnData;
+        }
+    }
+);
 
 // GuestInput: Data passed from the host to the ZKVM guest program.
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -11,7 +48,308 @@ pub struct GuestInput {
     pub required_addresses_desc: Vec<Address>, // The required addresses fetched from subgraph (DESC).
     pub n: usize,                     // The 'N' for Top-N.
     pub erc20_contract_address: Address,              // ERC20 token contract for balance checks.
-    pub chain_spec_name: String,                      // Chain spec name for the guest.
+    #[serde(alias = "chain_spec_name")]
+    pub chain: ChainName,                             // Chain the balances are read on.
+    pub excluded_addresses: Vec<Address>,             // Left out of the ranking (ascending, unique).
+    #[serde(default)]
+    pub fork_overrides: Vec<ForkOverride>,            // Applied on top of the chain spec.
+    #[serde(default)]
+    pub chunk: Option<ChunkRange>,                    // Set if the holders are one chunk of the list.
+    #[serde(default)]
+    pub commit_hhi: bool,                             // Commit the HHI of the ranked holders, see `hhi_ppm`.
+    #[serde(default)]
+    pub sanctioned_addresses: Vec<Address>,           // None of them may be ranked (ascending, unique).
+    #[serde(default)]
+    pub commit_merkle_root: Option<MerkleLeaves>,     // Commit the Merkle root of the ranked holders, see `merkle_root`.
+    #[serde(default)]
+    pub profile_cycles: bool,                         // Write a `CycleProfile` to stdout; only for --profile executions.
+}
+
+// --- Compact Encoding ---
+// How the host writes the guest input to the zkVM. The zkVM's serde encoding spends a 32-bit word
+// per byte of an address, i.e. 80 bytes per holder, and the guest pays cycles to read each word
+// back. The compact encoding packs addresses into 20 bytes:
+//
+//     version: u8, n: u64, erc20_contract_address: [u8; 20], chain id: u64,
+//     excluded_addresses: u32 count + 20 bytes each,
+//     sanctioned_addresses: u32 count + 20 bytes each,
+//     fork_overrides: u32 count + (name length: u8, name, kind: u8 (0 block, 1 timestamp), value: u64) each,
+//     chunk: u8 (0 none, 1 chunk) + (start: u64, total: u64) for a chunk,
+//     commit_hhi: u8 (0 or 1),
+//     commit_merkle_root: u8 (0 none, 1 address leaves, 2 address and balance leaves),
+//     profile_cycles: u8 (0 or 1),
+//     required_addresses_desc: u32 count + 20 bytes each
+//
+// Integers are little-endian. A changed layout gets a new version: a guest built before it fails
+// on the unknown version rather than proving something else.
+
+/// Version of the compact encoding written by `GuestInput::to_compact`.
+pub const COMPACT_INPUT_VERSION: u8 = 6;
+
+const ADDRESS_LEN: usize = 20;
+
+impl GuestInput {
+    /// The compact encoding of the input, see above.
+    pub fn to_compact(&self) -> Vec<u8> {
+        let addresses =
+            self.required_addresses_desc.len() + self.excluded_addresses.len() + self.sanctioned_addresses.len() + 1;
+        let mut out = Vec::with_capacity(32 + addresses * ADDRESS_LEN);
+        out.push(COMPACT_INPUT_VERSION);
+        out.extend_from_slice(&(self.n as u64).to_le_bytes());
+        out.extend_from_slice(self.erc20_contract_address.as_slice());
+        out.extend_from_slice(&self.chain.chain_id().to_le_bytes());
+        write_addresses(&mut out, &self.excluded_addresses);
+        write_addresses(&mut out, &self.sanctioned_addresses);
+        out.extend_from_slice(&(self.fork_overrides.len() as u32).to_le_bytes());
+        for fork_override in &self.fork_overrides {
+            out.push(fork_override.fork.len() as u8);
+            out.extend_from_slice(fork_override.fork.as_bytes());
+            let (kind, value) = match fork_override.activation {
+                ForkActivation::Block(number) => (0u8, number),
+                ForkActivation::Timestamp(timestamp) => (1u8, timestamp),
+            };
+            out.push(kind);
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        match self.chunk {
+            None => out.push(0),
+            Some(chunk) => {
+                out.push(1);
+                out.extend_from_slice(&(chunk.start as u64).to_le_bytes());
+                out.extend_from_slice(&(chunk.total as u64).to_le_bytes());
+            }
+        }
+        out.push(self.commit_hhi as u8);
+        out.push(match self.commit_merkle_root {
+            None => 0,
+            Some(MerkleLeaves::Address) => 1,
+            Some(MerkleLeaves::AddressBalance) => 2,
+        });
+        out.push(self.profile_cycles as u8);
+        write_addresses(&mut out, &self.required_addresses_desc);
+        out
+    }
+
+    /// Decodes `to_compact`'s encoding.
+    pub fn from_compact(data: &[u8]) -> Result<Self, String> {
+        let mut reader = CompactReader { data };
+        let version = reader.u8()?;
+        if version != COMPACT_INPUT_VERSION {
+            return Err(format!("Unsupported guest input version {}", version));
+        }
+        let n = reader.usize()?;
+        let erc20_contract_address = reader.address()?;
+        let chain_id = reader.u64()?;
+        let chain = ChainName::from_chain_id(chain_id).ok_or_else(|| format!("Unknown chain id {}", chain_id))?;
+        let excluded_addresses = reader.addresses()?;
+        let sanctioned_addresses = reader.addresses()?;
+        let fork_overrides = (0..reader.u32()?)
+            .map(|_| {
+                let len = reader.u8()? as usize;
+                let fork = String::from_utf8(reader.take(len)?.to_vec()).map_err(|_| "Invalid fork name".to_string())?;
+                let activation = match (reader.u8()?, reader.u64()?) {
+                    (0, number) => ForkActivation::Block(number),
+                    (1, timestamp) => ForkActivation::Timestamp(timestamp),
+                    (kind, _) => return Err(format!("Unknown fork activation kind {}", kind)),
+                };
+                Ok(ForkOverride { fork, activation })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let chunk = match reader.u8()? {
+            0 => None,
+            1 => Some(ChunkRange { start: reader.usize()?, total: reader.usize()? }),
+            flag => return Err(format!("Invalid chunk flag {}", flag)),
+        };
+        let commit_hhi = match reader.u8()? {
+            0 => false,
+            1 => true,
+            flag => return Err(format!("Invalid HHI flag {}", flag)),
+        };
+        let commit_merkle_root = match reader.u8()? {
+            0 => None,
+            1 => Some(MerkleLeaves::Address),
+            2 => Some(MerkleLeaves::AddressBalance),
+            flag => return Err(format!("Invalid Merkle root flag {}", flag)),
+        };
+        let profile_cycles = match reader.u8()? {
+            0 => false,
+            1 => true,
+            flag => return Err(format!("Invalid profile flag {}", flag)),
+        };
+        let required_addresses_desc = reader.addresses()?;
+        if !reader.data.is_empty() {
+            return Err(format!("{} trailing bytes after the guest input", reader.data.len()));
+        }
+        Ok(GuestInput {
+            required_addresses_desc,
+            n,
+            erc20_contract_address,
+            chain,
+            excluded_addresses,
+            fork_overrides,
+            chunk,
+            commit_hhi,
+            sanctioned_addresses,
+            commit_merkle_root,
+            profile_cycles,
+        })
+    }
+}
+
+fn write_addresses(out: &mut Vec<u8>, addresses: &[Address]) {
+    out.extend_from_slice(&(addresses.len() as u32).to_le_bytes());
+    for address in addresses {
+        out.extend_from_slice(address.as_slice());
+    }
+}
+
+struct CompactReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> CompactReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.data.len() < len {
+            return Err("Truncated guest input".to_string());
+        }
+        let (head, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn usize(&mut self) -> Result<usize, String> {
+        usize::try_from(self.u64()?).map_err(|_| "Integer out of range".to_string())
+    }
+
+    fn address(&mut self) -> Result<Address, String> {
+        Ok(Address::from_slice(self.take(ADDRESS_LEN)?))
+    }
+
+    fn addresses(&mut self) -> Result<Vec<Address>, String> {
+        let count = self.u32()? as usize;
+        let packed = self.take(count.checked_mul(ADDRESS_LEN).ok_or("Truncated guest input")?)?;
+        Ok(packed.chunks_exact(ADDRESS_LEN).map(Address::from_slice).collect())
+    }
+}
+
+// --- Cycle Profile ---
+// Where the guest spends its cycles, for the host's `--profile`. Only recorded with
+// `GuestInput::profile_cycles`, which the host sets for profiled executions alone, so that proofs
+// spend no cycles on it. The guest writes it to its stdout, not to the journal, so a profiled
+// execution commits the same as any other.
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CycleProfile {
+    pub input: u64,              // Guest start up to the Steel setup, mostly reading the input.
+    pub env_setup: u64,          // Building the Steel environment, i.e. checking the state proofs.
+    pub total_supply: u64,       // The totalSupply call.
+    pub ranking: u64,            // The ranking checks, including the balanceOf calls.
+    pub balance_calls: Vec<u64>, // Each balanceOf call, in call order (exclusions first).
+}
+
+impl CycleProfile {
+    /// Little-endian u64s: the four phases, then the balanceOf calls.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.input, self.env_setup, self.total_supply, self.ranking]
+            .iter()
+            .chain(&self.balance_calls)
+            .flat_map(|cycles| cycles.to_le_bytes())
+            .collect()
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        if data.len() % 8 != 0 || data.len() < 32 {
+            return Err(format!("Not a cycle profile: {} bytes", data.len()));
+        }
+        let mut words = data.chunks_exact(8).map(|word| u64::from_le_bytes(word.try_into().unwrap()));
+        let mut phase = || words.next().unwrap_or_default();
+        let (input, env_setup, total_supply, ranking) = (phase(), phase(), phase(), phase());
+        Ok(CycleProfile { input, env_setup, total_supply, ranking, balance_calls: words.collect() })
+    }
+}
+
+/// When a fork activates, see [ForkCondition].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForkActivation {
+    Block(u64),
+    Timestamp(u64),
+}
+
+/// A fork activation replacing the one in the chain spec (or adding one it lacks), e.g. the
+/// actual date of a hardfork that was announced after the guest was built.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ForkOverride {
+    pub fork: String, // The revm hardfork name, e.g. "Prague".
+    pub activation: ForkActivation,
+}
+
+impl ForkOverride {
+    pub fn spec_id(&self) -> Result<SpecId, String> {
+        SpecId::from_str(&self.fork).map_err(|_| format!("Unknown hardfork: {}", self.fork))
+    }
+}
+
+impl FromStr for ForkOverride {
+    type Err = String;
+
+    /// Parses `<fork>=block:<number>` or `<fork>=timestamp:<seconds>`, e.g.
+    /// `prague=timestamp:1746612311`. Fork names are case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (fork, activation) = s.split_once('=').ok_or_else(|| format!("Expected <fork>=<activation>: {}", s))?;
+        let (kind, value) = activation
+            .split_once(':')
+            .ok_or_else(|| format!("Expected block:<number> or timestamp:<seconds>: {}", activation))?;
+        let value: u64 = value.parse().map_err(|_| format!("Invalid {}: {}", kind, value))?;
+        let activation = match kind.to_ascii_lowercase().as_str() {
+            "block" => ForkActivation::Block(value),
+            "timestamp" => ForkActivation::Timestamp(value),
+            _ => return Err(format!("Expected block:<number> or timestamp:<seconds>: {}", activation)),
+        };
+        // revm names its hardforks capitalized, e.g. "Prague".
+        let mut chars = fork.trim().chars();
+        let fork: String = chars.next().map(|first| first.to_ascii_uppercase()).into_iter().chain(chars).collect();
+        let fork_override = ForkOverride { fork, activation };
+        fork_override.spec_id()?;
+        Ok(fork_override)
+    }
+}
+
+/// The fork `spec` puts a block under: the newest fork whose activation the block's number or
+/// timestamp has reached. None before the first fork of the spec.
+pub fn active_spec_id(spec: &EthChainSpec, number: u64, timestamp: u64) -> Option<SpecId> {
+    spec.forks
+        .iter()
+        .rev()
+        .find(|(_, condition)| match condition {
+            ForkCondition::Block(block) => number >= *block,
+            ForkCondition::Timestamp(activation) => timestamp >= *activation,
+        })
+        .map(|(spec_id, _)| *spec_id)
+}
+
+/// `spec` with `overrides` applied.
+pub fn with_fork_overrides(spec: &EthChainSpec, overrides: &[ForkOverride]) -> Result<EthChainSpec, String> {
+    let mut forks = spec.forks.clone();
+    for fork_override in overrides {
+        let condition = match fork_override.activation {
+            ForkActivation::Block(number) => ForkCondition::Block(number),
+            ForkActivation::Timestamp(timestamp) => ForkCondition::Timestamp(timestamp),
+        };
+        forks.insert(fork_override.spec_id()?, condition);
+    }
+    Ok(ChainSpec { chain_id: spec.chain_id, forks })
 }
 
 // GuestOutput: Data returned from the ZKVM guest program via the journal.
@@ -20,8 +358,822 @@ pub struct GuestInput {
 pub struct GuestOutput {
     pub verification_succeeded: bool,       // True if all guest-side checks passed.
     pub final_top_n_addresses: Vec<Address>, // The Top-N addresses determined by the guest.
+    pub commitment: Commitment,             // Steel commitment to the block the balances were read at.
+    pub excluded_addresses: Vec<Address>,   // Addresses the ranking was proven without.
+    pub failure: Option<VerificationFailure>, // Why the checks failed, if they did.
+    pub fork_overrides: Vec<ForkOverride>,  // Fork overrides the chain spec was proven with.
+    pub top_n_hhi: Option<u64>,             // HHI of the ranked holders in ppm, if requested (see `hhi_ppm`).
+    pub excluded_addresses_hash: B256,      // See `address_set_hash`.
+    pub sanctions_list_hash: Option<B256>,  // Of the list no ranked holder is on, if one was given.
+    pub top_n_merkle_root: Option<B256>,    // Of the ranked holders, if requested (see `merkle_root`).
+}
+
+/// The hash of a set of addresses (the exclusions, the sanctions list): keccak256 of the
+/// addresses' 20 bytes each, in ascending order (as in the `GuestInput`). Lets a verifier check
+/// the set against a published list by its hash alone.
+pub fn address_set_hash(addresses: &[Address]) -> B256 {
+    let mut hasher = Keccak256::new();
+    for address in addresses {
+        hasher.update(address.as_slice());
+    }
+    hasher.finalize()
+}
+
+/// Why the guest rejects an input, committed to the journal with `verification_succeeded: false`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum VerificationFailure {
+    EmptyHolders,
+    ZeroN,
+    NExceedsHolders { n: usize, holders: usize },
+    UnsortedExclusions,
+    ExcludedHolder(Address),
+    /// A holder is listed more than once, which would count its balance twice.
+    DuplicateHolder(Address),
+    ExclusionsExceedSupply,
+    /// The holder at (1-based) `position` holds more than the one before it.
+    OrderingViolation { position: usize, holder: Address, balance: U256, previous: Address, previous_balance: U256 },
+    /// The ranked holders hold more than the (remaining) supply.
+    SupplyExceeded,
+    TotalSupplyCallFailed,
+    BalanceCallFailed(Address),
+    /// Chunk `chunk` is not of the same block, token and options as the first one.
+    ChunkMismatch { chunk: usize },
+    /// The chunks do not cover the list exactly once, in order: the holder at (0-based)
+    /// `position` is missing or covered twice.
+    ChunkGap { position: usize },
+    /// The supply left after the ranked holders could hold a holder above the N-th one, see
+    /// `covers_remainder`.
+    RemainderNotCovered { remainder: U256, nth_balance: U256 },
+    UnsortedSanctionsList,
+    /// A holder to be ranked is on the sanctions list.
+    SanctionedHolder(Address),
+}
+
+impl fmt::Display for VerificationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationFailure::EmptyHolders => write!(f, "Holders list is empty"),
+            VerificationFailure::ZeroN => write!(f, "N must be greater than 0"),
+            VerificationFailure::NExceedsHolders { n, holders } => {
+                write!(f, "N ({}) exceeds number of holders ({})", n, holders)
+            }
+            VerificationFailure::UnsortedExclusions => write!(f, "Excluded addresses are not sorted and unique"),
+            VerificationFailure::ExcludedHolder(address) => {
+                write!(f, "Excluded address {} is among the ranked holders", address)
+            }
+            VerificationFailure::DuplicateHolder(address) => write!(f, "Holder {} is listed more than once", address),
+            VerificationFailure::ExclusionsExceedSupply => write!(f, "Excluded balances exceed the total supply"),
+            VerificationFailure::OrderingViolation { position, holder, balance, previous, previous_balance } => write!(
+                f,
+                "Balance is not lower than or equal to the latest balance: holder #{} {} holds {}, more than the {} of holder #{} {}",
+                position,
+                holder,
+                balance,
+                previous_balance,
+                position - 1,
+                previous
+            ),
+            VerificationFailure::SupplyExceeded => write!(f, "Top N holders exceed total supply"),
+            VerificationFailure::TotalSupplyCallFailed => write!(f, "totalSupply() failed"),
+            VerificationFailure::BalanceCallFailed(address) => write!(f, "balanceOf({}) failed", address),
+            VerificationFailure::ChunkMismatch { chunk } => {
+                write!(f, "Chunk {} is of another block, token or options than the first chunk", chunk)
+            }
+            VerificationFailure::ChunkGap { position } => {
+                write!(f, "The chunks do not cover the holders in order at holder #{}", position + 1)
+            }
+            VerificationFailure::RemainderNotCovered { remainder, nth_balance } => write!(
+                f,
+                "The remaining supply of {} is not below the balance of the N-th holder ({})",
+                remainder, nth_balance
+            ),
+            VerificationFailure::UnsortedSanctionsList => write!(f, "Sanctioned addresses are not sorted and unique"),
+            VerificationFailure::SanctionedHolder(address) => write!(f, "Holder {} is on the sanctions list", address),
+        }
+    }
+}
+
+impl GuestInput {
+    /// The hash committed for the sanctions list, or None without one.
+    pub fn sanctions_list_hash(&self) -> Option<B256> {
+        (!self.sanctioned_addresses.is_empty()).then(|| address_set_hash(&self.sanctioned_addresses))
+    }
+
+    pub fn builder(erc20_contract_address: Address, chain: ChainName) -> GuestInputBuilder {
+        GuestInputBuilder {
+            required_addresses_desc: Vec::new(),
+            n: 0,
+            erc20_contract_address,
+            chain,
+            excluded_addresses: Vec::new(),
+            fork_overrides: Vec::new(),
+            commit_hhi: false,
+            sanctioned_addresses: Vec::new(),
+            commit_merkle_root: None,
+        }
+    }
+
+    /// The checks of the input itself, before any balance is read: a non-empty holder list without
+    /// duplicates, 0 < N <= holders, sorted, unique exclusions that are not ranked, and no holder on
+    /// the (sorted, unique) sanctions list. For a chunk, N is checked against the whole list, the
+    /// rest against the chunk.
+    pub fn validate(&self) -> Result<(), VerificationFailure> {
+        let holders = &self.required_addresses_desc;
+        if holders.is_empty() {
+            return Err(VerificationFailure::EmptyHolders);
+        }
+        if self.n == 0 {
+            return Err(VerificationFailure::ZeroN);
+        }
+        let total = self.chunk.map_or(holders.len(), |chunk| chunk.total);
+        if self.n > total {
+            return Err(VerificationFailure::NExceedsHolders { n: self.n, holders: total });
+        }
+        if let Some(chunk) = self.chunk {
+            if chunk.start + holders.len() > chunk.total {
+                return Err(VerificationFailure::ChunkGap { position: chunk.total });
+            }
+        }
+        let mut seen = BTreeSet::new();
+        if let Some(duplicate) = holders.iter().find(|holder| !seen.insert(**holder)) {
+            return Err(VerificationFailure::DuplicateHolder(*duplicate));
+        }
+        // Sorted and unique, so that no excluded balance is subtracted twice.
+        if !self.excluded_addresses.windows(2).all(|pair| pair[0] < pair[1]) {
+            return Err(VerificationFailure::UnsortedExclusions);
+        }
+        if let Some(excluded) = self.excluded_addresses.iter().find(|address| seen.contains(*address)) {
+            return Err(VerificationFailure::ExcludedHolder(*excluded));
+        }
+        // Every holder the ranking is verified over is checked, so none of the committed ones is listed.
+        if !self.sanctioned_addresses.windows(2).all(|pair| pair[0] < pair[1]) {
+            return Err(VerificationFailure::UnsortedSanctionsList);
+        }
+        if let Some(sanctioned) = holders.iter().find(|holder| self.sanctioned_addresses.binary_search(holder).is_ok()) {
+            return Err(VerificationFailure::SanctionedHolder(*sanctioned));
+        }
+        Ok(())
+    }
+}
+
+/// Builds a `GuestInput` that the guest's input checks accept, so that an invalid input is
+/// rejected on the host before anything is spent on preflighting or proving it.
+#[derive(Debug, Clone)]
+pub struct GuestInputBuilder {
+    required_addresses_desc: Vec<Address>,
+    n: usize,
+    erc20_contract_address: Address,
+    chain: ChainName,
+    excluded_addresses: Vec<Address>,
+    fork_overrides: Vec<ForkOverride>,
+    commit_hhi: bool,
+    sanctioned_addresses: Vec<Address>,
+    commit_merkle_root: Option<MerkleLeaves>,
+}
+
+impl GuestInputBuilder {
+    /// The candidate holders, by descending balance.
+    pub fn holders(mut self, required_addresses_desc: Vec<Address>) -> Self {
+        self.required_addresses_desc = required_addresses_desc;
+        self
+    }
+
+    pub fn n(mut self, n: usize) -> Self {
+        self.n = n;
+        self
+    }
+
+    /// Sorted and deduplicated on `build`.
+    pub fn excluded_addresses(mut self, excluded_addresses: Vec<Address>) -> Self {
+        self.excluded_addresses = excluded_addresses;
+        self
+    }
+
+    pub fn fork_overrides(mut self, fork_overrides: Vec<ForkOverride>) -> Self {
+        self.fork_overrides = fork_overrides;
+        self
+    }
+
+    pub fn commit_hhi(mut self, commit_hhi: bool) -> Self {
+        self.commit_hhi = commit_hhi;
+        self
+    }
+
+    /// Sorted and deduplicated on `build`.
+    pub fn sanctioned_addresses(mut self, sanctioned_addresses: Vec<Address>) -> Self {
+        self.sanctioned_addresses = sanctioned_addresses;
+        self
+    }
+
+    pub fn commit_merkle_root(mut self, leaves: Option<MerkleLeaves>) -> Self {
+        self.commit_merkle_root = leaves;
+        self
+    }
+
+    pub fn build(mut self) -> Result<GuestInput, String> {
+        self.excluded_addresses.sort();
+        self.excluded_addresses.dedup();
+        self.sanctioned_addresses.sort();
+        self.sanctioned_addresses.dedup();
+        for fork_override in &self.fork_overrides {
+            fork_override.spec_id()?;
+        }
+        #[cfg(feature = "op")]
+        if !self.fork_overrides.is_empty() && op_chain_spec(self.chain).is_some() {
+            return Err(format!("Fork overrides are not supported on {}, an OP Stack chain", self.chain));
+        }
+        let input = GuestInput {
+            required_addresses_desc: self.required_addresses_desc,
+            n: self.n,
+            erc20_contract_address: self.erc20_contract_address,
+            chain: self.chain,
+            excluded_addresses: self.excluded_addresses,
+            fork_overrides: self.fork_overrides,
+            chunk: None,
+            commit_hhi: self.commit_hhi,
+            sanctioned_addresses: self.sanctioned_addresses,
+            commit_merkle_root: self.commit_merkle_root,
+            profile_cycles: false,
+        };
+        input.validate().map_err(|failure| failure.to_string())?;
+        Ok(input)
+    }
+}
+
+/// The ranking checks of the guest, over the balances returned by `balance_of`.
+///
+/// Shared by the guest (balances read through Steel) and the host's `--check` (preflighted
+/// balances), so that a check on the host predicts the outcome of the proof exactly. Returns the
+/// ranking that was verified, or the reason the guest rejects the input.
+pub fn rank_holders(
+    input: &GuestInput,
+    total_supply: U256,
+    mut balance_of: impl FnMut(Address) -> Result<U256, VerificationFailure>,
+) -> Result<Ranking, VerificationFailure> {
+    input.validate()?;
+
+    // --- Take excluded holders out of the ranked supply ---
+    let mut excluded_balance = U256::ZERO;
+    for excluded_address in &input.excluded_addresses {
+        excluded_balance += balance_of(*excluded_address)?;
+    }
+    let ranked_supply = total_supply
+        .checked_sub(excluded_balance)
+        .ok_or(VerificationFailure::ExclusionsExceedSupply)?;
+
+    // --- Verify the ordering ---
+    // The holders are sorted from the highest balance to the lowest one.
+    let mut latest: Option<(Address, U256)> = None;
+    let mut top_holders_accumulated = U256::ZERO;
+    let mut top_desc_holders: Vec<Address> = Vec::new();
+    let mut top_desc_balances: Vec<U256> = Vec::new();
+    for (i, holder_address) in input.required_addresses_desc.iter().enumerate() {
+        let balance = balance_of(*holder_address)?;
+        if let Some((prev_address, prev_balance)) = latest {
+            if balance > prev_balance {
+                return Err(VerificationFailure::OrderingViolation {
+                    position: i + 1,
+                    holder: *holder_address,
+                    balance,
+                    previous: prev_address,
+                    previous_balance: prev_balance,
+                });
+            }
+        }
+        latest = Some((*holder_address, balance));
+        top_holders_accumulated += balance;
+        top_desc_holders.push(*holder_address);
+        top_desc_balances.push(balance);
+    }
+
+    // --- Verify the supply remainder ---
+    check_coverage(ranked_supply, top_holders_accumulated, top_desc_balances[input.n - 1])?;
+    Ok(Ranking { holders: top_desc_holders, balances: top_desc_balances, ranked_supply })
+}
+
+/// Whether a list of holders by descending balance proves its Top-N: every holder left out of the
+/// list holds at most the `remainder` of the supply the list leaves over, so none can outrank the
+/// N-th holder if `nth_balance` exceeds it.
+///
+/// The one coverage rule of the proofs: the guest checks it over the whole list (`rank_holders`,
+/// `aggregate_chunks`), and the host selects the holders up to the first prefix that meets it.
+///
+/// for ex. total supply is 100, N = 2.
+///
+/// A has 40, cumulative 40, remainder 60
+/// B has 30, cumulative 70, remainder 30; 30 > 30 false
+/// C has 10, cumulative 80, remainder 20; 30 > 20 true: A and B are the Top 2
+pub fn covers_remainder(nth_balance: U256, remainder: U256) -> bool {
+    nth_balance > remainder
+}
+
+/// `covers_remainder` for holders holding `balance_sum` of the `ranked_supply` in total.
+fn check_coverage(ranked_supply: U256, balance_sum: U256, nth_balance: U256) -> Result<(), VerificationFailure> {
+    let remainder = ranked_supply.checked_sub(balance_sum).ok_or(VerificationFailure::SupplyExceeded)?;
+    if !covers_remainder(nth_balance, remainder) {
+        return Err(VerificationFailure::RemainderNotCovered { remainder, nth_balance });
+    }
+    Ok(())
+}
+
+/// The holders a ranking was verified over, with the balances they were verified with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ranking {
+    pub holders: Vec<Address>, // By descending balance.
+    pub balances: Vec<U256>,   // Of `holders`, in the same order.
+    pub ranked_supply: U256,   // Total supply less the excluded balances.
+}
+
+impl Ranking {
+    /// The HHI of the holders' shares of the ranked supply, see `hhi_ppm`.
+    pub fn hhi_ppm(&self) -> u64 {
+        hhi_ppm(&self.balances, self.ranked_supply)
+    }
+
+    /// The Merkle root of the holders with `leaves`, see `merkle_root`.
+    pub fn merkle_root(&self, leaves: MerkleLeaves) -> B256 {
+        let hashes: Vec<B256> =
+            self.holders.iter().zip(&self.balances).map(|(holder, balance)| merkle_leaf(leaves, *holder, *balance)).collect();
+        merkle_root(&hashes)
+    }
+}
+
+// --- Concentration ---
+
+/// The Herfindahl-Hirschman index of `balances` as shares of `supply`: the sum of the squared
+/// shares, in parts per million (1,000,000 for a single holder of the whole supply; divide by 100
+/// for the conventional 0-10,000 scale). Integer arithmetic only, so that the guest and the host
+/// compute the same value. Each share is truncated to whole ppm first.
+pub fn hhi_ppm(balances: &[U256], supply: U256) -> u64 {
+    const PPM: u64 = 1_000_000;
+    if supply.is_zero() {
+        return 0;
+    }
+    // Both shifted, so that a balance times a million cannot overflow; a balance is at most the supply.
+    let shift = supply.bit_len().saturating_sub(192);
+    let supply = supply >> shift;
+    let sum_of_squares: u128 = balances
+        .iter()
+        .map(|balance| {
+            let share = ((*balance >> shift).min(supply) * U256::from(PPM) / supply).to::<u128>();
+            share * share
+        })
+        .sum();
+    (sum_of_squares / PPM as u128) as u64
+}
+
+// --- Merkle Allowlist ---
+// A Merkle tree over the ranked holders in the encoding of OpenZeppelin's standard-merkle-tree, so
+// that a contract can check a holder with `MerkleProof.verify` against the root: the leaves are the
+// double keccak256 of the ABI-encoded values, sorted by hash, and the nodes hash their children in
+// sorted order. The host exports the tree with the proofs; the guest can commit the root.
+
+/// The values of a leaf.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MerkleLeaves {
+    Address,        // `address`, for an allowlist.
+    AddressBalance, // `(address, uint256)`, with the balance at the proven block.
+}
+
+impl MerkleLeaves {
+    /// The Solidity types of a leaf, as in the tree's `leafEncoding`.
+    pub fn encoding(self) -> &'static [&'static str] {
+        match self {
+            MerkleLeaves::Address => &["address"],
+            MerkleLeaves::AddressBalance => &["address", "uint256"],
+        }
+    }
+}
+
+impl fmt::Display for MerkleLeaves {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MerkleLeaves::Address => "address",
+            MerkleLeaves::AddressBalance => "address-balance",
+        })
+    }
+}
+
+impl FromStr for MerkleLeaves {
+    type Err = String;
+
+    /// Parses `address` or `address-balance`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "address" => Ok(MerkleLeaves::Address),
+            "address-balance" => Ok(MerkleLeaves::AddressBalance),
+            _ => Err(format!("Expected address or address-balance: {}", s)),
+        }
+    }
+}
+
+/// The standard leaf hash of a holder: keccak256(keccak256(abi.encode(values))).
+pub fn merkle_leaf(leaves: MerkleLeaves, address: Address, balance: U256) -> B256 {
+    let mut encoded = Vec::with_capacity(64);
+    encoded.extend_from_slice(address.into_word().as_slice());
+    if leaves == MerkleLeaves::AddressBalance {
+        encoded.extend_from_slice(&balance.to_be_bytes::<32>());
+    }
+    keccak256(keccak256(encoded))
+}
+
+/// The tree over `leaves` as a flat array, laid out like OpenZeppelin's `makeMerkleTree`: the root
+/// at 0, the children of node i at 2i + 1 and 2i + 2, and the leaves, sorted by hash, from the end.
+pub fn merkle_tree(leaves: &[B256]) -> Vec<B256> {
+    if leaves.is_empty() {
+        return Vec::new();
+    }
+    let mut sorted = leaves.to_vec();
+    sorted.sort();
+    let len = 2 * sorted.len() - 1;
+    let mut tree = vec![B256::ZERO; len];
+    for (i, leaf) in sorted.into_iter().enumerate() {
+        tree[len - 1 - i] = leaf;
+    }
+    for i in (0..len - leaves.len()).rev() {
+        tree[i] = merkle_node(tree[2 * i + 1], tree[2 * i + 2]);
+    }
+    tree
+}
+
+/// The root of the tree over `leaves`, zero without any.
+pub fn merkle_root(leaves: &[B256]) -> B256 {
+    merkle_tree(leaves).first().copied().unwrap_or_default()
+}
+
+fn merkle_node(a: B256, b: B256) -> B256 {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    keccak256([first.as_slice(), second.as_slice()].concat())
+}
+
+// --- Chunked Proofs ---
+// A candidate list too large for one proof is proven in chunks: contiguous slices of the list, each
+// proven by the guest on its own (`GuestInput::chunk`), committing a `ChunkOutput`. The aggregation
+// guest verifies the chunk receipts and checks what no chunk can check alone: the order across chunk
+// boundaries, duplicates and exclusions across chunks, N, and the remainder over the whole list.
+
+/// Where a chunk lies in the whole candidate list.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkRange {
+    pub start: usize, // Index of the chunk's first holder in the list.
+    pub total: usize, // Length of the whole list.
+}
+
+/// What a chunk proves about its holders.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChunkSummary {
+    pub first_balance: U256,
+    pub last_balance: U256,
+    pub balance_sum: U256,
+    pub ranked_supply: Option<U256>, // Total supply less the excluded balances; first chunk only.
+    pub nth_balance: Option<U256>,   // Of the list's N-th holder; only the chunk holding it.
+}
+
+/// Journal of the guest for a chunk.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChunkOutput {
+    pub commitment: Commitment,
+    pub erc20_contract_address: Address,
+    pub chain: ChainName,
+    pub excluded_addresses: Vec<Address>,
+    pub fork_overrides: Vec<ForkOverride>,
+    pub sanctions_list_hash: Option<B256>,
+    pub n: usize,
+    pub range: ChunkRange,
+    pub holders: Vec<Address>, // Verified in descending balance order.
+    pub result: Result<ChunkSummary, VerificationFailure>,
+}
+
+/// Journal of the aggregation guest: the output of the whole list, and the image ID of the guest
+/// whose chunk receipts it verified. Verifiers must check that this is the guest's image ID.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AggregateOutput {
+    pub output: GuestOutput,
+    pub chunk_image_id: [u32; 8],
+}
+
+/// The checks of one chunk, over the balances returned by `balance_of`. The first chunk also
+/// takes the excluded balances out of the `total_supply`.
+pub fn verify_chunk(
+    input: &GuestInput,
+    total_supply: impl FnOnce() -> Result<U256, VerificationFailure>,
+    mut balance_of: impl FnMut(Address) -> Result<U256, VerificationFailure>,
+) -> Result<ChunkSummary, VerificationFailure> {
+    input.validate()?;
+    let ranked_supply = match input.chunk {
+        Some(chunk) if chunk.start > 0 => None,
+        _ => {
+            let mut excluded_balance = U256::ZERO;
+            for excluded_address in &input.excluded_addresses {
+                excluded_balance += balance_of(*excluded_address)?;
+            }
+            let ranked_supply =
+                total_supply()?.checked_sub(excluded_balance).ok_or(VerificationFailure::ExclusionsExceedSupply)?;
+            Some(ranked_supply)
+        }
+    };
+
+    let start = input.chunk.map_or(0, |chunk| chunk.start);
+    let mut latest: Option<(Address, U256)> = None;
+    let mut first_balance = U256::ZERO;
+    let mut balance_sum = U256::ZERO;
+    let mut nth_balance = None;
+    for (i, holder_address) in input.required_addresses_desc.iter().enumerate() {
+        let balance = balance_of(*holder_address)?;
+        if start + i + 1 == input.n {
+            nth_balance = Some(balance);
+        }
+        match latest {
+            Some((prev_address, prev_balance)) if balance > prev_balance => {
+                return Err(VerificationFailure::OrderingViolation {
+                    position: start + i + 1,
+                    holder: *holder_address,
+                    balance,
+                    previous: prev_address,
+                    previous_balance: prev_balance,
+                });
+            }
+            Some(_) => {}
+            None => first_balance = balance,
+        }
+        latest = Some((*holder_address, balance));
+        balance_sum += balance;
+    }
+    let last_balance = latest.map_or(U256::ZERO, |(_, balance)| balance);
+    Ok(ChunkSummary { first_balance, last_balance, balance_sum, ranked_supply, nth_balance })
+}
+
+/// The checks of the aggregation guest over the (verified) chunk outputs, in list order. Returns
+/// the whole list on success.
+pub fn aggregate_chunks(n: usize, chunks: &[ChunkOutput]) -> Result<Vec<Address>, VerificationFailure> {
+    let first = chunks.first().ok_or(VerificationFailure::EmptyHolders)?;
+    let total = first.range.total;
+    let mut holders: Vec<Address> = Vec::with_capacity(total);
+    let mut balance_sum = U256::ZERO;
+    let mut latest: Option<(Address, U256)> = None;
+    let mut nth_balance = None;
+    for (i, chunk) in chunks.iter().enumerate() {
+        let summary = chunk.result.clone()?;
+        let consistent = chunk.commitment == first.commitment
+            && chunk.erc20_contract_address == first.erc20_contract_address
+            && chunk.chain == first.chain
+            && chunk.excluded_addresses == first.excluded_addresses
+            && chunk.fork_overrides == first.fork_overrides
+            && chunk.sanctions_list_hash == first.sanctions_list_hash
+            && chunk.n == n
+            && chunk.range.total == total;
+        if !consistent {
+            return Err(VerificationFailure::ChunkMismatch { chunk: i });
+        }
+        let (Some(&head), Some(&tail)) = (chunk.holders.first(), chunk.holders.last()) else {
+            return Err(VerificationFailure::ChunkGap { position: holders.len() });
+        };
+        if chunk.range.start != holders.len() {
+            return Err(VerificationFailure::ChunkGap { position: holders.len().min(chunk.range.start) });
+        }
+        // --- Order across the chunk boundary ---
+        if let Some((prev_address, prev_balance)) = latest {
+            if summary.first_balance > prev_balance {
+                return Err(VerificationFailure::OrderingViolation {
+                    position: holders.len() + 1,
+                    holder: head,
+                    balance: summary.first_balance,
+                    previous: prev_address,
+                    previous_balance: prev_balance,
+                });
+            }
+        }
+        latest = Some((tail, summary.last_balance));
+        balance_sum += summary.balance_sum;
+        nth_balance = nth_balance.or(summary.nth_balance);
+        holders.extend_from_slice(&chunk.holders);
+    }
+    if holders.len() != total {
+        return Err(VerificationFailure::ChunkGap { position: holders.len() });
+    }
+
+    // --- The whole list: N, duplicates and exclusions across the chunks ---
+    let input = GuestInput {
+        required_addresses_desc: holders,
+        n,
+        erc20_contract_address: first.erc20_contract_address,
+        chain: first.chain,
+        excluded_addresses: first.excluded_addresses.clone(),
+        fork_overrides: first.fork_overrides.clone(),
+        chunk: None,
+        commit_hhi: false,
+        sanctioned_addresses: Vec::new(), // Checked by each chunk.
+        commit_merkle_root: None,
+        profile_cycles: false,
+    };
+    input.validate()?;
+
+    // --- The supply remainder, as in `rank_holders` ---
+    let ranked_supply = first.result.as_ref().ok().and_then(|summary| summary.ranked_supply);
+    let ranked_supply = ranked_supply.ok_or(VerificationFailure::ChunkGap { position: 0 })?;
+    // The chunk holding the N-th holder recorded its balance (N <= the list, checked above).
+    let nth_balance = nth_balance.ok_or(VerificationFailure::ChunkGap { position: n - 1 })?;
+    check_coverage(ranked_supply, balance_sum, nth_balance)?;
+    Ok(input.required_addresses_desc)
+}
+
+/// The supported chains, by `--chain-spec` name. Shared by the host and the guest, so that a
+/// misspelled chain is rejected when parsing instead of reaching the guest.
+///
+/// The OP Stack chains only exist with the `op` feature. They come last, so that the (index-based)
+/// zkVM encoding of the other chains does not depend on the feature.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum ChainName {
+    Mainnet,
+    Sepolia,
+    Holesky,
+    Hoodi,
+    Gnosis,
+    Chiado,
+    Polygon,
+    Arbitrum,
+    Bsc,
+    #[cfg(feature = "op")]
+    Optimism,
+    #[cfg(feature = "op")]
+    Base,
+}
+
+impl ChainName {
+    pub const ALL: &'static [ChainName] = &[
+        ChainName::Mainnet,
+        ChainName::Sepolia,
+        ChainName::Holesky,
+        ChainName::Hoodi,
+        ChainName::Gnosis,
+        ChainName::Chiado,
+        ChainName::Polygon,
+        ChainName::Arbitrum,
+        ChainName::Bsc,
+        #[cfg(feature = "op")]
+        ChainName::Optimism,
+        #[cfg(feature = "op")]
+        ChainName::Base,
+    ];
+
+    /// The `--chain-spec` name, e.g. `gnosis`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ChainName::Mainnet => "mainnet",
+            ChainName::Sepolia => "sepolia",
+            ChainName::Holesky => "holesky",
+            ChainName::Hoodi => "hoodi",
+            ChainName::Gnosis => "gnosis",
+            ChainName::Chiado => "chiado",
+            ChainName::Polygon => "polygon",
+            ChainName::Arbitrum => "arbitrum",
+            ChainName::Bsc => "bsc",
+            #[cfg(feature = "op")]
+            ChainName::Optimism => "optimism",
+            #[cfg(feature = "op")]
+            ChainName::Base => "base",
+        }
+    }
+
+    pub fn chain_id(self) -> u64 {
+        #[cfg(feature = "op")]
+        if let Some(spec) = op_chain_spec(self) {
+            return spec.chain_id;
+        }
+        chain_spec(self).expect("every chain has a chain spec").chain_id
+    }
+
+    /// The chain with `chain_id`, if it is supported.
+    pub fn from_chain_id(chain_id: u64) -> Option<Self> {
+        Self::ALL.iter().copied().find(|chain| chain.chain_id() == chain_id)
+    }
+}
+
+impl fmt::Display for ChainName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ChainName {
+    type Err = String;
+
+    /// Parses a `--chain-spec` name (case-insensitive).
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL.iter().copied().find(|chain| chain.name().eq_ignore_ascii_case(name)).ok_or_else(|| {
+            let supported: Vec<&str> = Self::ALL.iter().map(|chain| chain.name()).collect();
+            format!("Chain spec not supported: {} (supported: {})", name, supported.join(", "))
+        })
+    }
 }
 
+/// The chain specs of the Ethereum-like chains. Adding a chain is a `ChainName` and one entry here.
+pub static CHAIN_SPECS: &[(ChainName, &LazyLock<EthChainSpec>)] = &[
+    (ChainName::Mainnet, &ETH_MAINNET_CHAIN_SPEC),
+    (ChainName::Sepolia, &ETH_SEPOLIA_CHAIN_SPEC),
+    (ChainName::Holesky, &ETH_HOLESKY_CHAIN_SPEC),
+    (ChainName::Hoodi, &ETH_HOODI_CHAIN_SPEC),
+    (ChainName::Gnosis, &GNOSIS_MAINNET_CHAIN_SPEC),
+    (ChainName::Chiado, &GNOSIS_CHIADO_CHAIN_SPEC),
+    (ChainName::Polygon, &POLYGON_MAINNET_CHAIN_SPEC),
+    (ChainName::Arbitrum, &ARBITRUM_ONE_CHAIN_SPEC),
+    (ChainName::Bsc, &BSC_MAINNET_CHAIN_SPEC),
+];
+
+/// The chain spec of `chain`, or None for an OP Stack chain.
+pub fn chain_spec(chain: ChainName) -> Option<&'static EthChainSpec> {
+    CHAIN_SPECS
+        .iter()
+        .find(|(spec_chain, _)| *spec_chain == chain)
+        .map(|(_, spec)| LazyLock::force(spec))
+}
+
+#[cfg(feature = "op")]
+pub use risc0_op_steel::optimism::{OpChainSpec, OP_MAINNET_CHAIN_SPEC};
+#[cfg(feature = "op")]
+use op_revm::OpSpecId;
+
+/// The chain specs of the OP Stack chains. Their blocks and EVM differ from Ethereum's, so the
+/// host and the guest build an OP Steel environment for them.
+#[cfg(feature = "op")]
+pub static OP_CHAIN_SPECS: &[(ChainName, &LazyLock<OpChainSpec>)] = &[
+    (ChainName::Optimism, &OP_MAINNET_CHAIN_SPEC),
+    (ChainName::Base, &BASE_MAINNET_CHAIN_SPEC),
+];
+
+/// The OP Stack chain spec of `chain`, or None for an Ethereum-like chain.
+#[cfg(feature = "op")]
+pub fn op_chain_spec(chain: ChainName) -> Option<&'static OpChainSpec> {
+    OP_CHAIN_SPECS
+        .iter()
+        .find(|(spec_chain, _)| *spec_chain == chain)
+        .map(|(_, spec)| LazyLock::force(spec))
+}
+
+/// The Base Mainnet [OpChainSpec].
+///
+/// Base launched on Bedrock. Its later network upgrades are the OP Stack (Superchain) upgrades,
+/// activated at the same timestamps as on OP Mainnet; see the superchain registry.
+#[cfg(feature = "op")]
+pub static BASE_MAINNET_CHAIN_SPEC: LazyLock<OpChainSpec> = LazyLock::new(|| ChainSpec {
+    chain_id: 8453, // Base Mainnet ID
+    forks: BTreeMap::from([
+        (OpSpecId::BEDROCK, ForkCondition::Block(0)),
+        (OpSpecId::REGOLITH, ForkCondition::Timestamp(0)),
+        // Canyon: 2024-01-11
+        (OpSpecId::CANYON, ForkCondition::Timestamp(1704992401)),
+        // Ecotone (Dencun): 2024-03-14
+        (OpSpecId::ECOTONE, ForkCondition::Timestamp(1710374401)),
+        // Fjord: 2024-07-10
+        (OpSpecId::FJORD, ForkCondition::Timestamp(1720627201)),
+        // Granite: 2024-09-11
+        (OpSpecId::GRANITE, ForkCondition::Timestamp(1726070401)),
+        // Holocene: 2025-01-09
+        (OpSpecId::HOLOCENE, ForkCondition::Timestamp(1736445601)),
+        // Isthmus (Prague): 2025-05-09
+        (OpSpecId::ISTHMUS, ForkCondition::Timestamp(1746806401)),
+    ]),
+});
+
+/// The Ethereum Holesky testnet [ChainSpec].
+pub static ETH_HOLESKY_CHAIN_SPEC: LazyLock<EthChainSpec> = LazyLock::new(|| ChainSpec {
+    chain_id: 17000, // Ethereum Holesky Testnet ID
+    forks: BTreeMap::from([
+        // Holesky launched after the Merge
+        (SpecId::MERGE, ForkCondition::Block(0)),
+
+        // Shapella (Shanghai+Capella)
+        // Activated at timestamp 1696000704 (September 29, 2023)
+        // Source: go-ethereum, params/config.go (HoleskyChainConfig)
+        (SpecId::SHANGHAI, ForkCondition::Timestamp(1696000704)),
+
+        // Dencun (Deneb+Cancun)
+        // Activated at timestamp 1707305664 (February 7, 2024)
+        // Source: go-ethereum, params/config.go (HoleskyChainConfig)
+        (SpecId::CANCUN, ForkCondition::Timestamp(1707305664)),
+
+        // Pectra (Prague+Electra)
+        // Activated at timestamp 1740434112 (February 24, 2025)
+        // Source: go-ethereum, params/config.go (HoleskyChainConfig)
+        (SpecId::PRAGUE, ForkCondition::Timestamp(1740434112)),
+    ]),
+});
+
+/// The Ethereum Hoodi testnet [ChainSpec].
+pub static ETH_HOODI_CHAIN_SPEC: LazyLock<EthChainSpec> = LazyLock::new(|| ChainSpec {
+    chain_id: 560048, // Ethereum Hoodi Testnet ID
+    forks: BTreeMap::from([
+        // Hoodi launched on Cancun (Dencun) in March 2025
+        (SpecId::CANCUN, ForkCondition::Timestamp(0)),
+
+        // Pectra (Prague+Electra)
+        // Activated at timestamp 1742999832 (March 26, 2025)
+        // Source: go-ethereum, params/config.go (HoodiChainConfig)
+        (SpecId::PRAGUE, ForkCondition::Timestamp(1742999832)),
+    ]),
+});
+
 pub type GnosisChainSpec = ChainSpec<SpecId>;
 
 /// The Gnosis Mainnet [ChainSpec].
@@ -51,3 +1203,426 @@ pub static GNOSIS_MAINNET_CHAIN_SPEC: LazyLock<GnosisChainSpec> = LazyLock::new(
         (SpecId::PRAGUE, ForkCondition::Timestamp(1746612311)), // Placeholder, align with ETH Mainnet or update when Gnosis announces
     ]),
 });
+
+/// The Gnosis Chiado testnet [ChainSpec].
+///
+/// Upgrades land on Chiado weeks before they reach Gnosis Chain, so proofs on Chiado cover the
+/// EVM the mainnet runs next.
+pub static GNOSIS_CHIADO_CHAIN_SPEC: LazyLock<GnosisChainSpec> = LazyLock::new(|| ChainSpec {
+    chain_id: 10200, // Gnosis Chiado Testnet ID
+    forks: BTreeMap::from([
+        // Chiado Merge (Bellatrix+Paris)
+        // Chiado merged in October 2022, within weeks of its launch; earlier blocks are treated as
+        // post-Merge, which only matters for proving at those blocks.
+        (SpecId::MERGE, ForkCondition::Block(0)),
+
+        // Chiado Shapella (Shanghai+Capella)
+        // Activated at timestamp 1684934220 (May 24, 2023)
+        // Source: Nethermind, chainspec/chiado.json
+        (SpecId::SHANGHAI, ForkCondition::Timestamp(1684934220)),
+
+        // Chiado Dencun (Deneb+Cancun)
+        // Activated at timestamp 1706724940 (January 31, 2024)
+        // Source: Nethermind, chainspec/chiado.json
+        (SpecId::CANCUN, ForkCondition::Timestamp(1706724940)),
+
+        // Chiado Pectra (Prague+Electra)
+        // Activated at timestamp 1741254220 (March 6, 2025)
+        // Source: Nethermind, chainspec/chiado.json
+        (SpecId::PRAGUE, ForkCondition::Timestamp(1741254220)),
+    ]),
+});
+
+/// The Polygon PoS Mainnet [ChainSpec].
+///
+/// Bor activates its hardforks at block numbers; there is no Merge, but the later EVM upgrades
+/// follow Ethereum's.
+pub static POLYGON_MAINNET_CHAIN_SPEC: LazyLock<EthChainSpec> = LazyLock::new(|| ChainSpec {
+    chain_id: 137, // Polygon PoS Mainnet ID
+    forks: BTreeMap::from([
+        // London (EIP-1559)
+        // Activated at block 23,850,000
+        // Source: bor, params/config.go (mainnet chain config)
+        (SpecId::LONDON, ForkCondition::Block(23_850_000)),
+
+        // Shanghai (PUSH0, without withdrawals)
+        // Activated at block 50,523,000
+        // Source: bor, params/config.go (mainnet chain config)
+        (SpecId::SHANGHAI, ForkCondition::Block(50_523_000)),
+
+        // Napoli (Cancun EVM changes: transient storage, MCOPY)
+        // Activated at block 54,876,000
+        // Source: bor, params/config.go (mainnet chain config)
+        (SpecId::CANCUN, ForkCondition::Block(54_876_000)),
+
+        // Bhilai (Prague EVM changes)
+        // Activated at block 73,440,256
+        // Source: bor, params/config.go (mainnet chain config)
+        (SpecId::PRAGUE, ForkCondition::Block(73_440_256)),
+    ]),
+});
+
+/// The Arbitrum One [ChainSpec].
+///
+/// Block numbers are L2 block numbers (as returned by the RPC), and the forks are the EVM
+/// upgrades shipped with ArbOS releases. Nitro blocks start out at London.
+pub static ARBITRUM_ONE_CHAIN_SPEC: LazyLock<EthChainSpec> = LazyLock::new(|| ChainSpec {
+    chain_id: 42161, // Arbitrum One ID
+    forks: BTreeMap::from([
+        // Nitro genesis (London)
+        // Block 22,207,817, the first block after the Classic -> Nitro migration
+        // Source: Arbitrum docs, "Arbitrum Nitro migration"
+        (SpecId::LONDON, ForkCondition::Block(22_207_817)),
+
+        // ArbOS 11 (Shanghai EVM changes, PUSH0)
+        // Activated at timestamp 1706634000 (January 30, 2024)
+        // Source: Arbitrum DAO proposal "AIP: ArbOS Version 11"
+        (SpecId::SHANGHAI, ForkCondition::Timestamp(1706634000)),
+
+        // ArbOS 20 "Atlas" (Cancun EVM changes)
+        // Activated at timestamp 1710770400 (March 18, 2024)
+        // Source: Arbitrum DAO proposal "AIP: ArbOS Version 20 Atlas"
+        (SpecId::CANCUN, ForkCondition::Timestamp(1710770400)),
+    ]),
+});
+
+/// The BNB Smart Chain Mainnet [ChainSpec].
+///
+/// BSC has no Merge; its hardforks bundle the Ethereum EVM upgrades, first by block number and
+/// by timestamp since Kepler.
+pub static BSC_MAINNET_CHAIN_SPEC: LazyLock<EthChainSpec> = LazyLock::new(|| ChainSpec {
+    chain_id: 56, // BNB Smart Chain Mainnet ID
+    forks: BTreeMap::from([
+        // Hertz (Berlin and London EVM changes)
+        // Activated at block 31,302,048
+        // Source: bsc, params/config.go (mainnet chain config)
+        (SpecId::LONDON, ForkCondition::Block(31_302_048)),
+
+        // Kepler (Shanghai EVM changes)
+        // Activated at timestamp 1705996800 (January 23, 2024)
+        // Source: bsc, params/config.go (mainnet chain config)
+        (SpecId::SHANGHAI, ForkCondition::Timestamp(1705996800)),
+
+        // Haber (Cancun EVM changes)
+        // Activated at timestamp 1718863500 (June 20, 2024)
+        // Source: bsc, params/config.go (mainnet chain config)
+        (SpecId::CANCUN, ForkCondition::Timestamp(1718863500)),
+
+        // Pascal (Prague EVM changes)
+        // Activated at timestamp 1742436600 (March 20, 2025)
+        // Source: bsc, params/config.go (mainnet chain config)
+        (SpecId::PRAGUE, ForkCondition::Timestamp(1742436600)),
+    ]),
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: Address = Address::repeat_byte(0xee);
+
+    /// Holders 1, 2, ... with `balances`, in that order.
+    fn holders(balances: &[u64]) -> Vec<Address> {
+        (1..=balances.len()).map(|i| Address::with_last_byte(i as u8)).collect()
+    }
+
+    fn input(n: usize, holders: Vec<Address>) -> GuestInput {
+        GuestInput {
+            required_addresses_desc: holders,
+            n,
+            erc20_contract_address: TOKEN,
+            chain: ChainName::Mainnet,
+            excluded_addresses: Vec::new(),
+            fork_overrides: Vec::new(),
+            chunk: None,
+            commit_hhi: false,
+            sanctioned_addresses: Vec::new(),
+            commit_merkle_root: None,
+            profile_cycles: false,
+        }
+    }
+
+    fn balance_of(balances: &[u64]) -> impl FnMut(Address) -> Result<U256, VerificationFailure> + '_ {
+        move |address| {
+            let index = usize::from(address.0[19]) - 1;
+            balances.get(index).map(|balance| U256::from(*balance)).ok_or(VerificationFailure::BalanceCallFailed(address))
+        }
+    }
+
+    fn rank(n: usize, total_supply: u64, balances: &[u64]) -> Result<Vec<Address>, VerificationFailure> {
+        rank_holders(&input(n, holders(balances)), U256::from(total_supply), balance_of(balances))
+            .map(|ranking| ranking.holders)
+    }
+
+    /// The chunk journals of proving the holders of `balances` in chunks of `chunk_size`.
+    fn prove_chunks(n: usize, total_supply: u64, balances: &[u64], chunk_size: usize) -> Vec<ChunkOutput> {
+        let all = holders(balances);
+        all.chunks(chunk_size)
+            .enumerate()
+            .map(|(index, chunk)| {
+                let input = GuestInput {
+                    chunk: Some(ChunkRange { start: index * chunk_size, total: all.len() }),
+                    ..input(n, chunk.to_vec())
+                };
+                let result = verify_chunk(&input, || Ok(U256::from(total_supply)), balance_of(balances));
+                ChunkOutput {
+                    commitment: Commitment::new(0, 1, B256::ZERO, B256::ZERO),
+                    erc20_contract_address: TOKEN,
+                    chain: ChainName::Mainnet,
+                    excluded_addresses: Vec::new(),
+                    fork_overrides: Vec::new(),
+                    sanctions_list_hash: None,
+                    n,
+                    range: input.chunk.unwrap(),
+                    holders: input.required_addresses_desc,
+                    result,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chunked_and_unchunked_proofs_agree() {
+        let cases: &[(usize, u64, &[u64])] = &[
+            (2, 100, &[40, 30, 10]),     // Covered after the third holder: 30 > 20.
+            (2, 100, &[40, 30]),         // Remainder 30 is not below the N-th balance.
+            (2, 100, &[60, 30]),         // Covered at exactly N holders: 30 > 10.
+            (1, 100, &[100]),            // No remainder at all.
+            (3, 100, &[30, 30, 20, 10]), // Covered after the fourth holder: 20 > 10.
+            (1, 50, &[40, 30]),          // More than the supply.
+            (2, 100, &[40, 50, 5]),      // Out of order.
+        ];
+        for &(n, total_supply, balances) in cases {
+            let expected = rank(n, total_supply, balances);
+            for chunk_size in 1..=balances.len() {
+                let chunks = prove_chunks(n, total_supply, balances, chunk_size);
+                assert_eq!(aggregate_chunks(n, &chunks), expected, "N {} of {:?} in chunks of {}", n, balances, chunk_size);
+            }
+        }
+    }
+
+    #[test]
+    fn coverage_is_decided_by_the_nth_balance() {
+        assert_eq!(rank(2, 100, &[40, 30, 10]), Ok(holders(&[40, 30, 10])));
+        assert_eq!(rank(2, 100, &[60, 30]), Ok(holders(&[60, 30])));
+        assert_eq!(
+            rank(2, 100, &[40, 30]),
+            Err(VerificationFailure::RemainderNotCovered { remainder: U256::from(30), nth_balance: U256::from(30) })
+        );
+        assert_eq!(rank(1, 50, &[40, 30]), Err(VerificationFailure::SupplyExceeded));
+    }
+
+    #[test]
+    fn aggregation_checks_the_order_across_chunk_boundaries() {
+        let chunks = prove_chunks(1, 100, &[50, 20, 30], 2);
+        assert_eq!(
+            aggregate_chunks(1, &chunks),
+            Err(VerificationFailure::OrderingViolation {
+                position: 3,
+                holder: Address::with_last_byte(3),
+                balance: U256::from(30),
+                previous: Address::with_last_byte(2),
+                previous_balance: U256::from(20),
+            })
+        );
+    }
+
+    #[test]
+    fn aggregation_rejects_gaps() {
+        let chunks = prove_chunks(1, 100, &[50, 20, 10, 5], 2);
+        assert_eq!(aggregate_chunks(1, &chunks[..1]), Err(VerificationFailure::ChunkGap { position: 2 }));
+        assert_eq!(aggregate_chunks(1, &chunks[1..]), Err(VerificationFailure::ChunkGap { position: 0 }));
+        let repeated = [chunks[0].clone(), chunks[0].clone()];
+        assert_eq!(aggregate_chunks(1, &repeated), Err(VerificationFailure::ChunkGap { position: 0 }));
+        let swapped = [chunks[1].clone(), chunks[0].clone()];
+        assert_eq!(aggregate_chunks(1, &swapped), Err(VerificationFailure::ChunkGap { position: 0 }));
+        assert_eq!(aggregate_chunks(1, &[]), Err(VerificationFailure::EmptyHolders));
+    }
+
+    #[test]
+    fn aggregation_rejects_mismatched_chunks() {
+        let chunks = prove_chunks(1, 100, &[50, 20, 10, 5], 2);
+        let mut other_token = chunks.clone();
+        other_token[1].erc20_contract_address = Address::repeat_byte(0xdd);
+        assert_eq!(aggregate_chunks(1, &other_token), Err(VerificationFailure::ChunkMismatch { chunk: 1 }));
+        let mut other_chain = chunks.clone();
+        other_chain[1].chain = ChainName::Sepolia;
+        assert_eq!(aggregate_chunks(1, &other_chain), Err(VerificationFailure::ChunkMismatch { chunk: 1 }));
+        // Proven for another N than the aggregation's.
+        assert_eq!(aggregate_chunks(2, &chunks), Err(VerificationFailure::ChunkMismatch { chunk: 0 }));
+    }
+
+    #[test]
+    fn aggregation_rejects_chunks_of_other_commitments() {
+        let chunks = prove_chunks(1, 100, &[50, 20, 10, 5], 2);
+        let commitments = [
+            Commitment::new(0, 2, B256::ZERO, B256::ZERO),               // Another block.
+            Commitment::new(0, 1, B256::repeat_byte(1), B256::ZERO),     // Another block hash.
+            Commitment::new(0, 1, B256::ZERO, B256::repeat_byte(1)),     // Another chain spec.
+        ];
+        for commitment in commitments {
+            for chunk in 0..chunks.len() {
+                let mut mismatched = chunks.clone();
+                mismatched[chunk].commitment = commitment.clone();
+                // Checked against the first chunk, so a mismatch there shows up at the second.
+                let expected = VerificationFailure::ChunkMismatch { chunk: chunk.max(1) };
+                assert_eq!(aggregate_chunks(1, &mismatched), Err(expected), "{:?} in chunk {}", commitment, chunk);
+            }
+        }
+    }
+
+    #[test]
+    fn chunks_summarize_their_holders() {
+        let balances = [50, 20, 10, 5];
+        let chunks = prove_chunks(3, 100, &balances, 2);
+        let summary = |chunk: &ChunkOutput| chunk.result.clone().unwrap();
+        // Only the first chunk reads the supply, only the chunk of the N-th holder records its balance.
+        assert_eq!(
+            summary(&chunks[0]),
+            ChunkSummary {
+                first_balance: U256::from(50),
+                last_balance: U256::from(20),
+                balance_sum: U256::from(70),
+                ranked_supply: Some(U256::from(100)),
+                nth_balance: None,
+            }
+        );
+        assert_eq!(
+            summary(&chunks[1]),
+            ChunkSummary {
+                first_balance: U256::from(10),
+                last_balance: U256::from(5),
+                balance_sum: U256::from(15),
+                ranked_supply: None,
+                nth_balance: Some(U256::from(10)),
+            }
+        );
+        // Positions are counted in the whole list.
+        let chunks = prove_chunks(1, 100, &[50, 20, 5, 10], 2);
+        assert!(matches!(chunks[1].result, Err(VerificationFailure::OrderingViolation { position: 4, .. })));
+    }
+
+    #[test]
+    fn aggregation_checks_the_remainder() {
+        // The N-th holder is in the second chunk.
+        let chunks = prove_chunks(3, 100, &[30, 30, 20, 10], 2);
+        assert_eq!(aggregate_chunks(3, &chunks), Ok(holders(&[30, 30, 20, 10])));
+        let chunks = prove_chunks(3, 200, &[30, 30, 20, 10], 2);
+        assert_eq!(
+            aggregate_chunks(3, &chunks),
+            Err(VerificationFailure::RemainderNotCovered { remainder: U256::from(110), nth_balance: U256::from(20) })
+        );
+        let chunks = prove_chunks(1, 50, &[40, 30], 1);
+        assert_eq!(aggregate_chunks(1, &chunks), Err(VerificationFailure::SupplyExceeded));
+    }
+
+    #[test]
+    fn compact_encoding_round_trips() {
+        let full = GuestInput {
+            excluded_addresses: vec![Address::repeat_byte(0x0e)],
+            fork_overrides: vec![
+                ForkOverride { fork: "Prague".to_string(), activation: ForkActivation::Timestamp(1_746_612_311) },
+                ForkOverride { fork: "Cancun".to_string(), activation: ForkActivation::Block(19_426_587) },
+            ],
+            chunk: Some(ChunkRange { start: 2, total: 5 }),
+            commit_hhi: true,
+            sanctioned_addresses: vec![Address::repeat_byte(0x5a), Address::repeat_byte(0x5b)],
+            commit_merkle_root: Some(MerkleLeaves::AddressBalance),
+            profile_cycles: true,
+            chain: ChainName::Gnosis,
+            ..input(3, holders(&[30, 20, 10]))
+        };
+        let compact = full.to_compact();
+        let decoded = GuestInput::from_compact(&compact).unwrap();
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", full));
+        assert_eq!(decoded.to_compact(), compact);
+
+        let plain = input(1, Vec::new());
+        assert_eq!(format!("{:?}", GuestInput::from_compact(&plain.to_compact()).unwrap()), format!("{:?}", plain));
+    }
+
+    #[test]
+    fn compact_decoding_rejects_malformed_input() {
+        let compact = input(2, holders(&[20, 10])).to_compact();
+        let mut other_version = compact.clone();
+        other_version[0] = COMPACT_INPUT_VERSION - 1;
+        assert!(GuestInput::from_compact(&other_version).unwrap_err().contains("version"));
+        assert!(GuestInput::from_compact(&compact[..compact.len() - 1]).unwrap_err().contains("Truncated"));
+        let trailing = [compact.as_slice(), &[0]].concat();
+        assert!(GuestInput::from_compact(&trailing).unwrap_err().contains("trailing"));
+        assert!(GuestInput::from_compact(&[]).is_err());
+    }
+
+    #[test]
+    fn hhi_is_the_sum_of_squared_ppm_shares() {
+        let hhi = |balances: &[u64], supply: u64| {
+            hhi_ppm(&balances.iter().map(|balance| U256::from(*balance)).collect::<Vec<_>>(), U256::from(supply))
+        };
+        assert_eq!(hhi(&[100], 100), 1_000_000);
+        assert_eq!(hhi(&[50, 50], 100), 500_000);
+        assert_eq!(hhi(&[50], 100), 250_000);
+        // Shares are truncated to 333,333 ppm before squaring.
+        assert_eq!(hhi(&[1, 1, 1], 3), 333_332);
+        assert_eq!(hhi(&[], 100), 0);
+        assert_eq!(hhi(&[0], 0), 0);
+    }
+
+    #[test]
+    fn hhi_does_not_overflow_with_large_supplies() {
+        assert_eq!(hhi_ppm(&[U256::MAX], U256::MAX), 1_000_000);
+        let half = U256::MAX >> 1;
+        // Each half is a share of 499,999 ppm, just under a half.
+        assert_eq!(hhi_ppm(&[half, half], U256::MAX), 499_998);
+    }
+
+    /// The proof of the leaf at `index` of `tree`, like `getProof` of OpenZeppelin's merkle-tree.
+    fn merkle_proof(tree: &[B256], mut index: usize) -> Vec<B256> {
+        let mut proof = Vec::new();
+        while index > 0 {
+            let sibling = if index % 2 == 1 { index + 1 } else { index - 1 };
+            proof.push(tree[sibling]);
+            index = (index - 1) / 2;
+        }
+        proof
+    }
+
+    /// `MerkleProof.processProof` of OpenZeppelin Contracts.
+    fn process_proof(leaf: B256, proof: &[B256]) -> B256 {
+        proof.iter().fold(leaf, |node, sibling| merkle_node(node, *sibling))
+    }
+
+    #[test]
+    fn merkle_leaves_are_double_hashed_abi_encodings() {
+        use alloy_sol_types::SolValue;
+
+        let (address, balance) = (Address::repeat_byte(0x11), U256::from(5_000_000_000_000_000_000u128));
+        assert_eq!(merkle_leaf(MerkleLeaves::Address, address, balance), keccak256(keccak256(address.abi_encode())));
+        assert_eq!(
+            merkle_leaf(MerkleLeaves::AddressBalance, address, balance),
+            keccak256(keccak256((address, balance).abi_encode()))
+        );
+    }
+
+    #[test]
+    fn merkle_proofs_verify_against_the_root() {
+        for count in 1..=7u64 {
+            let leaves: Vec<B256> = (1..=count)
+                .map(|i| merkle_leaf(MerkleLeaves::AddressBalance, Address::with_last_byte(i as u8), U256::from(i)))
+                .collect();
+            let tree = merkle_tree(&leaves);
+            assert_eq!(tree.len(), 2 * leaves.len() - 1);
+            assert_eq!(merkle_root(&leaves), tree[0]);
+            // The leaves are at the end, sorted by hash, so the first leaf is at the last index.
+            let mut sorted = leaves.clone();
+            sorted.sort();
+            assert_eq!(&tree[tree.len() - leaves.len()..], sorted.iter().rev().copied().collect::<Vec<_>>());
+            for leaf in &leaves {
+                let index = tree.iter().rposition(|node| node == leaf).unwrap();
+                assert_eq!(process_proof(*leaf, &merkle_proof(&tree, index)), tree[0]);
+            }
+        }
+        let single = merkle_leaf(MerkleLeaves::Address, Address::with_last_byte(1), U256::ZERO);
+        assert_eq!(merkle_root(&[single]), single);
+        assert_eq!(merkle_root(&[]), B256::ZERO);
+    }
+}
@@ -0,0 +1,123 @@
+// --- Account Classification ---
+// Whether the ranked holders are EOAs or contracts at the proven block, from their code
+// (eth_getCode), for the reports: a Top-N of bridges and pools reads differently from one of
+// wallets. With --detect-proxies, contracts are also checked for the proxy patterns that name
+// their implementation: EIP-1167 minimal proxies (in the code) and EIP-1967 proxies (in the
+// implementation slot). Beacon and custom proxies are reported as plain contracts.
+//
+// Informational only: the classification is not part of the proof.
+
+use alloy::network::Network;
+use alloy::providers::Provider;
+use futures::{stream, StreamExt};
+use risc0_steel::alloy::primitives::{b256, Address, Bytes, B256, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::{info, warn};
+
+use crate::rate_limit::RateLimiter;
+
+/// keccak256("eip1967.proxy.implementation") - 1
+const EIP1967_IMPLEMENTATION_SLOT: B256 = b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+
+/// The code of an EIP-1167 minimal proxy around the 20-byte implementation address.
+const EIP1167_PREFIX: [u8; 10] = [0x36, 0x3d, 0x3d, 0x37, 0x3d, 0x3d, 0x3d, 0x36, 0x3d, 0x73];
+const EIP1167_SUFFIX: [u8; 15] = [0x5a, 0xf4, 0x3d, 0x82, 0x80, 0x3e, 0x90, 0x3d, 0x91, 0x60, 0x2b, 0x57, 0xfd, 0x5b, 0xf3];
+
+/// An EIP-7702 delegation designator: the code of an EOA that delegates to a contract.
+const EIP7702_PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountKind {
+    Eoa,
+    Contract,
+}
+
+/// What an address is at the proven block.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AccountInfo {
+    pub kind: AccountKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_implementation: Option<Address>, // With --detect-proxies.
+}
+
+impl AccountInfo {
+    /// Short tag for tables: `eoa`, `contract` or `proxy`.
+    pub fn tag(&self) -> &'static str {
+        match (self.kind, self.proxy_implementation) {
+            (AccountKind::Eoa, _) => "eoa",
+            (AccountKind::Contract, Some(_)) => "proxy",
+            (AccountKind::Contract, None) => "contract",
+        }
+    }
+}
+
+/// Classifies `addresses` at `block_number`, with up to `concurrency` requests in flight. Addresses
+/// whose code could not be fetched are left out, with a warning.
+pub async fn classify<N: Network>(
+    provider: &impl Provider<N>,
+    addresses: &[Address],
+    block_number: u64,
+    detect_proxies: bool,
+    concurrency: usize,
+    rate_limiter: &RateLimiter,
+) -> BTreeMap<Address, AccountInfo> {
+    let results: Vec<(Address, anyhow::Result<AccountInfo>)> = stream::iter(addresses)
+        .map(|&address| async move {
+            let info = classify_one(provider, address, block_number, detect_proxies, rate_limiter).await;
+            (address, info)
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+
+    let mut accounts = BTreeMap::new();
+    for (address, info) in results {
+        match info {
+            Ok(info) => {
+                accounts.insert(address, info);
+            }
+            Err(e) => warn!("Failed to classify {}: {:#}", address, e),
+        }
+    }
+    let contracts = accounts.values().filter(|info| info.kind == AccountKind::Contract).count();
+    info!("{} of {} ranked holders are contracts.", contracts, addresses.len());
+    accounts
+}
+
+async fn classify_one<N: Network>(
+    provider: &impl Provider<N>,
+    address: Address,
+    block_number: u64,
+    detect_proxies: bool,
+    rate_limiter: &RateLimiter,
+) -> anyhow::Result<AccountInfo> {
+    rate_limiter.wait().await;
+    let code: Bytes = provider.get_code_at(address).number(block_number).await?;
+    // A delegating EOA is still an EOA: it holds its own key.
+    if code.is_empty() || code.starts_with(&EIP7702_PREFIX) {
+        return Ok(AccountInfo { kind: AccountKind::Eoa, proxy_implementation: None });
+    }
+    let mut proxy_implementation = None;
+    if detect_proxies {
+        proxy_implementation = minimal_proxy_implementation(&code);
+        if proxy_implementation.is_none() {
+            rate_limiter.wait().await;
+            let slot = provider
+                .get_storage_at(address, U256::from_be_bytes(EIP1967_IMPLEMENTATION_SLOT.0))
+                .number(block_number)
+                .await?;
+            let implementation = Address::from_word(slot.into());
+            proxy_implementation = (!implementation.is_zero()).then_some(implementation);
+        }
+    }
+    Ok(AccountInfo { kind: AccountKind::Contract, proxy_implementation })
+}
+
+/// The implementation of an EIP-1167 minimal proxy, if `code` is one.
+fn minimal_proxy_implementation(code: &[u8]) -> Option<Address> {
+    let rest = code.strip_prefix(EIP1167_PREFIX.as_slice())?;
+    let implementation = rest.strip_suffix(EIP1167_SUFFIX.as_slice())?;
+    (implementation.len() == 20).then(|| Address::from_slice(implementation))
+}
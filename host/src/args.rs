@@ -0,0 +1,612 @@
+// --- Pipeline Options ---
+// The options of the pipeline stages. They double as the command-line arguments of the binary
+// (every field has a flag and an environment variable) and as the configuration of `Pipeline`
+// for services that embed the library.
+
+use alloy::rpc::types::BlockNumberOrTag;
+use clap::ValueEnum;
+use risc0_steel::alloy::primitives::Address;
+use risc0_zkvm::ProverOpts;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr; // For parsing Address with clap
+use std::time::Duration;
+use url::Url; // For parsing URLs via clap
+
+use top_n_holders_core::{ChainName, ForkOverride, MerkleLeaves};
+
+/// Where the candidate holder set comes from.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HolderSource {
+    /// Paginate `tokenHolders` from the GraphQL Subgraph(s).
+    Subgraph,
+    /// Load a Snapshot.org strategy score export (see --snapshot-file).
+    Snapshot,
+}
+
+/// Block tag the proving block is resolved from.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockTag {
+    Latest,
+    Safe,
+    Finalized,
+}
+
+impl From<BlockTag> for BlockNumberOrTag {
+    fn from(tag: BlockTag) -> Self {
+        match tag {
+            BlockTag::Latest => BlockNumberOrTag::Latest,
+            BlockTag::Safe => BlockNumberOrTag::Safe,
+            BlockTag::Finalized => BlockNumberOrTag::Finalized,
+        }
+    }
+}
+
+/// The chain to prove on: a supported chain, or `auto` to detect it from the RPC endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainSelection {
+    Auto,
+    Chain(ChainName),
+}
+
+impl FromStr for ChainSelection {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        if name.eq_ignore_ascii_case("auto") {
+            return Ok(ChainSelection::Auto);
+        }
+        name.parse().map(ChainSelection::Chain)
+    }
+}
+
+impl fmt::Display for ChainSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainSelection::Auto => f.write_str("auto"),
+            ChainSelection::Chain(chain) => chain.fmt(f),
+        }
+    }
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ArtifactArgs {
+    /// Optional: Directory for the artifacts shared between stages.
+    #[arg(long, env = "ARTIFACTS_DIR", default_value = "artifacts")]
+    pub artifacts_dir: PathBuf,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct RunArgs {
+    #[command(flatten)]
+    pub fetch: FetchArgs,
+
+    #[command(flatten)]
+    pub selection: SelectionArgs,
+
+    #[command(flatten)]
+    pub prover: ProverArgs,
+
+    #[command(flatten)]
+    pub registry: RegistryArgs,
+
+    /// Optional: URL that receives a JSON summary (POST) for every proven or failed token.
+    #[arg(long, env = "WEBHOOK_URL")]
+    pub webhook_url: Option<Url>,
+
+    /// Optional: HTTP API of an IPFS node (e.g. http://127.0.0.1:5001), or of a pinning service
+    /// with credentials in the URL. After a successful proof, the receipt, journal and JSON report
+    /// are pinned there in one directory, and its CID is printed.
+    #[arg(long, env = "IPFS_API")]
+    pub ipfs_api: Option<Url>,
+
+    /// Optional: Bucket to upload the receipt, journal and JSON report of every successful proof
+    /// to, as `s3://<bucket>/<prefix>` (S3 and compatible services) or `gs://<bucket>/<prefix>`,
+    /// under `<chain>/<token>/<block>/top-<N>/`. Credentials come from the usual AWS_* and
+    /// GOOGLE_* environment variables.
+    #[arg(long, env = "ARTIFACT_STORE")]
+    pub artifact_store: Option<Url>,
+
+    /// Optional: Options of the --artifact-store as `key=value`, comma-separated or repeated, e.g.
+    /// `aws_endpoint=https://minio.internal:9000`.
+    #[arg(long, env = "ARTIFACT_STORE_OPTIONS", value_delimiter = ',')]
+    pub artifact_store_option: Vec<String>,
+
+    /// Optional: After a successful proof, write a report of the verified Top-N to this file, in
+    /// --report-format. With several tokens, each token's address is appended to the file name
+    /// (`report-<token>.csv`).
+    #[arg(long, env = "REPORT_OUT")]
+    pub report_out: Option<PathBuf>,
+
+    /// Optional: Format of the --report-out report.
+    #[arg(long, env = "REPORT_FORMAT", value_enum, default_value_t = ReportFormat::Csv)]
+    pub report_format: ReportFormat,
+
+    /// Optional: After a successful proof, write the OpenZeppelin standard Merkle tree over the
+    /// verified Top-N (--merkle-leaves) to this JSON file, with the root and every holder's proof.
+    /// With several tokens, named like the --report-out report.
+    #[arg(long, env = "MERKLE_OUT")]
+    pub merkle_out: Option<PathBuf>,
+
+    /// Optional: After a successful proof, write the claims file of Uniswap's merkle-distributor
+    /// (index, amount and proof per holder) to this JSON file, with every verified holder's balance
+    /// as their amount. With several tokens, named like the --report-out report.
+    #[arg(long, env = "DISTRIBUTOR_OUT")]
+    pub distributor_out: Option<PathBuf>,
+
+    /// Optional: Stop after preflight, which runs the guest's ranking checks natively on the
+    /// preflighted balances, and only report whether the proof would succeed. `run` only.
+    #[arg(long, env = "CHECK_ONLY", default_value_t = false)]
+    pub check: bool,
+
+    /// Optional: Reuse a cached proof for the same token, chain, block, N and receipt kind instead of
+    /// proving again (it is verified first), and cache new proofs in the cache directory.
+    #[arg(long, env = "CACHE_PROOFS", default_value_t = false)]
+    pub cache_proofs: bool,
+
+    /// Optional: Path to a SQLite proof history. Every verified proof is recorded there (block, N,
+    /// journal and Top-N), once; see `history list`. The daemon resumes after the last recorded block.
+    #[arg(long, env = "HISTORY_DB")]
+    pub history_db: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct SelectionArgs {
+    /// The number 'N' for Top-N holders verification. Required, except for `serve` (set per request).
+    #[arg(long, env = "N_TOP_HOLDERS", value_parser = clap::value_parser!(usize).range(1..))]
+    pub n_top_holders: Option<usize>,
+
+    /// Optional: Use Multicall3 to look up the balances and the storage they read in batches. The
+    /// Steel preflight still calls balanceOf individually, like the guest, so Multicall3 stays out
+    /// of the EVM input. Defaults to false (look up each balance on its own).
+    #[arg(long, env = "USE_MULTICALL3", default_value_t = false)]
+    pub multicall3: bool,
+
+    /// Optional: Address of the Multicall3 contract used with --multicall3. Defaults to the
+    /// canonical deployment, which exists on every supported chain (checked before it is called).
+    #[arg(long, env = "MULTICALL3_ADDRESS", value_parser = Address::from_str, default_value = "0xcA11bde05977b3631167028862bE2a173976CA11")]
+    pub multicall3_address: Address,
+
+    /// Optional: Maximum number of balanceOf calls per Multicall3 `aggregate3`. Larger candidate
+    /// sets are preflighted in several aggregate3 calls, staying below provider gas/response limits.
+    #[arg(long, env = "MULTICALL_CHUNK_SIZE", default_value_t = 500, value_parser = clap::value_parser!(u64).range(1..))]
+    pub multicall_chunk_size: u64,
+
+    /// Optional: File of addresses (one per line) to leave out of the ranking, e.g. burn addresses
+    /// and bridges. The guest proves the Top-N over the supply held by everyone else.
+    #[arg(long, env = "EXCLUDE_FILE")]
+    pub exclude_file: Option<PathBuf>,
+
+    /// Optional: File of `address,label[,category]` lines (e.g. `0x28C6…1d60,Binance 14,cex`) whose
+    /// labels are shown next to the ranked holders in the reports.
+    #[arg(long, env = "LABELS_FILE")]
+    pub labels_file: Option<PathBuf>,
+
+    /// Optional: Categories of the --labels-file to leave out of the ranking, like the addresses of
+    /// --exclude-file (e.g. `cex,bridge`). Comma-separated or repeated.
+    #[arg(long, env = "EXCLUDE_CATEGORIES", value_delimiter = ',', requires = "labels_file")]
+    pub exclude_categories: Vec<String>,
+
+    /// Optional: File of sanctioned addresses (one per line, e.g. an OFAC SDN export). The guest
+    /// proves that none of them is among the ranked holders and commits the list's hash; a listed
+    /// holder fails the preflight.
+    #[arg(long, env = "SANCTIONS_FILE")]
+    pub sanctions_file: Option<PathBuf>,
+
+    /// Optional: Fork activation replacing the chain spec's, as `<fork>=block:<number>` or
+    /// `<fork>=timestamp:<seconds>` (e.g. `prague=timestamp:1746612311`), for hardforks scheduled
+    /// after the guest was built. Comma-separated or repeated. The guest commits the overrides.
+    #[arg(long, env = "FORK_OVERRIDES", value_delimiter = ',', value_parser = ForkOverride::from_str)]
+    pub fork_override: Vec<ForkOverride>,
+
+    /// Optional: Prove on Arbitrum, whose contracts cannot check the commitment to the L2 block
+    /// (BLOCKHASH does not return L2 block hashes), for verification off-chain only.
+    #[arg(long, env = "OFFCHAIN_COMMITMENT", default_value_t = false)]
+    pub offchain_commitment: bool,
+
+    /// Optional: Have the guest commit the Herfindahl-Hirschman index of the ranked holders' shares
+    /// of the supply, so that the concentration in the report is proven too. Not supported with
+    /// --chunk-size.
+    #[arg(long, env = "COMMIT_HHI", default_value_t = false)]
+    pub commit_hhi: bool,
+
+    /// Optional: Have the guest commit the root of the Merkle tree over the ranked holders (with
+    /// --merkle-leaves), so that the --merkle-out allowlist is proven too. Not supported with
+    /// --chunk-size.
+    #[arg(long, env = "COMMIT_MERKLE_ROOT", default_value_t = false)]
+    pub commit_merkle_root: bool,
+
+    /// Optional: Leaves of the Merkle tree over the ranked holders: `address`, or `address-balance`
+    /// for (address, uint256) leaves with the balance at the proven block.
+    #[arg(long, env = "MERKLE_LEAVES", default_value = "address", value_parser = MerkleLeaves::from_str)]
+    pub merkle_leaves: MerkleLeaves,
+
+    /// Optional: Check the ranked holders that are contracts for EIP-1167 and EIP-1967 proxies, and
+    /// name their implementation in the reports. Every holder is classified as EOA or contract
+    /// either way.
+    #[arg(long, env = "DETECT_PROXIES", default_value_t = false)]
+    pub detect_proxies: bool,
+
+    /// Optional: Skip looking up the ENS names of the ranked holders, which is done on mainnet for
+    /// the reports.
+    #[arg(long, env = "NO_ENS", default_value_t = false)]
+    pub no_ens: bool,
+
+    /// Optional: Maximum number of preflight RPC requests (or individual `balanceOf` calls) per
+    /// second, for rate-limited (e.g. free-tier) RPC providers. Unlimited by default.
+    #[arg(long, env = "RPC_RATE_LIMIT", value_parser = clap::value_parser!(u32).range(1..))]
+    pub rpc_rate_limit: Option<u32>,
+
+    /// Optional: Number of concurrent requests prefetching the storage of the balance preflight.
+    /// Without --multicall3, 1 preflights strictly sequentially.
+    #[arg(long, env = "PREFLIGHT_CONCURRENCY", default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+    pub preflight_concurrency: u16,
+
+    /// Optional: Attempts per preflight call for provider errors (reverts are not retried), including the first one.
+    #[arg(long, env = "PREFLIGHT_MAX_ATTEMPTS", default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..))]
+    pub preflight_max_attempts: u32,
+
+    /// Optional: Initial preflight retry backoff in milliseconds, doubled after every failed attempt.
+    #[arg(long, env = "PREFLIGHT_BACKOFF_MS", default_value_t = 1_000)]
+    pub preflight_backoff_ms: u64,
+
+    /// Optional: Directory to archive every preflighted input in, as
+    /// `<chain>-<token>-<block>/{guest_input.json, evm_input.json}`, e.g. for auditors. The
+    /// archived inputs can be replayed with `prove --evm-input`.
+    #[arg(long, env = "EVM_INPUT_ARCHIVE")]
+    pub evm_input_archive: Option<PathBuf>,
+}
+
+/// Format of the --report-out report.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Rank, address, balance and percent of supply, for spreadsheets.
+    Csv,
+    /// A versioned document with token metadata, block, holders, concentration stats and the
+    /// receipt's location, for dashboards and data warehouses.
+    Json,
+}
+
+/// Kind of receipt produced by the prover.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiptKind {
+    /// One STARK per segment; fastest to produce.
+    Composite,
+    /// A single aggregated STARK.
+    Succinct,
+    /// A Groth16 SNARK, required for on-chain verification (see `submit`).
+    Groth16,
+}
+
+/// Hash function of the proofs.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashFn {
+    /// Cheapest to verify in recursion; the RISC Zero default.
+    Poseidon2,
+    Sha256,
+}
+
+impl HashFn {
+    /// The name RISC Zero's `ProverOpts` knows the hash function by.
+    pub fn name(self) -> &'static str {
+        match self {
+            HashFn::Poseidon2 => "poseidon2",
+            HashFn::Sha256 => "sha-256",
+        }
+    }
+}
+
+impl ReceiptKind {
+    pub fn prover_opts(self) -> ProverOpts {
+        match self {
+            ReceiptKind::Composite => ProverOpts::composite(),
+            ReceiptKind::Succinct => ProverOpts::succinct(),
+            ReceiptKind::Groth16 => ProverOpts::groth16(),
+        }
+    }
+}
+
+/// Where proofs are generated, see `backend.rs`.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProverKind {
+    /// Chosen by the RISC0_PROVER and RISC0_DEV_MODE environment variables, as in other RISC Zero tools.
+    Default,
+    /// Prove on this machine.
+    Local,
+    /// Skip proving and produce fake receipts, for testing the pipeline only.
+    Dev,
+    /// Bonsai proving service (BONSAI_API_URL and BONSAI_API_KEY).
+    Bonsai,
+    /// A separate `r0vm` process (see --r0vm-path).
+    External,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ProverArgs {
+    /// Optional: Kind of receipt to produce. Defaults to composite.
+    #[arg(long, env = "RECEIPT_KIND", value_enum, default_value_t = ReceiptKind::Composite)]
+    pub receipt_kind: ReceiptKind,
+
+    /// Optional: Prover backend. Defaults to the RISC Zero environment variables.
+    #[arg(long, env = "PROVER_BACKEND", value_enum, default_value_t = ProverKind::Default)]
+    pub prover_backend: ProverKind,
+
+    /// Optional: Log2 of the maximum segment size, in cycles (e.g. 20 for segments of 1M cycles).
+    /// Smaller segments need less prover memory; larger ones prove faster where memory allows.
+    /// Defaults to the RISC Zero default.
+    #[arg(long, env = "SEGMENT_LIMIT_PO2", value_parser = clap::value_parser!(u32).range(13..=24))]
+    pub segment_limit_po2: Option<u32>,
+
+    /// Optional: Hash function of the proofs. Defaults to the RISC Zero default (poseidon2).
+    #[arg(long, env = "HASHFN", value_enum)]
+    pub hashfn: Option<HashFn>,
+
+    /// Optional: Prove executions that end in a guest error (a panic) instead of failing, e.g. to
+    /// prove that an input is rejected.
+    #[arg(long, env = "PROVE_GUEST_ERRORS", default_value_t = false)]
+    pub prove_guest_errors: bool,
+
+    /// Optional: Prove the candidates in chunks of this many holders, one receipt each, joined by
+    /// the aggregation guest into the final receipt. For candidate sets too large for one proof.
+    /// The preflight of `run` gives every chunk an EVM input with only its own holders' state.
+    /// Chunks are proven as succinct poseidon2 receipts without executing the whole input first,
+    /// so --hashfn and --prove-guest-errors are rejected with it.
+    #[arg(long, env = "CHUNK_SIZE", value_parser = clap::value_parser!(u64).range(1..))]
+    pub chunk_size: Option<u64>,
+
+    /// Optional: Chunks (--chunk-size) proven at the same time. Each worker runs its own prover:
+    /// separate `r0vm` processes with --prover-backend external, separate jobs with bonsai. Local
+    /// provers share the machine, so only raise it where memory allows several.
+    #[arg(long, env = "CHUNK_WORKERS", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub chunk_workers: u16,
+
+    /// Optional: Fail instead of proving locally on the CPU, e.g. on a machine where the GPU is
+    /// missing or the binary was built without `--features cuda`.
+    #[arg(long, env = "REQUIRE_GPU", default_value_t = false)]
+    pub require_gpu: bool,
+
+    /// Optional: Path to the `r0vm` binary for --prover-backend external. Defaults to `r0vm` on the PATH.
+    #[arg(long, env = "R0VM_PATH", default_value = "r0vm")]
+    pub r0vm_path: PathBuf,
+
+    /// Optional: Only execute the guest, without proving: prints the decoded journal and the cycle
+    /// count. Not supported by `daemon` and `serve`.
+    #[arg(long, env = "EXECUTE_ONLY", default_value_t = false)]
+    pub execute_only: bool,
+
+    /// Optional: Execute the guest with the RISC Zero profiler: writes a pprof profile
+    /// (`profile.pb`) and the cycles per guest phase (`cycles.json`) to the artifacts directory.
+    #[arg(long, env = "PROFILE", default_value_t = false)]
+    pub profile: bool,
+
+    /// Optional: Start proving without asking for confirmation. Only asked on a terminal; `daemon`
+    /// and `serve` never ask.
+    #[arg(long, env = "YES", default_value_t = false)]
+    pub yes: bool,
+}
+
+impl ProverArgs {
+    /// The prover options for --receipt-kind, --hashfn and --prove-guest-errors.
+    pub fn prover_opts(&self) -> ProverOpts {
+        let opts = self.receipt_kind.prover_opts().with_prove_guest_errors(self.prove_guest_errors);
+        match self.hashfn {
+            Some(hashfn) => opts.with_hashfn(hashfn.name().to_string()),
+            None => opts,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct SubmitArgs {
+    /// URL of the JSON-RPC endpoint the transaction is sent to.
+    #[arg(long, env = "RPC_URL")]
+    pub rpc_url: Url,
+
+    /// Address of the contract consuming the proof.
+    #[arg(long, env = "SUBMIT_CONTRACT", value_parser = Address::from_str)]
+    pub contract: Address,
+
+    /// Optional: Signature of the contract function, called with the journal and the seal.
+    #[arg(long, env = "SUBMIT_FUNCTION", default_value = "submit(bytes,bytes)")]
+    pub function: String,
+
+    /// Private key of the account sending the transaction.
+    #[arg(long, env = "PRIVATE_KEY", hide_env_values = true)]
+    pub private_key: String,
+
+    #[command(flatten)]
+    pub artifacts: ArtifactArgs,
+}
+
+/// Sending every verified proof to a registry contract after proving (`run`, `daemon`).
+#[derive(clap::Args, Debug, Clone)]
+pub struct RegistryArgs {
+    /// Optional: Contract that receives every verified proof right after proving, through
+    /// --registry-function. Needs proofs that verify on-chain (--receipt-kind groth16).
+    #[arg(long, env = "REGISTRY_CONTRACT", value_parser = Address::from_str, requires = "registry_private_key")]
+    pub registry_contract: Option<Address>,
+
+    /// Optional: Signature of the registry function, called with the journal and the seal.
+    #[arg(long, env = "REGISTRY_FUNCTION", default_value = "updateTopN(bytes,bytes)")]
+    pub registry_function: String,
+
+    /// Optional: JSON-RPC endpoint the registry transactions are sent to. Defaults to the first
+    /// --rpc-url.
+    #[arg(long, env = "REGISTRY_RPC_URL")]
+    pub registry_rpc_url: Option<String>,
+
+    /// Private key of the account sending the registry transactions. Required with
+    /// --registry-contract.
+    #[arg(long, env = "REGISTRY_PRIVATE_KEY", hide_env_values = true)]
+    pub registry_private_key: Option<String>,
+
+    /// Optional: Confirmations a registry transaction needs before the update counts as done.
+    #[arg(long, env = "REGISTRY_CONFIRMATIONS", default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    pub registry_confirmations: u64,
+
+    /// Optional: How long to wait for the confirmations before the update counts as failed.
+    #[arg(long, env = "REGISTRY_TIMEOUT", default_value = "10m", value_parser = humantime::parse_duration)]
+    pub registry_timeout: Duration,
+
+    /// Optional: Highest fee per gas to pay, in gwei. While the network's fee is above it, updates
+    /// fail instead of being sent.
+    #[arg(long, env = "REGISTRY_MAX_FEE_GWEI")]
+    pub registry_max_fee_gwei: Option<f64>,
+
+    /// Optional: Gas limit of the registry transactions. Defaults to the node's estimate.
+    #[arg(long, env = "REGISTRY_GAS_LIMIT")]
+    pub registry_gas_limit: Option<u64>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct FetchArgs {
+    /// URL(s) of the GraphQL Subgraph endpoint providing token holder data.
+    /// Repeat the flag (or comma-separate) to configure fallbacks, tried in order on failure.
+    /// Required unless another --source is used.
+    #[arg(long, env = "SUBGRAPH_URL", value_delimiter = ',')]
+    pub subgraph_url: Vec<String>, // Keep as String, URL parsing might be too strict
+
+    /// URL(s) of the JSON-RPC endpoint for the Ethereum node (e.g., Infura, Alchemy): `http(s)://`,
+    /// `ws(s)://` or the path of a local node's IPC socket (e.g. `/var/lib/geth/geth.ipc`).
+    /// Repeat the flag (or comma-separate) to configure fallbacks, tried in order on failure.
+    #[arg(long, env = "RPC_URL", value_delimiter = ',', required = true)]
+    pub rpc_url: Vec<String>,
+
+    /// Address of the ERC20 token contract to verify.
+    /// `run` accepts several (repeat the flag or comma-separate) and proves each of them at the
+    /// same block, with the artifacts of every token in `<artifacts-dir>/<token>/`.
+    /// Required, except for `serve` (set per request).
+    #[arg(long, env = "ERC20_ADDRESS", value_parser = Address::from_str, value_delimiter = ',')]
+    pub erc20_address: Vec<Address>,
+
+    /// Optional: Block to prove at. Defaults to the block selected by --block-tag.
+    /// Holders are fetched and all balances are preflighted at this block.
+    #[arg(long, env = "BLOCK_NUMBER", conflicts_with = "block_tag")]
+    pub block_number: Option<u64>,
+
+    /// Optional: Tag of the block to prove at. `finalized` avoids proofs that a reorg invalidates.
+    #[arg(long, env = "BLOCK_TAG", value_enum, default_value_t = BlockTag::Latest)]
+    pub block_tag: BlockTag,
+
+    /// Optional: Chain specification name (e.g., mainnet, sepolia, holesky, gnosis), or `auto` to
+    /// detect it from the chain id of the RPC endpoint.
+    /// See top_n_holders_core::ChainName for available specs; OP Stack chains (optimism, base)
+    /// need a build with the `op` feature.
+    #[arg(long, env = "CHAIN_SPEC", value_parser = ChainSelection::from_str)]
+    pub chain_spec: ChainSelection,
+
+    /// Optional: Cache Subgraph responses. Defaults to false.
+    /// Cached holders are refetched once they lag the proving block by more than --max-subgraph-lag.
+    #[arg(long, env = "CACHE_SUBGRAPH", default_value_t = false)]
+    pub cache_subgraph: bool,
+
+    /// Optional: Directory for cached data. Defaults to the platform cache directory
+    /// (e.g. ~/.cache/top-n-holders on Linux, %LOCALAPPDATA%\top-n-holders on Windows).
+    #[arg(long, env = "CACHE_DIR")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Optional: Disable pagination checkpoints. By default Subgraph pages are persisted under
+    /// the cache directory as they arrive, so an interrupted fetch resumes where it stopped.
+    #[arg(long, env = "NO_CHECKPOINTS", default_value_t = false)]
+    pub no_checkpoints: bool,
+
+    /// Optional: Maximum age of cached Subgraph responses (e.g. "30m", "12h"). Unlimited by default.
+    #[arg(long, env = "CACHE_MAX_AGE", value_parser = humantime::parse_duration)]
+    pub cache_max_age: Option<Duration>,
+
+    /// Optional: Source of the candidate holder set. Defaults to the Subgraph.
+    #[arg(long, env = "HOLDER_SOURCE", value_enum, default_value_t = HolderSource::Subgraph)]
+    pub source: HolderSource,
+
+    /// Path to a Snapshot.org score export (JSON from the score API, or `address,score` CSV).
+    #[arg(long, env = "SNAPSHOT_FILE", required_if_eq("source", "snapshot"))]
+    pub snapshot_file: Option<PathBuf>,
+
+    /// Optional: Token decimals used to convert Snapshot scores into raw balances.
+    #[arg(long, env = "SNAPSHOT_DECIMALS", default_value_t = 18)]
+    pub snapshot_decimals: u8,
+
+    /// Optional: Subgraph entity collection listing the holders.
+    #[arg(long, env = "SUBGRAPH_COLLECTION", default_value = "tokenHolders")]
+    pub subgraph_collection: String,
+
+    /// Optional: Field of the collection to filter by token address. Empty for single-token subgraphs.
+    #[arg(long, env = "SUBGRAPH_TOKEN_FIELD", default_value = "token")]
+    pub subgraph_token_field: String,
+
+    /// Optional: Dot-separated path to the holder address within an entity (e.g. `account.id`).
+    #[arg(long, env = "SUBGRAPH_HOLDER_FIELD", default_value = "id")]
+    pub subgraph_holder_field: String,
+
+    /// Optional: Dot-separated path to the raw balance within an entity (e.g. `value`).
+    #[arg(long, env = "SUBGRAPH_BALANCE_FIELD", default_value = "balance")]
+    pub subgraph_balance_field: String,
+
+    /// Optional: Keep Subgraph holders with a zero balance. By default they are dropped while fetching.
+    #[arg(long, env = "KEEP_ZERO", default_value_t = false)]
+    pub keep_zero: bool,
+
+    /// Optional: Maximum number of top holders kept in memory while streaming Subgraph pages
+    /// (used when neither --cache-subgraph nor --holder-index needs the full holder list).
+    #[arg(long, env = "MAX_CANDIDATES", default_value_t = 100_000, value_parser = clap::value_parser!(u64).range(1..))]
+    pub max_candidates: u64,
+
+    /// Optional: Path to a local SQLite holder index. When set, holders are seeded once from the
+    /// source and afterwards kept in sync from Transfer logs instead of being refetched.
+    #[arg(long, env = "HOLDER_INDEX")]
+    pub holder_index: Option<PathBuf>,
+
+    /// Optional: Number of blocks per eth_getLogs request when syncing the holder index.
+    #[arg(long, env = "INDEX_LOG_CHUNK", default_value_t = 2_000)]
+    pub index_log_chunk: u64,
+
+    /// Optional: Maximum number of blocks the Subgraph may lag behind the proving block.
+    #[arg(long, env = "MAX_SUBGRAPH_LAG", default_value_t = 10)]
+    pub max_subgraph_lag: u64,
+
+    /// Optional: Proceed (with a warning) when the Subgraph lags more than --max-subgraph-lag blocks.
+    #[arg(long, env = "ALLOW_STALE", default_value_t = false)]
+    pub allow_stale: bool,
+
+    /// Optional: Number of Subgraph pages fetched concurrently. 1 keeps the sequential `id_gt` walk.
+    #[arg(long, env = "SUBGRAPH_CONCURRENCY", default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+    pub subgraph_concurrency: u16,
+
+    /// Optional: Timeout for a single Subgraph HTTP request, in seconds.
+    #[arg(long, env = "SUBGRAPH_TIMEOUT_SECS", default_value_t = 60)]
+    pub subgraph_timeout_secs: u64,
+
+    /// Optional: Attempts per Subgraph endpoint for transient errors (429/5xx/timeouts), including the first one.
+    #[arg(long, env = "SUBGRAPH_MAX_ATTEMPTS", default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..))]
+    pub subgraph_max_attempts: u32,
+
+    /// Optional: Initial retry backoff in milliseconds, doubled after every failed attempt.
+    #[arg(long, env = "SUBGRAPH_BACKOFF_MS", default_value_t = 500)]
+    pub subgraph_backoff_ms: u64,
+
+    /// Optional: Upper bound for a single retry backoff in milliseconds.
+    #[arg(long, env = "SUBGRAPH_MAX_BACKOFF_MS", default_value_t = 30_000)]
+    pub subgraph_max_backoff_ms: u64,
+
+    /// Optional: Upper bound for any single wait between attempts in milliseconds, including one a
+    /// server asks for with `Retry-After`.
+    #[arg(long, env = "SUBGRAPH_MAX_DELAY_MS", default_value_t = 120_000)]
+    pub subgraph_max_delay_ms: u64,
+
+    /// Optional: Random jitter applied to each backoff, as a fraction (0.0 - 1.0).
+    #[arg(long, env = "SUBGRAPH_JITTER", default_value_t = 0.2, value_parser = parse_jitter)]
+    pub subgraph_jitter: f64,
+
+    #[command(flatten)]
+    pub artifacts: ArtifactArgs,
+}
+
+/// A retry jitter: a finite fraction in 0.0..=1.0.
+fn parse_jitter(value: &str) -> Result<f64, String> {
+    let jitter: f64 = value.parse().map_err(|e| format!("{}", e))?;
+    if jitter.is_finite() && (0.0..=1.0).contains(&jitter) {
+        Ok(jitter)
+    } else {
+        Err(format!("{} is not a fraction between 0.0 and 1.0", value))
+    }
+}
@@ -0,0 +1,261 @@
+// --- Pipeline Artifacts ---
+// Intermediate results of the pipeline stages, persisted so that each stage can be run,
+// inspected and retried on its own. Every stage reads the artifacts of the previous one.
+//
+// Layout: `<artifacts dir>/{holders.json, guest_input.json, evm_input.json, token.json, receipt.json}`, or
+// `<artifacts dir>/<token>/...` per token when several tokens are proven in one run. Chunked proofs
+// keep the receipt of each chunk in `chunk-<index>-receipt.json`, and the preflight for a chunked proof
+// the EVM input of each chunk in `chunk-<index>-evm_input.json` (described by `chunks.json`).
+// `timings.json` records how long each phase of the stages took. With --profile, `profile.pb` and
+// `cycles.json` hold the guest's pprof profile and its cycles per phase.
+
+use anyhow::{Context, Result};
+use risc0_steel::alloy::primitives::{Address, U256};
+use risc0_zkvm::Receipt;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::info;
+
+use top_n_holders_core::{ChainName, CycleProfile, GuestInput};
+
+use crate::accounts::AccountInfo;
+use crate::evm::EvmInput;
+use crate::subgraph::HolderData;
+
+/// Output of the `fetch` stage: the candidate holders for one token at the proving block.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HolderArtifact {
+    pub chain_spec: ChainName,
+    pub token: Address,
+    pub block: u64,                 // Proving block the candidates were collected for.
+    pub candidates_truncated: bool, // Only the top --max-candidates holders were kept.
+    pub holders: Vec<HolderData>,
+}
+
+/// Written by the `preflight` stage next to the EVM inputs of the chunks, see `store_chunk_evm_inputs`.
+#[derive(Serialize, Deserialize)]
+struct ChunkInputs {
+    chunk_size: usize,
+    chunks: usize,
+}
+
+/// Written by the `preflight` stage for the result summary: token metadata and the preflighted
+/// balances. Not part of the proof.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TokenArtifact {
+    pub symbol: Option<String>, // None if the token has no (string) `symbol()`.
+    pub decimals: Option<u8>,   // None if the token has no `decimals()`.
+    pub total_supply: U256,
+    pub balances: BTreeMap<Address, U256>,
+    #[serde(default)]
+    pub accounts: BTreeMap<Address, AccountInfo>, // The ranked holders, see `accounts.rs`.
+    #[serde(default)]
+    pub ens_names: BTreeMap<Address, String>, // Of the ranked holders on mainnet, see `ens.rs`.
+    #[serde(default)]
+    pub labels: BTreeMap<Address, String>, // Of the ranked holders, from --labels-file.
+}
+
+/// Wall-clock duration of one phase of the stages.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhaseTiming {
+    pub phase: String,
+    pub seconds: f64,
+}
+
+pub struct ArtifactDir {
+    dir: PathBuf,
+}
+
+impl ArtifactDir {
+    pub fn new(dir: &Path) -> Self {
+        Self { dir: dir.to_path_buf() }
+    }
+
+    /// The artifacts of one token of a multi-token run.
+    pub fn for_token(root: &Path, token: Address) -> Self {
+        Self::new(&root.join(format!("{:#x}", token)))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn store_holders(&self, holders: &HolderArtifact) -> Result<()> {
+        self.store("holders.json", holders)
+    }
+
+    pub fn load_holders(&self) -> Result<HolderArtifact> {
+        self.load("holders.json", "fetch")
+    }
+
+    /// Stores the guest input together with the Steel EVM input it was preflighted with. The chunk
+    /// EVM inputs of an earlier preflight no longer match and are dropped.
+    pub fn store_preflight(&self, guest_input: &GuestInput, evm_input: &EvmInput) -> Result<()> {
+        self.store("guest_input.json", guest_input)?;
+        self.store("evm_input.json", evm_input)?;
+        // The chunk inputs and receipts of an earlier preflight, possibly at another block.
+        for entry in fs::read_dir(&self.dir).with_context(|| format!("Failed to read {:?}", self.dir))? {
+            let path = entry?.path();
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+            if name == "chunks.json" || (name.starts_with("chunk-") && name.ends_with("-receipt.json")) {
+                fs::remove_file(&path).with_context(|| format!("Failed to remove artifact: {:?}", path))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Stores an EVM input per chunk of `chunk_size` holders, each with only the state of its chunk.
+    pub fn store_chunk_evm_inputs(&self, chunk_size: usize, evm_inputs: &[EvmInput]) -> Result<()> {
+        for (index, evm_input) in evm_inputs.iter().enumerate() {
+            self.store(&format!("chunk-{}-evm_input.json", index), evm_input)?;
+        }
+        self.store("chunks.json", &ChunkInputs { chunk_size, chunks: evm_inputs.len() })
+    }
+
+    /// The EVM inputs of the chunks, or None if the preflight did not store any for `chunk_size`.
+    pub fn load_chunk_evm_inputs(&self, chunk_size: usize, chain: ChainName) -> Result<Option<Vec<EvmInput>>> {
+        if !self.dir.join("chunks.json").exists() {
+            return Ok(None);
+        }
+        let chunks: ChunkInputs = self.load("chunks.json", "preflight")?;
+        if chunks.chunk_size != chunk_size {
+            return Ok(None);
+        }
+        (0..chunks.chunks)
+            .map(|index| {
+                let name = format!("chunk-{}-evm_input.json", index);
+                read_evm_input(&self.existing(&name, "preflight")?, chain)
+            })
+            .collect::<Result<_>>()
+            .map(Some)
+    }
+
+    /// The EVM input is parsed as the type of the chain the guest input names.
+    pub fn load_preflight(&self) -> Result<(GuestInput, EvmInput)> {
+        let guest_input: GuestInput = self.load("guest_input.json", "preflight")?;
+        let evm_input = read_evm_input(&self.existing("evm_input.json", "preflight")?, guest_input.chain)?;
+        Ok((guest_input, evm_input))
+    }
+
+    /// Copies a saved guest input and Steel EVM input into this directory, for replaying them
+    /// without RPC access.
+    pub fn import_preflight(&self, guest_input: &Path, evm_input: &Path) -> Result<()> {
+        let guest_input: GuestInput = read(guest_input)?;
+        let evm_input = read_evm_input(evm_input, guest_input.chain)?;
+        self.store_preflight(&guest_input, &evm_input)
+    }
+
+    pub fn store_token(&self, token: &TokenArtifact) -> Result<()> {
+        self.store("token.json", token)
+    }
+
+    /// The token metadata, or None for artifacts written before it was stored.
+    pub fn load_token(&self) -> Result<Option<TokenArtifact>> {
+        if !self.dir.join("token.json").exists() {
+            return Ok(None);
+        }
+        self.load("token.json", "preflight").map(Some)
+    }
+
+    pub fn store_receipt(&self, receipt: &Receipt) -> Result<()> {
+        self.store("receipt.json", receipt)
+    }
+
+    /// Where `store_receipt` writes the receipt.
+    pub fn receipt_path(&self) -> PathBuf {
+        self.dir.join("receipt.json")
+    }
+
+    pub fn load_receipt(&self) -> Result<Receipt> {
+        self.load("receipt.json", "prove")
+    }
+
+    pub fn store_chunk_receipt(&self, index: usize, receipt: &Receipt) -> Result<()> {
+        self.store(&format!("chunk-{}-receipt.json", index), receipt)
+    }
+
+    /// The receipt of a chunk, or None if it has not been proven yet.
+    pub fn load_chunk_receipt(&self, index: usize) -> Result<Option<Receipt>> {
+        let name = format!("chunk-{}-receipt.json", index);
+        if !self.dir.join(&name).exists() {
+            return Ok(None);
+        }
+        self.load(&name, "prove").map(Some)
+    }
+
+    /// Records the duration of `phase` in `timings.json`. Running a phase again drops it and the
+    /// phases recorded after it, which belonged to the previous run.
+    pub fn record_timing(&self, phase: &str, duration: Duration) -> Result<()> {
+        let mut timings = self.load_timings()?;
+        if let Some(position) = timings.iter().position(|timing| timing.phase == phase) {
+            timings.truncate(position);
+        }
+        timings.push(PhaseTiming { phase: phase.to_string(), seconds: duration.as_secs_f64() });
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create artifacts directory: {:?}", self.dir))?;
+        let path = self.dir.join("timings.json");
+        fs::write(&path, serde_json::to_vec_pretty(&timings)?)
+            .with_context(|| format!("Failed to write artifact: {:?}", path))
+    }
+
+    /// Where the profiler writes the pprof profile of the guest.
+    pub fn pprof_path(&self) -> PathBuf {
+        self.dir.join("profile.pb")
+    }
+
+    pub fn store_cycle_profile(&self, profile: &CycleProfile) -> Result<()> {
+        self.store("cycles.json", profile)
+    }
+
+    /// The recorded phase timings, in the order the phases ran.
+    pub fn load_timings(&self) -> Result<Vec<PhaseTiming>> {
+        if !self.dir.join("timings.json").exists() {
+            return Ok(Vec::new());
+        }
+        self.load("timings.json", "fetch")
+    }
+
+    fn store<T: Serialize>(&self, name: &str, value: &T) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create artifacts directory: {:?}", self.dir))?;
+        let path = self.dir.join(name);
+        let file = File::create(&path).with_context(|| format!("Failed to write artifact: {:?}", path))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, value).with_context(|| format!("Failed to serialize {}", name))?;
+        writer.flush().with_context(|| format!("Failed to write artifact: {:?}", path))?;
+        info!("Wrote artifact {:?}", path);
+        Ok(())
+    }
+
+    /// Loads an artifact, pointing at the stage that produces it if it is missing.
+    fn load<T: DeserializeOwned>(&self, name: &str, produced_by: &str) -> Result<T> {
+        read(&self.existing(name, produced_by)?)
+    }
+
+    fn existing(&self, name: &str, produced_by: &str) -> Result<PathBuf> {
+        let path = self.dir.join(name);
+        anyhow::ensure!(
+            path.exists(),
+            "Missing artifact {:?}. Run the `{}` stage first (with the same --artifacts-dir).",
+            path,
+            produced_by
+        );
+        Ok(path)
+    }
+}
+
+/// Parses straight from the file: `holders.json` of a large token is not read into memory first.
+fn read<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let file = File::open(path).with_context(|| format!("Failed to read artifact: {:?}", path))?;
+    serde_json::from_reader(BufReader::new(file)).with_context(|| format!("Failed to parse artifact: {:?}", path))
+}
+
+fn read_evm_input(path: &Path, chain: ChainName) -> Result<EvmInput> {
+    let data = fs::read(path).with_context(|| format!("Failed to read artifact: {:?}", path))?;
+    EvmInput::from_json(&data, chain).with_context(|| format!("Failed to parse artifact: {:?}", path))
+}
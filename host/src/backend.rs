@@ -0,0 +1,177 @@
+// --- Prover Backends ---
+// The `prove` stage hands the guest to a `ProverBackend` instead of calling `default_prover()`,
+// so proving services can be added (or supplied by a service embedding the library, see
+// `Pipeline::with_backend`) without touching the pipeline. --prover-backend selects one of the
+// built-in backends.
+//
+// Local proving runs on the GPU if the build supports one: CUDA with the `cuda` feature, Metal on
+// Apple Silicon. `local_accelerator` tells which, and the local backends log it.
+
+use anyhow::Result;
+use risc0_zkvm::{default_prover, BonsaiProver, ExecutorEnv, ExternalProver, LocalProver, Prover, ProverOpts, Receipt};
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Command;
+use tracing::{info, warn};
+
+use crate::args::{ProverArgs, ProverKind};
+
+/// Proves a guest execution.
+pub trait ProverBackend: Send + Sync {
+    /// Name shown in the logs.
+    fn name(&self) -> &str;
+
+    fn prove(&self, env: ExecutorEnv<'_>, elf: &[u8], opts: &ProverOpts) -> Result<Receipt>;
+}
+
+/// The built-in backend selected by --prover-backend.
+pub fn from_args(args: &ProverArgs) -> Result<Box<dyn ProverBackend>> {
+    // The default backend proves locally unless RISC0_PROVER or dev mode say otherwise.
+    let proves_locally = match args.prover_backend {
+        ProverKind::Local => true,
+        ProverKind::Default => {
+            std::env::var("RISC0_PROVER").map_or(true, |prover| prover == "local")
+                && std::env::var("RISC0_DEV_MODE").map_or(true, |dev_mode| matches!(dev_mode.as_str(), "" | "0" | "false"))
+        }
+        _ => false,
+    };
+    if proves_locally {
+        let accelerator = local_accelerator();
+        info!("Local proving on: {}", accelerator);
+        anyhow::ensure!(
+            !args.require_gpu || accelerator != Accelerator::Cpu,
+            "--require-gpu: the local prover would run on the CPU. Build with `--features cuda` on a machine with an NVIDIA GPU, or use --prover-backend bonsai."
+        );
+    }
+    Ok(match args.prover_backend {
+        ProverKind::Default => Box::new(DefaultBackend),
+        ProverKind::Local => Box::new(LocalBackend),
+        ProverKind::Dev => Box::new(DevBackend::new()),
+        ProverKind::Bonsai => Box::new(BonsaiBackend::new()?),
+        ProverKind::External => Box::new(ExternalBackend { r0vm_path: args.r0vm_path.clone() }),
+    })
+}
+
+/// The hardware the local prover runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Accelerator {
+    Cuda,
+    Metal,
+    Cpu,
+}
+
+impl fmt::Display for Accelerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Accelerator::Cuda => write!(f, "GPU (CUDA)"),
+            Accelerator::Metal => write!(f, "GPU (Metal)"),
+            Accelerator::Cpu => write!(f, "CPU"),
+        }
+    }
+}
+
+/// The accelerator of this build, if the machine has the device for it. A CUDA build on a machine
+/// without an NVIDIA GPU cannot prove at all, which is worth knowing before the execution.
+pub fn local_accelerator() -> Accelerator {
+    if cfg!(feature = "cuda") {
+        let has_gpu = Command::new("nvidia-smi")
+            .arg("-L")
+            .output()
+            .is_ok_and(|output| output.status.success() && !output.stdout.is_empty());
+        if !has_gpu {
+            warn!("Built with CUDA, but `nvidia-smi -L` finds no GPU; local proving will fail");
+            return Accelerator::Cpu;
+        }
+        Accelerator::Cuda
+    } else if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+        Accelerator::Metal
+    } else {
+        Accelerator::Cpu
+    }
+}
+
+/// `default_prover()`: RISC0_PROVER and RISC0_DEV_MODE decide.
+struct DefaultBackend;
+
+impl ProverBackend for DefaultBackend {
+    fn name(&self) -> &str {
+        "default"
+    }
+
+    fn prove(&self, env: ExecutorEnv<'_>, elf: &[u8], opts: &ProverOpts) -> Result<Receipt> {
+        Ok(default_prover().prove_with_opts(env, elf, opts)?.receipt)
+    }
+}
+
+struct LocalBackend;
+
+impl ProverBackend for LocalBackend {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    fn prove(&self, env: ExecutorEnv<'_>, elf: &[u8], opts: &ProverOpts) -> Result<Receipt> {
+        Ok(LocalProver::new("local").prove_with_opts(env, elf, opts)?.receipt)
+    }
+}
+
+/// Fake receipts. Dev mode is switched on for the whole process, so that `verify` accepts them.
+struct DevBackend;
+
+impl DevBackend {
+    fn new() -> Self {
+        warn!("Dev mode: receipts are fake and prove nothing. Use it for testing only.");
+        std::env::set_var("RISC0_DEV_MODE", "1");
+        Self
+    }
+}
+
+impl ProverBackend for DevBackend {
+    fn name(&self) -> &str {
+        "dev"
+    }
+
+    fn prove(&self, env: ExecutorEnv<'_>, elf: &[u8], opts: &ProverOpts) -> Result<Receipt> {
+        Ok(default_prover().prove_with_opts(env, elf, opts)?.receipt)
+    }
+}
+
+struct BonsaiBackend;
+
+impl BonsaiBackend {
+    fn new() -> Result<Self> {
+        for variable in ["BONSAI_API_URL", "BONSAI_API_KEY"] {
+            anyhow::ensure!(
+                std::env::var_os(variable).is_some(),
+                "{} must be set for --prover-backend bonsai",
+                variable
+            );
+        }
+        Ok(Self)
+    }
+}
+
+impl ProverBackend for BonsaiBackend {
+    fn name(&self) -> &str {
+        "bonsai"
+    }
+
+    fn prove(&self, env: ExecutorEnv<'_>, elf: &[u8], opts: &ProverOpts) -> Result<Receipt> {
+        Ok(BonsaiProver::new("bonsai").prove_with_opts(env, elf, opts)?.receipt)
+    }
+}
+
+/// A separate `r0vm` process, e.g. a build with GPU support.
+struct ExternalBackend {
+    r0vm_path: PathBuf,
+}
+
+impl ProverBackend for ExternalBackend {
+    fn name(&self) -> &str {
+        "external"
+    }
+
+    fn prove(&self, env: ExecutorEnv<'_>, elf: &[u8], opts: &ProverOpts) -> Result<Receipt> {
+        Ok(ExternalProver::new("external", &self.r0vm_path).prove_with_opts(env, elf, opts)?.receipt)
+    }
+}
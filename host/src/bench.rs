@@ -0,0 +1,92 @@
+// --- Benchmark ---
+// `bench` executes the guest on synthetic candidate sets of several sizes and reports the cycles,
+// so that guest changes can be compared. Each set is preflighted against the given RPC endpoint,
+// typically a local fork (`anvil --fork-url …`), and executed without proving.
+//
+// The candidates are synthetic addresses without balances, ranked with N equal to the set size:
+// the guest calls balanceOf for every one of them, and its storage proofs are as deep as for real
+// holders. The cycles per holder are the difference between consecutive sizes, which leaves out
+// what every run pays once (reading the input, the Steel environment, totalSupply).
+
+use alloy::providers::Provider;
+use anyhow::{Context, Result};
+use risc0_steel::alloy::primitives::{keccak256, Address, U256};
+use tracing::{info, warn};
+
+use top_n_holders_host::artifacts::{ArtifactDir, HolderArtifact};
+use top_n_holders_host::stages::{self, Execution};
+use top_n_holders_host::HolderData;
+
+use crate::BenchArgs;
+
+struct BenchResult {
+    size: usize,
+    execution: Execution,
+}
+
+pub async fn bench(args: &BenchArgs) -> Result<()> {
+    if args.selection.n_top_holders.is_some() {
+        warn!("--n-top-holders is ignored by `bench`; N is the size of each candidate set");
+    }
+    let rpc_url = args.rpc_url.first().context("At least one --rpc-url is required")?;
+    let block_number = match args.block_number {
+        Some(block_number) => block_number,
+        None => stages::connect(rpc_url).await?.get_block_number().await.context("Failed to fetch the latest block")?,
+    };
+    info!("Benchmarking {} on {} at block {}", args.erc20_address, args.chain_spec, block_number);
+
+    let mut sizes: Vec<usize> = args.sizes.iter().map(|&size| size as usize).collect();
+    sizes.sort_unstable();
+    sizes.dedup();
+    let clients = stages::PreflightClients::new(&args.rpc_url);
+    let mut results = Vec::new();
+    for size in sizes {
+        let artifacts = ArtifactDir::new(&args.artifacts.artifacts_dir.join(format!("bench-{}", size)));
+        artifacts.store_holders(&HolderArtifact {
+            chain_spec: args.chain_spec,
+            token: args.erc20_address,
+            block: block_number,
+            candidates_truncated: false,
+            holders: synthetic_holders(size),
+        })?;
+        let mut selection = args.selection.clone();
+        selection.n_top_holders = Some(size);
+        stages::preflight(&clients, &selection, None, &artifacts)
+            .await
+            .with_context(|| format!("Failed to preflight {} synthetic holders", size))?;
+        let execution = stages::execute(&artifacts, Default::default())?;
+        info!("{} holders: {} cycles ({} user cycles)", size, execution.cycles, execution.user_cycles);
+        results.push(BenchResult { size, execution });
+    }
+
+    println!("{:>8} {:>14} {:>14} {:>9} {:>16}", "HOLDERS", "CYCLES", "USER CYCLES", "SEGMENTS", "CYCLES/HOLDER");
+    let mut previous: Option<&BenchResult> = None;
+    for result in &results {
+        // Against the next smaller set; the smallest set has nothing to subtract its overhead with.
+        let per_holder = previous.map(|previous| {
+            let cycles = result.execution.user_cycles.saturating_sub(previous.execution.user_cycles);
+            (cycles / (result.size - previous.size) as u64).to_string()
+        });
+        println!(
+            "{:>8} {:>14} {:>14} {:>9} {:>16}",
+            result.size,
+            result.execution.cycles,
+            result.execution.user_cycles,
+            result.execution.segments,
+            per_holder.unwrap_or_else(|| "-".to_string())
+        );
+        previous = Some(result);
+    }
+    Ok(())
+}
+
+/// Deterministic addresses, so that runs at the same block execute the same input. Their balance
+/// is zero, and the host ranks ties by ascending address, as the guest expects.
+fn synthetic_holders(count: usize) -> Vec<HolderData> {
+    (0..count as u64)
+        .map(|i| HolderData {
+            address: Address::from_word(keccak256([b"top-n-holders-bench".as_slice(), &i.to_be_bytes()].concat())),
+            balance: U256::ZERO,
+        })
+        .collect()
+}
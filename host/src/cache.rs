@@ -0,0 +1,325 @@
+// --- Holder Cache ---
+// Subgraph results cached on disk together with the block they were read at and the time
+// of the fetch, so that stale entries can be detected instead of silently reused.
+//
+// Layout: `<cache dir>/holders/<source>/<chain>-<token>/<block>.json`
+//
+// Verified proofs are cached as well (--cache-proofs), keyed by token, chain, N, receipt kind and
+// block: `<cache dir>/proofs/<chain>-<token>/top<N>-<receipt kind>/<block>.json`.
+
+use anyhow::{Context, Result};
+use risc0_steel::alloy::primitives::Address;
+use risc0_zkvm::Receipt;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+use crate::artifacts::TokenArtifact;
+use crate::subgraph::HolderData;
+
+/// Platform cache directory (`$XDG_CACHE_HOME` or `~/.cache` on Linux, `~/Library/Caches` on
+/// macOS, `%LOCALAPPDATA%` on Windows), falling back to `./tmp` if none can be determined.
+pub fn default_cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .map(|dir| dir.join("top-n-holders"))
+        .unwrap_or_else(|| PathBuf::from("./tmp"))
+}
+
+/// Cached holder sets of one token from one source, one file per block.
+pub struct HolderCacheDir {
+    dir: PathBuf,
+}
+
+impl HolderCacheDir {
+    pub fn new(cache_dir: &Path, source: &str, chain: &str, token: Address) -> Self {
+        Self {
+            dir: cache_dir
+                .join("holders")
+                .join(source)
+                .join(format!("{}-{:#x}", chain.to_lowercase(), token)),
+        }
+    }
+
+    pub fn entry_path(&self, block: u64) -> PathBuf {
+        self.dir.join(format!("{}.json", block))
+    }
+
+    /// The most recent entry at or before `block`.
+    pub fn latest_at_or_before(&self, block: u64) -> Result<Option<(PathBuf, CachedHolders)>> {
+        let Some(cached_block) = self
+            .blocks()?
+            .into_iter()
+            .filter(|&cached_block| cached_block <= block)
+            .max()
+        else {
+            return Ok(None);
+        };
+        let path = self.entry_path(cached_block);
+        Ok(CachedHolders::load(&path)?.map(|cached| (path, cached)))
+    }
+
+    fn blocks(&self) -> Result<Vec<u64>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut blocks = Vec::new();
+        for entry in fs::read_dir(&self.dir)
+            .with_context(|| format!("Failed to list cache directory: {:?}", self.dir))?
+        {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(block) = path.file_stem().and_then(|stem| stem.to_str()).and_then(|stem| stem.parse().ok()) {
+                    blocks.push(block);
+                }
+            }
+        }
+        Ok(blocks)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CachedHolders {
+    pub block: u64,      // Block the holders were read at.
+    pub fetched_at: u64, // Unix timestamp (seconds) of the fetch.
+    pub holders: Vec<HolderData>,
+}
+
+impl CachedHolders {
+    pub fn new(block: u64, holders: Vec<HolderData>) -> Self {
+        Self {
+            block,
+            fetched_at: unix_now(),
+            holders,
+        }
+    }
+
+    /// Loads a cache entry. Returns `None` if it is missing or was written in an older format.
+    ///
+    /// Entries of large tokens run into hundreds of MB, so they are parsed straight from the file
+    /// instead of being read into memory first.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let file = File::open(path).with_context(|| format!("Failed to read cache file: {:?}", path))?;
+        match serde_json::from_reader(BufReader::new(file)) {
+            Ok(cached) => Ok(Some(cached)),
+            Err(e) => {
+                warn!("Ignoring unreadable or outdated cache file {:?}: {}", path, e);
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn store(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create cache directory: {:?}", dir))?;
+        }
+        let file = File::create(path).with_context(|| format!("Failed to write cache file: {:?}", path))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, self).context("Failed to serialize holder addresses for caching")?;
+        writer.flush().with_context(|| format!("Failed to write cache file: {:?}", path))?;
+        info!("Successfully wrote cache file {:?} (block {}).", path, self.block);
+        Ok(())
+    }
+
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(unix_now().saturating_sub(self.fetched_at))
+    }
+
+    /// Why this entry must not be used for proving at `block_number`, if anything.
+    pub fn staleness(&self, block_number: u64, max_block_lag: u64, max_age: Option<Duration>) -> Option<String> {
+        if self.block > block_number {
+            return Some(format!("cached at block {}, after the proving block {}", self.block, block_number));
+        }
+        if block_number - self.block > max_block_lag {
+            return Some(format!(
+                "cached at block {}, {} blocks behind the proving block {} (max lag: {})",
+                self.block,
+                block_number - self.block,
+                block_number,
+                max_block_lag
+            ));
+        }
+        match max_age {
+            Some(max_age) if self.age() > max_age => Some(format!(
+                "cached {}s ago, older than the maximum age of {}s",
+                self.age().as_secs(),
+                max_age.as_secs()
+            )),
+            _ => None,
+        }
+    }
+}
+
+// --- Proof Cache ---
+
+/// Cached proofs of the Top-N of one token, one file per block.
+pub struct ProofCacheDir {
+    dir: PathBuf,
+}
+
+impl ProofCacheDir {
+    pub fn new(cache_dir: &Path, chain: &str, token: Address, n: usize, receipt_kind: &str) -> Self {
+        Self {
+            dir: cache_dir
+                .join("proofs")
+                .join(format!("{}-{:#x}", chain.to_lowercase(), token))
+                .join(format!("top{}-{}", n, receipt_kind)),
+        }
+    }
+
+    pub fn entry_path(&self, block: u64) -> PathBuf {
+        self.dir.join(format!("{}.json", block))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CachedProof {
+    pub proven_at: u64, // Unix timestamp (seconds) of the proof.
+    pub receipt: Receipt,
+    pub token: Option<TokenArtifact>, // For the result summary.
+}
+
+impl CachedProof {
+    pub fn new(receipt: Receipt, token: Option<TokenArtifact>) -> Self {
+        Self {
+            proven_at: unix_now(),
+            receipt,
+            token,
+        }
+    }
+
+    /// Loads a cache entry. Returns `None` if it is missing or unreadable.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = fs::read(path).with_context(|| format!("Failed to read cache file: {:?}", path))?;
+        match serde_json::from_slice(&data) {
+            Ok(cached) => Ok(Some(cached)),
+            Err(e) => {
+                warn!("Ignoring unreadable or outdated cache file {:?}: {}", path, e);
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn store(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create cache directory: {:?}", dir))?;
+        }
+        let data = serde_json::to_vec(self).context("Failed to serialize the proof for caching")?;
+        fs::write(path, data).with_context(|| format!("Failed to write cache file: {:?}", path))?;
+        info!("Cached the proof in {:?}", path);
+        Ok(())
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or_default()
+}
+
+// --- Cache Maintenance ---
+
+/// One cached artifact: a file, or a directory of files (e.g. a pagination checkpoint).
+/// Entries are stored as `<category..>/<key>/<block>[.ext]` below the cache directory.
+pub struct CacheEntry {
+    pub category: String,     // e.g. `holders/subgraph` or `checkpoints`.
+    pub key: String,          // e.g. `<chain>-<token>`.
+    pub block: Option<u64>,   // Block the entry was produced at, if encoded in its name.
+    pub modified: SystemTime, // Most recent modification within the entry.
+    pub size: u64,            // Total size in bytes.
+    pub path: PathBuf,
+}
+
+impl CacheEntry {
+    pub fn age(&self) -> Duration {
+        SystemTime::now().duration_since(self.modified).unwrap_or_default()
+    }
+}
+
+/// Lists all entries below `cache_dir`, sorted by path.
+pub fn list_entries(cache_dir: &Path) -> Result<Vec<CacheEntry>> {
+    let mut entries = Vec::new();
+    if cache_dir.exists() {
+        collect_entries(cache_dir, cache_dir, &mut entries)?;
+    }
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+fn collect_entries(cache_dir: &Path, dir: &Path, entries: &mut Vec<CacheEntry>) -> Result<()> {
+    for dir_entry in fs::read_dir(dir).with_context(|| format!("Failed to list cache directory: {:?}", dir))? {
+        let path = dir_entry?.path();
+        let block = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse::<u64>().ok());
+
+        // Block-named directories (checkpoints) are one entry; other directories are namespaces.
+        if path.is_dir() && block.is_none() {
+            collect_entries(cache_dir, &path, entries)?;
+            continue;
+        }
+
+        let relative = path.strip_prefix(cache_dir).unwrap_or(&path);
+        let components: Vec<String> = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        let (key, category) = match components.as_slice() {
+            [category @ .., key, _] => (key.clone(), category.join("/")),
+            _ => (String::new(), String::new()),
+        };
+        let (size, modified) = size_and_modified(&path)?;
+        entries.push(CacheEntry { category, key, block, modified, size, path });
+    }
+    Ok(())
+}
+
+fn size_and_modified(path: &Path) -> Result<(u64, SystemTime)> {
+    let metadata = fs::metadata(path).with_context(|| format!("Failed to stat {:?}", path))?;
+    if !metadata.is_dir() {
+        return Ok((metadata.len(), metadata.modified().unwrap_or(UNIX_EPOCH)));
+    }
+    let mut total = (0, metadata.modified().unwrap_or(UNIX_EPOCH));
+    for dir_entry in fs::read_dir(path)? {
+        let (size, modified) = size_and_modified(&dir_entry?.path())?;
+        total = (total.0 + size, total.1.max(modified));
+    }
+    Ok(total)
+}
+
+/// Removes a cache entry from disk.
+pub fn remove_entry(entry: &CacheEntry) -> Result<()> {
+    let removed = if entry.path.is_dir() {
+        fs::remove_dir_all(&entry.path)
+    } else {
+        fs::remove_file(&entry.path)
+    };
+    removed.with_context(|| format!("Failed to remove cache entry {:?}", entry.path))
+}
+
+/// Formats a byte count for humans, e.g. `12.3 MiB`.
+pub fn format_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", size)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
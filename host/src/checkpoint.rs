@@ -0,0 +1,130 @@
+// --- Pagination Checkpoints ---
+// Pages are appended to disk as they arrive, so an interrupted Subgraph fetch resumes from the
+// last stored cursor instead of starting over. Checkpoints are only valid for the block the
+// pages were read at, hence one directory per block.
+//
+// Layout: `<cache dir>/checkpoints/<chain>-<token>/<block>/<range>.ndjson` (+ `<range>.done`)
+
+use anyhow::{Context, Result};
+use risc0_steel::alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::subgraph::HolderData;
+
+/// One stored page: the holders it contained and the cursor after it.
+#[derive(Serialize, Deserialize)]
+struct CheckpointPage {
+    last_id: String,
+    holders: Vec<HolderData>,
+}
+
+/// Progress of one id range restored from a checkpoint.
+#[derive(Default)]
+pub struct RangeProgress {
+    pub holders: Vec<HolderData>,
+    pub last_id: Option<String>,
+    pub done: bool,
+}
+
+pub struct FetchCheckpoint {
+    dir: PathBuf,
+}
+
+impl FetchCheckpoint {
+    /// Directory holding the checkpoints of one token, across blocks.
+    pub fn root(cache_dir: &Path, chain: &str, token: Address) -> PathBuf {
+        cache_dir
+            .join("checkpoints")
+            .join(format!("{}-{:#x}", chain.to_lowercase(), token))
+    }
+
+    /// The most recent checkpointed block at or before `max_block`.
+    pub fn latest_block(root: &Path, max_block: u64) -> Result<Option<u64>> {
+        if !root.exists() {
+            return Ok(None);
+        }
+        let mut latest = None;
+        for entry in fs::read_dir(root).with_context(|| format!("Failed to list checkpoints: {:?}", root))? {
+            let block = entry?.file_name().to_str().and_then(|name| name.parse::<u64>().ok());
+            if let Some(block) = block.filter(|&block| block <= max_block) {
+                latest = latest.max(Some(block));
+            }
+        }
+        Ok(latest)
+    }
+
+    pub fn open(root: &Path, block: u64) -> Result<Self> {
+        let dir = root.join(block.to_string());
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create checkpoint directory: {:?}", dir))?;
+        Ok(Self { dir })
+    }
+
+    pub fn load_range(&self, range_key: &str) -> Result<RangeProgress> {
+        let pages_path = self.pages_path(range_key);
+        let mut progress = RangeProgress {
+            done: self.done_path(range_key).exists(),
+            ..Default::default()
+        };
+        if !pages_path.exists() {
+            return Ok(progress);
+        }
+
+        let contents = fs::read_to_string(&pages_path)
+            .with_context(|| format!("Failed to read checkpoint: {:?}", pages_path))?;
+        for line in contents.lines() {
+            // A torn last line from an interrupted write is dropped; that page is refetched.
+            let Ok(page) = serde_json::from_str::<CheckpointPage>(line) else {
+                warn!("Ignoring incomplete page in checkpoint {:?}", pages_path);
+                break;
+            };
+            progress.holders.extend(page.holders);
+            progress.last_id = Some(page.last_id);
+        }
+        if progress.last_id.is_some() {
+            info!(
+                "Resuming range {} from checkpoint with {} holders (last_id='{}')",
+                range_key,
+                progress.holders.len(),
+                progress.last_id.as_deref().unwrap_or("")
+            );
+        }
+        Ok(progress)
+    }
+
+    pub fn append_page(&self, range_key: &str, last_id: &str, holders: &[HolderData]) -> Result<()> {
+        let pages_path = self.pages_path(range_key);
+        let mut line = serde_json::to_string(&CheckpointPage {
+            last_id: last_id.to_string(),
+            holders: holders.to_vec(),
+        })?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&pages_path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .with_context(|| format!("Failed to write checkpoint: {:?}", pages_path))
+    }
+
+    pub fn mark_done(&self, range_key: &str) -> Result<()> {
+        let done_path = self.done_path(range_key);
+        fs::write(&done_path, b"").with_context(|| format!("Failed to write checkpoint: {:?}", done_path))
+    }
+
+    /// Deletes the checkpoints once the fetch has completed.
+    pub fn remove(self) -> Result<()> {
+        fs::remove_dir_all(&self.dir).with_context(|| format!("Failed to remove checkpoint directory: {:?}", self.dir))
+    }
+
+    fn pages_path(&self, range_key: &str) -> PathBuf {
+        self.dir.join(format!("{}.ndjson", range_key))
+    }
+
+    fn done_path(&self, range_key: &str) -> PathBuf {
+        self.dir.join(format!("{}.done", range_key))
+    }
+}
@@ -0,0 +1,111 @@
+// --- Configuration File ---
+// `--config <path>` loads a TOML file whose keys are the long argument names of the commands
+// (`rpc_url` or `rpc-url`). Its values are defaults only: command-line flags and environment
+// variables (including those from a `.env` file) take precedence. A `[chains.<name>]` table holds per-chain defaults (e.g. Subgraph
+// URLs) that apply on top of the top-level keys when `--chain-spec` selects that chain.
+//
+//     chain_spec = "gnosis"
+//     n_top_holders = 10
+//
+//     [chains.gnosis]
+//     rpc_url = "https://rpc.gnosischain.com"
+//     subgraph_url = ["https://primary.example/subgraph", "https://fallback.example/subgraph"]
+
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+use tracing::warn;
+
+use top_n_holders_host::ChainSelection;
+
+/// Applies the `--config` file (if any) as defaults for the invoked subcommand.
+///
+/// Every argument has an environment variable, so the file is applied by setting the variables
+/// that are not already set, before the command line is parsed for real. This keeps clap's own
+/// precedence (flag > environment > default) and lets required arguments come from the file.
+pub fn apply_config_file(command: clap::Command) -> Result<()> {
+    // Lenient first pass, only to find the subcommand, the config path and the chain.
+    let matches = command.clone().ignore_errors(true).get_matches();
+    let Some((name, sub_matches)) = matches.subcommand() else {
+        return Ok(());
+    };
+    let config_path = sub_matches
+        .try_get_one::<PathBuf>("config")
+        .ok()
+        .flatten()
+        .or_else(|| matches.try_get_one::<PathBuf>("config").ok().flatten());
+    let Some(config_path) = config_path else {
+        return Ok(());
+    };
+    let Some(subcommand) = command.find_subcommand(name) else {
+        return Ok(());
+    };
+
+    let mut table = load(config_path)?;
+    let chains = match table.remove("chains") {
+        Some(Value::Table(chains)) => chains,
+        Some(_) => anyhow::bail!("`chains` in {:?} must be a table of per-chain settings", config_path),
+        None => Table::new(),
+    };
+
+    // The chain is selected by flag or environment first, then by the file itself.
+    let chain = sub_matches
+        .try_get_one::<ChainSelection>("chain_spec")
+        .ok()
+        .flatten()
+        .map(ChainSelection::to_string)
+        .or_else(|| table.get("chain_spec").and_then(Value::as_str).map(str::to_string));
+    if let Some(chain) = chain {
+        match chains.get(&chain.to_lowercase()) {
+            Some(Value::Table(chain_table)) => table.extend(chain_table.clone()),
+            Some(_) => anyhow::bail!("`chains.{}` in {:?} must be a table", chain, config_path),
+            None => {}
+        }
+    }
+
+    let known_keys: HashSet<String> = command
+        .get_subcommands()
+        .flat_map(|subcommand| subcommand.get_arguments())
+        .map(|arg| arg.get_id().to_string())
+        .collect();
+    for (key, value) in table {
+        let key = key.replace('-', "_");
+        let Some(arg) = subcommand.get_arguments().find(|arg| arg.get_id() == key.as_str()) else {
+            if !known_keys.contains(&key) {
+                warn!("Ignoring unknown key `{}` in config file {:?}", key, config_path);
+            }
+            continue;
+        };
+        let Some(env_name) = arg.get_env() else {
+            continue;
+        };
+        if std::env::var_os(env_name).is_none() {
+            std::env::set_var(env_name, to_arg_value(&key, &value)?);
+        }
+    }
+    Ok(())
+}
+
+fn load(path: &Path) -> Result<Table> {
+    let contents = fs::read_to_string(path).with_context(|| format!("Failed to read config file: {:?}", path))?;
+    contents.parse().with_context(|| format!("Failed to parse config file: {:?}", path))
+}
+
+/// Renders a TOML value the way the argument would be written on the command line.
+/// Arrays become comma-separated lists (see `value_delimiter`).
+fn to_arg_value(key: &str, value: &Value) -> Result<String> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Integer(number) => Ok(number.to_string()),
+        Value::Float(number) => Ok(number.to_string()),
+        Value::Boolean(flag) => Ok(flag.to_string()),
+        Value::Array(items) => Ok(items
+            .iter()
+            .map(|item| to_arg_value(key, item))
+            .collect::<Result<Vec<_>>>()?
+            .join(",")),
+        _ => anyhow::bail!("Config key `{}` must be a string, number, boolean or list", key),
+    }
+}
@@ -0,0 +1,114 @@
+// --- Daemon Mode ---
+// Re-proves the configured tokens on a schedule: every --interval, or whenever the chain has
+// advanced --every-blocks blocks past the last proven block. Each round writes its artifacts to
+// `<artifacts dir>/<block>/` and, once all tokens are proven, points `<artifacts dir>/latest.json`
+// at that directory, so consumers always find the most recent verified snapshot.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::net::TcpListener;
+use tracing::{error, info};
+
+use top_n_holders_host::metrics;
+use top_n_holders_host::pipeline::{check_results, reject_dry_run, require_n, require_tokens, Pipeline};
+
+use crate::DaemonArgs;
+
+/// Contents of `latest.json`.
+#[derive(Serialize)]
+struct LatestArtifacts {
+    block: u64,
+    dir: PathBuf,
+    updated_at: u64, // Unix timestamp (seconds).
+}
+
+pub async fn run_daemon(args: &DaemonArgs) -> Result<()> {
+    anyhow::ensure!(
+        args.run.fetch.block_number.is_none(),
+        "--block-number cannot be used in daemon mode; use --block-tag to choose which blocks are proven"
+    );
+    require_tokens(&args.run)?;
+    reject_dry_run(&args.run, "daemon")?;
+    require_n(&args.run)?;
+    let mut run_args = args.run.clone();
+    run_args.prover.yes = true; // Unattended: never ask before proving.
+    let pipeline = Pipeline::new(run_args).await?;
+    let root = &args.run.fetch.artifacts.artifacts_dir;
+    let wait = args.interval.unwrap_or(args.poll_interval);
+
+    if let Some(metrics_addr) = args.metrics_addr {
+        let listener = TcpListener::bind(metrics_addr)
+            .await
+            .with_context(|| format!("Failed to bind the metrics server to {}", metrics_addr))?;
+        info!("Serving metrics on http://{}/metrics", metrics_addr);
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(listener).await {
+                error!("{:#}", e);
+            }
+        });
+    }
+
+    // After a restart, carry on from the last round the history recorded instead of proving it again.
+    let mut last_block: Option<u64> = pipeline.last_recorded_block()?;
+    if let Some(last_block) = last_block {
+        info!("Resuming after block {}, the last one in the proof history", last_block);
+    }
+    loop {
+        match round(args, &pipeline, root, last_block).await {
+            Ok(Some(block_number)) => last_block = Some(block_number),
+            Ok(None) => {}
+            // The next round starts over from a fresh block.
+            Err(e) => error!("Daemon round failed: {:#}", e),
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = tokio::signal::ctrl_c() => {
+                info!("Interrupted. Stopping the daemon.");
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Runs one round if it is due. Returns the proven block.
+async fn round(args: &DaemonArgs, pipeline: &Pipeline, root: &Path, last_block: Option<u64>) -> Result<Option<u64>> {
+    let block_number = pipeline.resolve_block(None).await?;
+    if let Some(last_block) = last_block {
+        let due_block = last_block + args.every_blocks.unwrap_or(1);
+        if block_number < due_block {
+            info!("Block {} already proven; next round at block {}.", last_block, due_block);
+            return Ok(None);
+        }
+    }
+
+    info!("Starting daemon round at block {}", block_number);
+    let round_dir = root.join(block_number.to_string());
+    let results = pipeline.prove_tokens(block_number, &round_dir).await;
+    let published = check_results(block_number, &results).and_then(|()| publish_latest(root, block_number, &round_dir));
+    metrics::ROUNDS.with_label_values(&[metrics::outcome(&published)]).inc();
+    published?;
+    Ok(Some(block_number))
+}
+
+/// Points `latest.json` at a finished round. Written to a temporary file first, so readers
+/// never see a partial pointer.
+fn publish_latest(root: &Path, block: u64, dir: &Path) -> Result<()> {
+    let latest = LatestArtifacts {
+        block,
+        dir: dir.to_path_buf(),
+        updated_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_secs())
+            .unwrap_or_default(),
+    };
+    let path = root.join("latest.json");
+    let tmp_path = root.join("latest.json.tmp");
+    fs::write(&tmp_path, serde_json::to_vec_pretty(&latest)?)
+        .with_context(|| format!("Failed to write {:?}", tmp_path))?;
+    fs::rename(&tmp_path, &path).with_context(|| format!("Failed to publish {:?}", path))?;
+    info!("Published artifacts of block {} ({:?})", block, dir);
+    Ok(())
+}
@@ -0,0 +1,120 @@
+// --- Snapshot Diff ---
+// `diff` compares the verified Top-N of two proofs of the same token, typically at different
+// blocks: who entered and who dropped out, how the ranks moved and how the balances changed. Both
+// receipts are verified first, so the rankings at either end are proven; the balances are the
+// preflighted ones from `token.json`, which the guest read at each block.
+
+use anyhow::{Context, Result};
+use risc0_steel::alloy::primitives::{Address, U256};
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+use top_n_holders_core::GuestOutput;
+
+use crate::artifacts::{ArtifactDir, TokenArtifact};
+use crate::stages;
+use crate::summary::format_amount;
+
+/// One side of the diff: a verified proof with its token metadata.
+struct Snapshot {
+    token: Address,
+    block: u64,
+    output: GuestOutput,
+    metadata: TokenArtifact,
+}
+
+impl Snapshot {
+    fn load(artifacts: &ArtifactDir, name: &str) -> Result<Self> {
+        let receipt = artifacts.load_receipt().with_context(|| format!("Failed to load the {} proof", name))?;
+        let (output, _) = stages::verify_receipt(&receipt).with_context(|| format!("The {} receipt does not verify", name))?;
+        if !output.verification_succeeded {
+            anyhow::bail!("The guest rejected the ranking of the {} proof", name);
+        }
+        // The journal does not name the token; the artifacts of the proof do.
+        let holders = artifacts.load_holders()?;
+        let metadata = artifacts
+            .load_token()?
+            .with_context(|| format!("No token.json with the balances of the {} proof; run `preflight` again", name))?;
+        let (block, _) = output.commitment.decode_id();
+        Ok(Self { token: holders.token, block: block.saturating_to(), output, metadata })
+    }
+
+    fn rank(&self, address: &Address) -> Option<usize> {
+        self.output.final_top_n_addresses.iter().position(|holder| holder == address).map(|i| i + 1)
+    }
+
+    fn balance(&self, address: &Address) -> Option<U256> {
+        self.metadata.balances.get(address).copied()
+    }
+}
+
+/// Prints how the Top-N changed from the proof in `from` to the one in `to`.
+pub fn print(from: &ArtifactDir, to: &ArtifactDir) -> Result<()> {
+    let old = Snapshot::load(from, "--from")?;
+    let new = Snapshot::load(to, "--to")?;
+    anyhow::ensure!(old.token == new.token, "The proofs are of different tokens: {} and {}", old.token, new.token);
+    if old.block == new.block {
+        warn!("Both proofs are of block {}", old.block);
+    }
+    if old.output.final_top_n_addresses.len() != new.output.final_top_n_addresses.len() {
+        warn!(
+            "The proofs rank {} and {} holders; holders beyond the shorter ranking count as entrants or dropouts",
+            old.output.final_top_n_addresses.len(),
+            new.output.final_top_n_addresses.len()
+        );
+    }
+    let decimals = new.metadata.decimals.or(old.metadata.decimals);
+    let symbol = new.metadata.symbol.as_deref().or(old.metadata.symbol.as_deref());
+    let amount = |balance: Option<U256>| balance.map_or_else(|| "?".to_string(), |balance| format_amount(balance, decimals));
+    let delta = |old: Option<U256>, new: Option<U256>| match (old, new) {
+        (Some(old), Some(new)) if new >= old => format!("+{}", format_amount(new - old, decimals)),
+        (Some(old), Some(new)) => format!("-{}", format_amount(old - new, decimals)),
+        _ => "?".to_string(),
+    };
+    let rank = |rank: Option<usize>| rank.map_or_else(|| "-".to_string(), |rank| rank.to_string());
+
+    // The new ranking first, then who dropped out of it in their old order.
+    let mut addresses: Vec<Address> = new.output.final_top_n_addresses.clone();
+    let dropouts: Vec<Address> =
+        old.output.final_top_n_addresses.iter().filter(|address| new.rank(address).is_none()).copied().collect();
+    addresses.extend(&dropouts);
+    let moves: HashMap<Address, (Option<usize>, Option<usize>)> =
+        addresses.iter().map(|address| (*address, (old.rank(address), new.rank(address)))).collect();
+
+    println!();
+    println!(
+        "Top holders of {} {} from block {} to block {}",
+        symbol.unwrap_or("token"),
+        old.token,
+        old.block,
+        new.block
+    );
+    println!("{:>4}  {:>4}  {:<9}  {:<42}  {:>28}  {:>28}  {:>28}", "FROM", "TO", "CHANGE", "ADDRESS", "BALANCE BEFORE", "BALANCE AFTER", "DELTA");
+    for address in &addresses {
+        let (before, after) = moves[address];
+        let change = match (before, after) {
+            (None, Some(_)) => "entered".to_string(),
+            (Some(_), None) => "dropped".to_string(),
+            (Some(before), Some(after)) if after < before => format!("up {}", before - after),
+            (Some(before), Some(after)) if after > before => format!("down {}", after - before),
+            _ => "=".to_string(),
+        };
+        let (old_balance, new_balance) = (old.balance(address), new.balance(address));
+        println!(
+            "{:>4}  {:>4}  {:<9}  {:<42}  {:>28}  {:>28}  {:>28}",
+            rank(before),
+            rank(after),
+            change,
+            address.to_string(),
+            amount(old_balance),
+            amount(new_balance),
+            delta(old_balance, new_balance)
+        );
+    }
+    println!();
+
+    let entrants = moves.values().filter(|(before, _)| before.is_none()).count();
+    let moved = moves.values().filter(|(before, after)| before.is_some() && after.is_some() && before != after).count();
+    info!("{} entered, {} dropped out, {} changed rank", entrants, dropouts.len(), moved);
+    Ok(())
+}
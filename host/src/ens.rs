@@ -0,0 +1,116 @@
+// --- ENS Names ---
+// On mainnet, the primary ENS names of the ranked holders, for the reports: `vitalik.eth` is
+// legible where `0xd8dA…6045` is not. The name is the address's reverse record
+// (`<address>.addr.reverse`), read at the proven block. Anyone can point their reverse record at
+// any name, so a name is only kept if it resolves forward to the address again.
+//
+// Informational only: the names are not part of the proof.
+
+use alloy::network::{Network, TransactionBuilder};
+use alloy::providers::Provider;
+use alloy::rpc::types::BlockId;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use anyhow::Result;
+use futures::{stream, StreamExt};
+use risc0_steel::alloy::primitives::{address, keccak256, Address, B256};
+use std::collections::BTreeMap;
+use tracing::{info, warn};
+
+use crate::rate_limit::RateLimiter;
+
+sol! {
+    interface IEnsRegistry {
+        function resolver(bytes32 node) external view returns (address);
+    }
+
+    interface IEnsResolver {
+        function name(bytes32 node) external view returns (string);
+        function addr(bytes32 node) external view returns (address);
+    }
+}
+
+/// The ENS registry, at the same address since the 2020 migration.
+const ENS_REGISTRY: Address = address!("00000000000C2E074eC69A0dFb2997BA6C7d2e1e");
+
+/// The verified primary names of `addresses` at `block_number`, with up to `concurrency`
+/// requests in flight. Addresses without one are left out.
+pub async fn primary_names<N: Network>(
+    provider: &impl Provider<N>,
+    addresses: &[Address],
+    block_number: u64,
+    concurrency: usize,
+    rate_limiter: &RateLimiter,
+) -> BTreeMap<Address, String> {
+    let results: Vec<(Address, Result<Option<String>>)> = stream::iter(addresses)
+        .map(|&address| async move { (address, primary_name(provider, address, block_number, rate_limiter).await) })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+
+    let mut names = BTreeMap::new();
+    for (address, name) in results {
+        match name {
+            Ok(Some(name)) => {
+                names.insert(address, name);
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to look up the ENS name of {}: {:#}", address, e),
+        }
+    }
+    info!("{} of {} ranked holders have an ENS name.", names.len(), addresses.len());
+    names
+}
+
+async fn primary_name<N: Network>(
+    provider: &impl Provider<N>,
+    address: Address,
+    block_number: u64,
+    rate_limiter: &RateLimiter,
+) -> Result<Option<String>> {
+    let reverse_node = namehash(&format!("{:x}.addr.reverse", address));
+    let Some(name) = resolve(provider, reverse_node, block_number, rate_limiter, IEnsResolver::nameCall { node: reverse_node })
+        .await?
+        .filter(|name| !name.is_empty())
+    else {
+        return Ok(None);
+    };
+    let node = namehash(&name);
+    let resolved = resolve(provider, node, block_number, rate_limiter, IEnsResolver::addrCall { node }).await?;
+    Ok((resolved == Some(address)).then_some(name))
+}
+
+/// Calls `call` on the resolver of `node`, or returns None if the node has no resolver.
+async fn resolve<N: Network, C: SolCall>(
+    provider: &impl Provider<N>,
+    node: B256,
+    block_number: u64,
+    rate_limiter: &RateLimiter,
+    call: C,
+) -> Result<Option<C::Return>> {
+    let resolver = eth_call(provider, ENS_REGISTRY, block_number, rate_limiter, IEnsRegistry::resolverCall { node }).await?;
+    if resolver.is_zero() {
+        return Ok(None);
+    }
+    eth_call(provider, resolver, block_number, rate_limiter, call).await.map(Some)
+}
+
+async fn eth_call<N: Network, C: SolCall>(
+    provider: &impl Provider<N>,
+    to: Address,
+    block_number: u64,
+    rate_limiter: &RateLimiter,
+    call: C,
+) -> Result<C::Return> {
+    rate_limiter.wait().await;
+    let tx = N::TransactionRequest::default().with_to(to).with_input(call.abi_encode());
+    let output = provider.call(tx).block(BlockId::number(block_number)).await?;
+    Ok(C::abi_decode_returns(&output)?)
+}
+
+/// The ENS namehash of `name` (EIP-137).
+fn namehash(name: &str) -> B256 {
+    name.rsplit('.')
+        .filter(|label| !label.is_empty())
+        .fold(B256::ZERO, |node, label| keccak256([node.as_slice(), keccak256(label).as_slice()].concat()))
+}
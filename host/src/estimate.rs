@@ -0,0 +1,51 @@
+// --- Cost Estimation ---
+// `estimate` executes the guest on the preflighted input and projects the proving time (and, with
+// an hourly price, the cost) per backend from its cycle count. Proving time grows about linearly
+// with the total cycles, so a throughput per backend is enough for a projection; the defaults are
+// rough figures for current hardware and are meant to be overridden with measured ones. The
+// compression into a succinct or Groth16 receipt adds a fixed time that is not included.
+
+use anyhow::Result;
+use std::time::Duration;
+use tracing::info;
+
+use top_n_holders_host::artifacts::ArtifactDir;
+use top_n_holders_host::stages;
+
+use crate::EstimateArgs;
+
+struct Projection {
+    backend: &'static str,
+    khz: f64,                    // Proving throughput in thousands of cycles per second.
+    usd_per_hour: Option<f64>,   // Price of the machine (CPU, GPU) while it proves.
+    usd_per_mcycle: Option<f64>, // Price per million cycles (Bonsai).
+}
+
+pub fn estimate(args: &EstimateArgs) -> Result<()> {
+    let execution = stages::execute(&ArtifactDir::new(&args.artifacts.artifacts_dir), Default::default())?;
+    info!("Estimate for {} verified holders:", execution.output.final_top_n_addresses.len());
+    info!("  User cycles:  {}", execution.user_cycles);
+    info!("  Total cycles: {}", execution.cycles);
+    info!("  Segments:     {}", execution.segments);
+
+    let projections = [
+        Projection { backend: "local (CPU)", khz: args.cpu_khz, usd_per_hour: args.cpu_usd_per_hour, usd_per_mcycle: None },
+        Projection { backend: "local (GPU)", khz: args.gpu_khz, usd_per_hour: args.gpu_usd_per_hour, usd_per_mcycle: None },
+        Projection { backend: "bonsai", khz: args.bonsai_khz, usd_per_hour: None, usd_per_mcycle: args.bonsai_usd_per_mcycle },
+    ];
+    for projection in &projections {
+        let seconds = execution.cycles as f64 / (projection.khz.max(f64::MIN_POSITIVE) * 1000.0);
+        let cost = projection
+            .usd_per_hour
+            .map(|usd_per_hour| usd_per_hour * seconds / 3600.0)
+            .or(projection.usd_per_mcycle.map(|usd_per_mcycle| usd_per_mcycle * execution.cycles as f64 / 1e6));
+        info!(
+            "  {:<12} ~{} at {} kHz{}",
+            projection.backend,
+            humantime::format_duration(Duration::from_secs(seconds.ceil() as u64)),
+            projection.khz,
+            cost.map(|usd| format!(", ~${:.2}", usd)).unwrap_or_default()
+        );
+    }
+    Ok(())
+}
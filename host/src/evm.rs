@@ -0,0 +1,128 @@
+// --- Steel Environments ---
+// Ethereum-like chains are preflighted in an `EthEvmEnv`, OP Stack chains (feature `op`) in an
+// `OpEvmEnv`. The two are different types; `PreflightEnv` is what the preflight needs from
+// either, so that it is written once.
+
+use alloy::network::Ethereum;
+use alloy::sol_types::SolCall;
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use risc0_steel::alloy::primitives::Address;
+use risc0_steel::ethereum::{EthEvmFactory, EthEvmInput};
+use risc0_steel::host::db::AlloyDb;
+use risc0_steel::host::HostEvmEnv;
+use risc0_steel::{Commitment, Contract, EvmBlockHeader};
+use risc0_zkvm::ExecutorEnvBuilder;
+use serde::Serialize;
+
+use top_n_holders_core::{with_fork_overrides, ChainName, ForkOverride};
+
+use crate::prefetch::PrefetchProvider;
+
+/// The Steel input of a preflight, as stored in `evm_input.json`.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum EvmInput {
+    Eth(EthEvmInput),
+    #[cfg(feature = "op")]
+    Op(risc0_op_steel::OpEvmInput),
+}
+
+impl EvmInput {
+    /// Parses the `evm_input.json` of a preflight on `chain`.
+    pub fn from_json(data: &[u8], chain: ChainName) -> serde_json::Result<Self> {
+        #[cfg(feature = "op")]
+        if top_n_holders_core::op_chain_spec(chain).is_some() {
+            return serde_json::from_slice(data).map(EvmInput::Op);
+        }
+        let _ = chain;
+        serde_json::from_slice(data).map(EvmInput::Eth)
+    }
+
+    /// The commitment of a guest executing the input on `chain` with `fork_overrides`: the block of
+    /// the input and the chain spec the guest builds its environment with.
+    pub fn commitment(&self, chain: ChainName, fork_overrides: &[ForkOverride]) -> Result<Commitment> {
+        match self {
+            EvmInput::Eth(input) => {
+                let spec = top_n_holders_core::chain_spec(chain).with_context(|| format!("No Ethereum chain spec for {}", chain))?;
+                let spec = with_fork_overrides(spec, fork_overrides).map_err(anyhow::Error::msg)?;
+                Ok(input.clone().into_env(&spec).commitment().clone())
+            }
+            #[cfg(feature = "op")]
+            EvmInput::Op(input) => {
+                let spec = top_n_holders_core::op_chain_spec(chain).with_context(|| format!("No OP Stack chain spec for {}", chain))?;
+                Ok(input.clone().into_env(spec).commitment().clone())
+            }
+        }
+    }
+
+    /// Writes the input for the guest, which reads it right after the guest input.
+    pub fn write_to(&self, builder: &mut ExecutorEnvBuilder<'_>) -> Result<()> {
+        match self {
+            EvmInput::Eth(input) => builder.write(input)?,
+            #[cfg(feature = "op")]
+            EvmInput::Op(input) => builder.write(input)?,
+        };
+        Ok(())
+    }
+}
+
+/// A Steel host environment the preflight calls into.
+pub trait PreflightEnv: Send {
+    fn block_number(&self) -> u64;
+
+    /// Preflights `call` on the contract at `address`.
+    fn call<'a, C>(&'a mut self, address: Address, call: C) -> BoxFuture<'a, Result<C::Return>>
+    where
+        C: SolCall + Send + Sync + 'a,
+        C::Return: Send;
+
+    /// The input for the guest, with the state of every preflighted call.
+    fn into_evm_input(self) -> BoxFuture<'static, Result<EvmInput>>;
+}
+
+pub type EthHostEnv = HostEvmEnv<AlloyDb<Ethereum, PrefetchProvider>, EthEvmFactory, ()>;
+
+impl PreflightEnv for EthHostEnv {
+    fn block_number(&self) -> u64 {
+        self.header().number()
+    }
+
+    fn call<'a, C>(&'a mut self, address: Address, call: C) -> BoxFuture<'a, Result<C::Return>>
+    where
+        C: SolCall + Send + Sync + 'a,
+        C::Return: Send,
+    {
+        Box::pin(async move { Contract::preflight(address, self).call_builder(&call).call().await })
+    }
+
+    fn into_evm_input(self) -> BoxFuture<'static, Result<EvmInput>> {
+        Box::pin(async move { Ok(EvmInput::Eth(self.into_input().await?)) })
+    }
+}
+
+#[cfg(feature = "op")]
+pub type OpHostEnv = HostEvmEnv<
+    AlloyDb<op_alloy_network::Optimism, PrefetchProvider<op_alloy_network::Optimism>>,
+    risc0_op_steel::OpEvmFactory,
+    (),
+>;
+
+#[cfg(feature = "op")]
+impl PreflightEnv for OpHostEnv {
+    fn block_number(&self) -> u64 {
+        self.header().number()
+    }
+
+    fn call<'a, C>(&'a mut self, address: Address, call: C) -> BoxFuture<'a, Result<C::Return>>
+    where
+        C: SolCall + Send + Sync + 'a,
+        C::Return: Send,
+    {
+        Box::pin(async move { Contract::preflight(address, self).call_builder(&call).call().await })
+    }
+
+    fn into_evm_input(self) -> BoxFuture<'static, Result<EvmInput>> {
+        Box::pin(async move { Ok(EvmInput::Op(self.into_input().await?)) })
+    }
+}
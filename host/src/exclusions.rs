@@ -0,0 +1,76 @@
+// --- Excluded Addresses ---
+// Addresses that are left out of the ranking (burn addresses, bridges, known contracts).
+// The same list is handed to the guest, which proves the ranking over the remaining supply.
+//
+// File format: one address per line; anything after `#` or `,` is ignored, so comments and
+// `address,label` CSV exports both work.
+//
+// --exclude-categories adds the addresses of the given categories in the --labels-file, e.g.
+// `cex,bridge` for the Top-N excluding exchanges and bridges. The guest commits a hash of the
+// excluded set (see `top_n_holders_core::address_set_hash`), so a claim can be checked against
+// a published list without reading the journal's addresses one by one.
+//
+// --sanctions-file (e.g. an export of the OFAC SDN list's Ethereum addresses, in the same format)
+// works the other way round: the listed addresses stay in the supply, and the guest proves that
+// none of them is among the ranked holders, committing the list's hash.
+
+use anyhow::{Context, Result};
+use risc0_steel::alloy::primitives::Address;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+use crate::args::SelectionArgs;
+use crate::labels::AddressLabel;
+
+/// The addresses to leave out of the ranking: the --exclude-file and the labeled addresses of
+/// the --exclude-categories, sorted ascending and deduplicated as the guest expects.
+pub fn excluded_addresses(args: &SelectionArgs, labels: &BTreeMap<Address, AddressLabel>) -> Result<Vec<Address>> {
+    let mut addresses = match &args.exclude_file {
+        Some(exclude_file) => load_address_list(exclude_file)?,
+        None => Vec::new(),
+    };
+    for category in &args.exclude_categories {
+        let category = category.to_lowercase();
+        let members: Vec<Address> = labels
+            .iter()
+            .filter(|(_, label)| label.category.as_deref() == Some(category.as_str()))
+            .map(|(address, _)| *address)
+            .collect();
+        anyhow::ensure!(!members.is_empty(), "No address in the --labels-file has the category `{}`", category);
+        info!("Excluding the {} addresses labeled `{}`", members.len(), category);
+        addresses.extend(members);
+    }
+    addresses.sort();
+    addresses.dedup();
+    Ok(addresses)
+}
+
+/// The sanctions list of --sanctions-file, sorted ascending and deduplicated as the guest expects.
+/// None of its addresses may be among the ranked holders; the guest commits its hash.
+pub fn sanctioned_addresses(args: &SelectionArgs) -> Result<Vec<Address>> {
+    match &args.sanctions_file {
+        Some(sanctions_file) => load_address_list(sanctions_file),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Loads a file of addresses in the format above, sorted ascending and deduplicated.
+fn load_address_list(path: &Path) -> Result<Vec<Address>> {
+    let contents = fs::read_to_string(path).with_context(|| format!("Failed to read address file: {:?}", path))?;
+    let mut addresses = Vec::new();
+    for (line_number, line) in contents.lines().enumerate() {
+        let entry = line.split(['#', ',']).next().unwrap_or_default().trim();
+        if entry.is_empty() || entry.eq_ignore_ascii_case("address") {
+            continue;
+        }
+        let address = Address::from_str(entry)
+            .with_context(|| format!("Invalid address on line {} of {:?}: {}", line_number + 1, path, entry))?;
+        addresses.push(address);
+    }
+    addresses.sort();
+    addresses.dedup();
+    Ok(addresses)
+}
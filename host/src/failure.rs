@@ -0,0 +1,38 @@
+// --- Failure Kinds ---
+// Errors are tagged (as context) with the kind of failure, so that the binary can exit with a
+// stable code per kind and orchestrators can tell a bad data source from a failed proof.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// The guest rejects the input: the ranking does not hold at the proven block.
+    GuestVerification,
+    /// The RPC endpoint(s) or the holder source failed, or returned unusable data.
+    DataSource,
+    /// Executing, proving or verifying the guest failed.
+    Prover,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::GuestVerification => write!(f, "guest verification failed"),
+            Failure::DataSource => write!(f, "data source error"),
+            Failure::Prover => write!(f, "prover error"),
+        }
+    }
+}
+
+/// Tags `err` with `kind`, unless it is tagged already (the innermost tag is the most specific).
+pub fn tag(err: anyhow::Error, kind: Failure) -> anyhow::Error {
+    if failure_kind(&err).is_some() {
+        err
+    } else {
+        err.context(kind)
+    }
+}
+
+pub fn failure_kind(err: &anyhow::Error) -> Option<Failure> {
+    err.downcast_ref::<Failure>().copied()
+}
@@ -0,0 +1,130 @@
+// --- Foundry Fixtures ---
+// `gen-fixtures` turns a stored receipt into test vectors for the contracts consuming the proofs:
+// `<name>.sol`, a library of constants (image ID, journal, seal, ...) to import into Foundry tests,
+// and `<name>.json` with the same values for `vm.readFile` + `vm.parseJson`. A test can then call
+// `verifier.verify(SEAL, IMAGE_ID, JOURNAL_DIGEST)` and its own `updateTopN(JOURNAL, SEAL)` against
+// real vectors instead of hand-made ones.
+//
+// The seal of a Groth16 receipt verifies with the deployed `RiscZeroVerifierRouter`. A receipt
+// proven in dev mode (RISC0_DEV_MODE=1) gives the seal of `RiscZeroMockVerifier` (selector
+// 0xFFFFFFFF), which is enough for most tests and takes seconds to make. Other receipt kinds have
+// no on-chain seal.
+
+use anyhow::{Context, Result};
+use risc0_steel::alloy::primitives::{Address, B256, U256};
+use risc0_zkvm::sha::{Digest, Digestible};
+use risc0_zkvm::Receipt;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use crate::stages;
+
+/// The `<name>.json` fixture. The fields are in alphabetical order, so that
+/// `abi.decode(vm.parseJson(json), (Fixture))` fills a Solidity struct declaring them in that order.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Fixture {
+    pub block_hash: B256,
+    pub block_number: u64,
+    pub commitment_config_id: B256,
+    pub commitment_id: U256,
+    pub image_id: B256,
+    pub journal: String,
+    pub journal_digest: B256,
+    pub seal: String,
+    pub top_n: Vec<Address>,
+    pub verification_succeeded: bool,
+}
+
+impl Fixture {
+    /// The fixture of `receipt`, after verifying it.
+    pub fn new(receipt: &Receipt) -> Result<Self> {
+        let (output, image_id) = stages::verify_receipt(receipt)?;
+        let seal = risc0_ethereum_contracts::encode_seal(receipt).context(
+            "Failed to encode the seal. Fixtures need a receipt proven with --receipt-kind groth16, or in dev mode",
+        )?;
+        let (block_number, _) = output.commitment.decode_id();
+        Ok(Self {
+            block_hash: output.commitment.digest,
+            block_number,
+            commitment_config_id: output.commitment.configID,
+            commitment_id: output.commitment.id,
+            image_id: digest_bytes(image_id),
+            journal: format!("0x{}", hex::encode(&receipt.journal.bytes)),
+            journal_digest: digest_bytes(receipt.journal.digest()),
+            seal: format!("0x{}", hex::encode(seal)),
+            top_n: output.final_top_n_addresses,
+            verification_succeeded: output.verification_succeeded,
+        })
+    }
+
+    /// Writes `<name>.sol` and `<name>.json` to `dir`. `name` is also the name of the library.
+    pub fn write(&self, dir: &Path, name: &str) -> Result<()> {
+        anyhow::ensure!(
+            name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+            "`{}` is not a Solidity identifier",
+            name
+        );
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+        let sol_path = dir.join(format!("{}.sol", name));
+        fs::write(&sol_path, self.solidity(name)).with_context(|| format!("Failed to write {:?}", sol_path))?;
+        let json_path = dir.join(format!("{}.json", name));
+        fs::write(&json_path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("Failed to write {:?}", json_path))?;
+        info!("Wrote the fixtures of the proof at block {} to {:?} and {:?}", self.block_number, sol_path, json_path);
+        Ok(())
+    }
+
+    /// The library of constants. Address literals are checksummed, as solc requires.
+    fn solidity(&self, name: &str) -> String {
+        let holders: String = self
+            .top_n
+            .iter()
+            .enumerate()
+            .map(|(i, address)| format!("        holders[{}] = {};\n", i, address))
+            .collect();
+        format!(
+            r#"// SPDX-License-Identifier: UNLICENSED
+// Generated by `top-n-holders-host gen-fixtures`. Do not edit.
+pragma solidity ^0.8.20;
+
+/// @notice A verified Top-{n} proof at block {block_number}.
+library {name} {{
+    bytes32 internal constant IMAGE_ID = {image_id};
+    bytes internal constant JOURNAL = hex"{journal}";
+    bytes32 internal constant JOURNAL_DIGEST = {journal_digest};
+    bytes internal constant SEAL = hex"{seal}";
+    bool internal constant VERIFICATION_SUCCEEDED = {verification_succeeded};
+    uint256 internal constant BLOCK_NUMBER = {block_number};
+    bytes32 internal constant BLOCK_HASH = {block_hash};
+    uint256 internal constant COMMITMENT_ID = {commitment_id:#x};
+    bytes32 internal constant COMMITMENT_CONFIG_ID = {commitment_config_id};
+
+    /// @notice The Top-N addresses of the journal, in rank order.
+    function topN() internal pure returns (address[] memory holders) {{
+        holders = new address[]({n});
+{holders}    }}
+}}
+"#,
+            n = self.top_n.len(),
+            block_number = self.block_number,
+            name = name,
+            image_id = self.image_id,
+            journal = &self.journal[2..],
+            journal_digest = self.journal_digest,
+            seal = &self.seal[2..],
+            verification_succeeded = self.verification_succeeded,
+            block_hash = self.block_hash,
+            commitment_id = self.commitment_id,
+            commitment_config_id = self.commitment_config_id,
+            holders = holders,
+        )
+    }
+}
+
+fn digest_bytes(digest: Digest) -> B256 {
+    B256::from_slice(digest.as_bytes())
+}
@@ -0,0 +1,236 @@
+// --- Proof History ---
+// SQLite database with one row per verified proof (--history-db): chain, token, block, N, the
+// journal and the proven Top-N set, with the preflighted balances where `token.json` has them.
+// The same proof is only recorded once, so reruns and daemon restarts don't duplicate rows, and
+// the daemon resumes after the last block it recorded. `history list` and `history show` query
+// it; for longitudinal analysis, query the `proofs` and `holders` tables directly.
+
+use anyhow::{Context, Result};
+use risc0_steel::alloy::primitives::{Address, U256};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+use top_n_holders_core::ChainName;
+
+use crate::artifacts::{ArtifactDir, TokenArtifact};
+use crate::stages::VerifiedProof;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS proofs (
+        id             INTEGER PRIMARY KEY AUTOINCREMENT,
+        chain          TEXT    NOT NULL,
+        token          TEXT    NOT NULL,
+        block          INTEGER NOT NULL,
+        n              INTEGER NOT NULL,
+        verified       INTEGER NOT NULL, -- Whether the guest verified the ranking.
+        image_id       TEXT    NOT NULL,
+        journal_digest TEXT    NOT NULL,
+        journal        BLOB    NOT NULL,
+        artifacts_dir  TEXT    NOT NULL,
+        recorded_at    INTEGER NOT NULL, -- Unix timestamp (seconds).
+        UNIQUE (chain, token, block, n, journal_digest)
+    );
+    CREATE TABLE IF NOT EXISTS holders (
+        proof_id INTEGER NOT NULL REFERENCES proofs (id),
+        rank     INTEGER NOT NULL,
+        address  TEXT    NOT NULL,
+        balance  TEXT,             -- Decimal, in base units; NULL without token.json.
+        PRIMARY KEY (proof_id, rank)
+    );
+    CREATE INDEX IF NOT EXISTS proofs_by_token ON proofs (chain, token, block);
+    CREATE INDEX IF NOT EXISTS holders_by_address ON holders (address);
+";
+
+/// A recorded proof, without its holders.
+#[derive(Debug, Clone)]
+pub struct ProofRecord {
+    pub id: i64,
+    pub chain: String,
+    pub token: Address,
+    pub block: u64,
+    pub n: usize,
+    pub verified: bool,
+    pub image_id: String,
+    pub journal_digest: String,
+    pub journal: Vec<u8>,
+    pub artifacts_dir: PathBuf,
+    pub recorded_at: u64,
+}
+
+/// A holder of a recorded Top-N.
+#[derive(Debug, Clone)]
+pub struct HolderRecord {
+    pub rank: usize,
+    pub address: Address,
+    pub balance: Option<U256>,
+}
+
+pub struct History {
+    conn: Connection,
+}
+
+impl History {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create history directory: {:?}", parent))?;
+        }
+        let conn = Connection::open(path).with_context(|| format!("Failed to open proof history: {:?}", path))?;
+        conn.execute_batch(SCHEMA).context("Failed to initialize proof history schema")?;
+        Ok(Self { conn })
+    }
+
+    /// Records the verified `proof` of `token`, whose receipt and `token.json` are in `artifacts`.
+    /// Returns the id of the proof, which is the existing one if it was recorded before.
+    pub fn record(&mut self, chain: ChainName, token: Address, artifacts: &ArtifactDir, proof: &VerifiedProof) -> Result<i64> {
+        let output = &proof.output;
+        let (block, _) = output.commitment.decode_id();
+        let block: u64 = block.saturating_to();
+        let n = output.final_top_n_addresses.len();
+        let journal_digest = format!("0x{}", proof.journal_digest);
+        let key = params![chain.name(), address_key(token), block as i64, n as i64, journal_digest];
+        let existing: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT id FROM proofs WHERE chain = ?1 AND token = ?2 AND block = ?3 AND n = ?4 AND journal_digest = ?5",
+                key,
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(id) = existing {
+            info!("Proof of block {} is already in the history as #{}", block, id);
+            return Ok(id);
+        }
+
+        let receipt = artifacts.load_receipt()?;
+        let metadata: Option<TokenArtifact> = artifacts.load_token()?;
+        let artifacts_dir = std::fs::canonicalize(artifacts.dir()).unwrap_or_else(|_| artifacts.dir().to_path_buf());
+        let recorded_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_secs())
+            .unwrap_or_default();
+
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO proofs (chain, token, block, n, verified, image_id, journal_digest, journal, artifacts_dir, recorded_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                chain.name(),
+                address_key(token),
+                block as i64,
+                n as i64,
+                output.verification_succeeded,
+                proof.image_id.to_string(),
+                journal_digest,
+                receipt.journal.bytes,
+                artifacts_dir.to_string_lossy(),
+                recorded_at as i64
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+        {
+            let mut insert = tx.prepare("INSERT INTO holders (proof_id, rank, address, balance) VALUES (?1, ?2, ?3, ?4)")?;
+            for (i, address) in output.final_top_n_addresses.iter().enumerate() {
+                let balance = metadata.as_ref().and_then(|metadata| metadata.balances.get(address));
+                insert.execute(params![id, (i + 1) as i64, address_key(*address), balance.map(U256::to_string)])?;
+            }
+        }
+        tx.commit().context("Failed to record the proof in the history")?;
+        info!("Recorded the proof of block {} in the history as #{}", block, id);
+        Ok(id)
+    }
+
+    /// The recorded proofs, newest block first, optionally of one chain and token only.
+    pub fn list(&self, chain: Option<ChainName>, token: Option<Address>, limit: usize) -> Result<Vec<ProofRecord>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, chain, token, block, n, verified, image_id, journal_digest, journal, artifacts_dir, recorded_at
+             FROM proofs
+             WHERE (?1 IS NULL OR chain = ?1) AND (?2 IS NULL OR token = ?2)
+             ORDER BY block DESC, id DESC
+             LIMIT ?3",
+        )?;
+        let rows = statement.query_map(
+            params![chain.map(ChainName::name), token.map(address_key), limit.min(i64::MAX as usize) as i64],
+            read_proof,
+        )?;
+        rows.collect::<rusqlite::Result<_>>().context("Failed to read the proof history")
+    }
+
+    /// The recorded proof with `id`, if there is one.
+    pub fn proof(&self, id: i64) -> Result<Option<ProofRecord>> {
+        self.conn
+            .query_row(
+                "SELECT id, chain, token, block, n, verified, image_id, journal_digest, journal, artifacts_dir, recorded_at
+                 FROM proofs WHERE id = ?1",
+                params![id],
+                read_proof,
+            )
+            .optional()
+            .context("Failed to read the proof history")
+    }
+
+    /// The Top-N of the recorded proof with `id`, by rank.
+    pub fn holders(&self, id: i64) -> Result<Vec<HolderRecord>> {
+        let mut statement =
+            self.conn.prepare("SELECT rank, address, balance FROM holders WHERE proof_id = ?1 ORDER BY rank")?;
+        let rows = statement.query_map(params![id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?))
+        })?;
+        let mut holders = Vec::new();
+        for row in rows {
+            let (rank, address, balance) = row?;
+            holders.push(HolderRecord {
+                rank: rank as usize,
+                address: Address::from_str(&address)
+                    .with_context(|| format!("Invalid holder address in history: {}", address))?,
+                balance: balance
+                    .map(|balance| U256::from_str_radix(&balance, 10))
+                    .transpose()
+                    .with_context(|| format!("Invalid balance of {} in history", address))?,
+            });
+        }
+        Ok(holders)
+    }
+
+    /// The last block for which a verified Top-N of every one of `tokens` is recorded.
+    pub fn last_block(&self, chain: ChainName, tokens: &[Address], n: usize) -> Result<Option<u64>> {
+        let mut last_block: Option<u64> = None;
+        for &token in tokens {
+            let block: Option<i64> = self.conn.query_row(
+                "SELECT MAX(block) FROM proofs WHERE chain = ?1 AND token = ?2 AND n = ?3 AND verified",
+                params![chain.name(), address_key(token), n as i64],
+                |row| row.get(0),
+            )?;
+            let Some(block) = block else {
+                return Ok(None);
+            };
+            last_block = Some(last_block.map_or(block as u64, |last_block| last_block.min(block as u64)));
+        }
+        Ok(last_block)
+    }
+}
+
+fn read_proof(row: &rusqlite::Row<'_>) -> rusqlite::Result<ProofRecord> {
+    let token: String = row.get(2)?;
+    Ok(ProofRecord {
+        id: row.get(0)?,
+        chain: row.get(1)?,
+        token: Address::from_str(&token)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?,
+        block: row.get::<_, i64>(3)? as u64,
+        n: row.get::<_, i64>(4)? as usize,
+        verified: row.get(5)?,
+        image_id: row.get(6)?,
+        journal_digest: row.get(7)?,
+        journal: row.get(8)?,
+        artifacts_dir: PathBuf::from(row.get::<_, String>(9)?),
+        recorded_at: row.get::<_, i64>(10)? as u64,
+    })
+}
+
+fn address_key(address: Address) -> String {
+    format!("{:#x}", address)
+}
@@ -0,0 +1,224 @@
+// --- Local Holder Index ---
+// SQLite database of holder balances per (chain, token), kept up to date from `Transfer` logs.
+// The index is seeded once from the regular holder source at some block and afterwards only
+// the logs between the last synced block and the proving block are applied, so repeated runs
+// don't have to refetch the whole holder universe.
+
+use alloy::providers::Provider;
+use alloy::rpc::types::Filter;
+use alloy::sol_types::SolEvent;
+use anyhow::{Context, Result};
+use risc0_steel::alloy::primitives::{Address, U256};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+use crate::subgraph::HolderData;
+use crate::IERC20;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS sync_state (
+        chain_id   INTEGER NOT NULL,
+        token      TEXT    NOT NULL,
+        last_block INTEGER NOT NULL,
+        PRIMARY KEY (chain_id, token)
+    );
+    CREATE TABLE IF NOT EXISTS balances (
+        chain_id INTEGER NOT NULL,
+        token    TEXT    NOT NULL,
+        holder   TEXT    NOT NULL,
+        balance  TEXT    NOT NULL, -- 32-byte big-endian hex, so that text order is numeric order
+        PRIMARY KEY (chain_id, token, holder)
+    );
+    CREATE INDEX IF NOT EXISTS balances_by_balance ON balances (chain_id, token, balance);
+";
+
+pub struct HolderIndex {
+    conn: Connection,
+}
+
+impl HolderIndex {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create holder index directory: {:?}", parent))?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open holder index: {:?}", path))?;
+        conn.execute_batch(SCHEMA)
+            .context("Failed to initialize holder index schema")?;
+        Ok(Self { conn })
+    }
+
+    /// The block up to which (inclusive) the index reflects the token balances, if seeded.
+    pub fn synced_block(&self, chain_id: u64, token: Address) -> Result<Option<u64>> {
+        let last_block: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT last_block FROM sync_state WHERE chain_id = ?1 AND token = ?2",
+                params![chain_id as i64, token_key(token)],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("Failed to read holder index sync state")?;
+        Ok(last_block.map(|block| block as u64))
+    }
+
+    /// Replaces the indexed balances of `token` with `holders`, valid at `block`.
+    pub fn seed(&mut self, chain_id: u64, token: Address, block: u64, holders: &[HolderData]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "DELETE FROM balances WHERE chain_id = ?1 AND token = ?2",
+            params![chain_id as i64, token_key(token)],
+        )?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT OR REPLACE INTO balances (chain_id, token, holder, balance) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for holder in holders.iter().filter(|holder| !holder.balance.is_zero()) {
+                insert.execute(params![
+                    chain_id as i64,
+                    token_key(token),
+                    token_key(holder.address),
+                    balance_key(holder.balance)
+                ])?;
+            }
+        }
+        set_synced_block(&tx, chain_id, token, block)?;
+        tx.commit().context("Failed to seed holder index")?;
+        info!("Seeded holder index with {} holders at block {}", holders.len(), block);
+        Ok(())
+    }
+
+    /// Applies all `Transfer` logs after the synced block up to `to_block` (inclusive), in
+    /// chunks of `chunk_size` blocks. Each chunk is committed together with the new sync
+    /// position, so an interrupted sync resumes where it stopped.
+    pub async fn sync_transfers<P: Provider>(
+        &mut self,
+        provider: &P,
+        chain_id: u64,
+        token: Address,
+        to_block: u64,
+        chunk_size: u64,
+    ) -> Result<()> {
+        let synced_block = self
+            .synced_block(chain_id, token)?
+            .context("Holder index has not been seeded for this token")?;
+        anyhow::ensure!(
+            synced_block <= to_block,
+            "Holder index is synced to block {}, past the requested block {}",
+            synced_block,
+            to_block
+        );
+
+        let mut from_block = synced_block + 1;
+        let mut applied = 0usize;
+        while from_block <= to_block {
+            let chunk_end = to_block.min(from_block + chunk_size.max(1) - 1);
+            let filter = Filter::new()
+                .address(token)
+                .event_signature(IERC20::Transfer::SIGNATURE_HASH)
+                .from_block(from_block)
+                .to_block(chunk_end);
+            let logs = provider
+                .get_logs(&filter)
+                .await
+                .with_context(|| format!("Failed to fetch Transfer logs for blocks {}..={}", from_block, chunk_end))?;
+
+            // Net the chunk's transfers per account before touching the database.
+            let mut deltas: HashMap<Address, (U256, U256)> = HashMap::new(); // (received, sent)
+            for log in &logs {
+                let transfer = log.log_decode::<IERC20::Transfer>()
+                    .context("Failed to decode Transfer log")?
+                    .inner
+                    .data;
+                // The zero address stands for mints and burns; it is not a holder.
+                if transfer.from != Address::ZERO {
+                    deltas.entry(transfer.from).or_default().1 += transfer.value;
+                }
+                if transfer.to != Address::ZERO {
+                    deltas.entry(transfer.to).or_default().0 += transfer.value;
+                }
+            }
+
+            let tx = self.conn.transaction()?;
+            for (holder, (received, sent)) in deltas {
+                let current: Option<String> = tx
+                    .query_row(
+                        "SELECT balance FROM balances WHERE chain_id = ?1 AND token = ?2 AND holder = ?3",
+                        params![chain_id as i64, token_key(token), token_key(holder)],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                let current = current.map(|balance| parse_balance_key(&balance)).transpose()?.unwrap_or_default();
+                let updated = (current + received).checked_sub(sent).with_context(|| format!(
+                    "Holder index is inconsistent: balance of {} would become negative at blocks {}..={}. Delete the index to reseed it.",
+                    holder, from_block, chunk_end
+                ))?;
+                if updated.is_zero() {
+                    tx.execute(
+                        "DELETE FROM balances WHERE chain_id = ?1 AND token = ?2 AND holder = ?3",
+                        params![chain_id as i64, token_key(token), token_key(holder)],
+                    )?;
+                } else {
+                    tx.execute(
+                        "INSERT OR REPLACE INTO balances (chain_id, token, holder, balance) VALUES (?1, ?2, ?3, ?4)",
+                        params![chain_id as i64, token_key(token), token_key(holder), balance_key(updated)],
+                    )?;
+                }
+            }
+            set_synced_block(&tx, chain_id, token, chunk_end)?;
+            tx.commit().context("Failed to commit holder index update")?;
+
+            applied += logs.len();
+            info!("Holder index synced to block {} ({} Transfer logs in chunk)", chunk_end, logs.len());
+            from_block = chunk_end + 1;
+        }
+
+        info!("Holder index up to date at block {} ({} Transfer logs applied)", to_block, applied);
+        Ok(())
+    }
+
+    /// All indexed holders of `token` with a non-zero balance, by descending balance.
+    pub fn holders(&self, chain_id: u64, token: Address) -> Result<Vec<HolderData>> {
+        let mut statement = self.conn.prepare(
+            "SELECT holder, balance FROM balances WHERE chain_id = ?1 AND token = ?2 ORDER BY balance DESC, holder ASC",
+        )?;
+        let rows = statement.query_map(params![chain_id as i64, token_key(token)], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut holders = Vec::new();
+        for row in rows {
+            let (holder, balance) = row?;
+            holders.push(HolderData {
+                address: Address::from_str(&holder)
+                    .with_context(|| format!("Invalid holder address in index: {}", holder))?,
+                balance: parse_balance_key(&balance)?,
+            });
+        }
+        Ok(holders)
+    }
+}
+
+fn set_synced_block(conn: &Connection, chain_id: u64, token: Address, block: u64) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO sync_state (chain_id, token, last_block) VALUES (?1, ?2, ?3)",
+        params![chain_id as i64, token_key(token), block as i64],
+    )?;
+    Ok(())
+}
+
+fn token_key(address: Address) -> String {
+    format!("{:#x}", address)
+}
+
+fn balance_key(balance: U256) -> String {
+    hex::encode(balance.to_be_bytes::<32>())
+}
+
+fn parse_balance_key(key: &str) -> Result<U256> {
+    U256::from_str_radix(key, 16).with_context(|| format!("Invalid balance in index: {}", key))
+}
@@ -0,0 +1,94 @@
+// --- IPFS Publication ---
+// `--ipfs-api` pins a bundle of every verified proof to IPFS through the HTTP API of a Kubo node
+// (or a pinning service speaking it): the receipt, the raw journal and the JSON report, wrapped
+// in a directory. The directory's CID is printed, so on-chain consumers can reference a
+// content-addressed copy of the full proof next to the journal they accept.
+//
+// Best effort, like the webhook: a failed upload is logged and does not fail the proof.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::info;
+use url::Url;
+
+use crate::artifacts::ArtifactDir;
+use crate::report::Report;
+
+/// One line of the `add` response.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct AddedEntry {
+    name: String,
+    hash: String,
+}
+
+pub struct Ipfs {
+    http: reqwest::Client,
+    api: Url,
+    credentials: Option<(String, Option<String>)>, // Basic auth from the URL, for pinning services.
+}
+
+impl Ipfs {
+    pub fn new(mut api: Url) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(300))
+            .build()
+            .context("Failed to build IPFS HTTP client")?;
+        let credentials = (!api.username().is_empty())
+            .then(|| (api.username().to_string(), api.password().map(str::to_string)));
+        // Cannot fail for http(s) URLs, the only ones with a username.
+        let _ = api.set_username("");
+        let _ = api.set_password(None);
+        Ok(Self { http, api, credentials })
+    }
+
+    /// Pins the bundle of the verified proof in `artifacts`, whose report is `report`. Returns the
+    /// CID of the directory holding `receipt.json`, `journal.bin` and `report.json`.
+    pub async fn pin_proof(&self, artifacts: &ArtifactDir, report: &Report) -> Result<String> {
+        self.add_directory(&report.bundle(artifacts)?).await
+    }
+
+    /// Adds `files` in a directory of their own, pinned. Returns the directory's CID.
+    async fn add_directory(&self, files: &[(&str, Vec<u8>)]) -> Result<String> {
+        let boundary = format!("top-n-holders-{:016x}", rand::random::<u64>());
+        let mut body = Vec::new();
+        for (name, contents) in files {
+            body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+            body.extend_from_slice(
+                format!("Content-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n", name).as_bytes(),
+            );
+            body.extend_from_slice(b"Content-Type: application/octet-stream\r\n\r\n");
+            body.extend_from_slice(contents);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+        let url = self.api.join("api/v0/add?pin=true&wrap-with-directory=true&cid-version=1")?;
+        let mut request = self
+            .http
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, format!("multipart/form-data; boundary={}", boundary))
+            .body(body);
+        if let Some((username, password)) = &self.credentials {
+            request = request.basic_auth(username, password.as_ref());
+        }
+        let response = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to add the proof bundle to IPFS via {}", self.api))?;
+        let text = response.text().await?;
+
+        // One JSON object per added file; the wrapping directory comes last, without a name.
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let entry: AddedEntry =
+                serde_json::from_str(line).with_context(|| format!("Unexpected IPFS add response: {}", line))?;
+            if entry.name.is_empty() {
+                info!("Pinned the proof bundle to IPFS as {}", entry.hash);
+                return Ok(entry.hash);
+            }
+        }
+        anyhow::bail!("The IPFS add response has no directory entry: {}", text)
+    }
+}
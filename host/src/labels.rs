@@ -0,0 +1,67 @@
+// --- Address Labels ---
+// Operators' own names for known addresses ("Binance 14", "Uniswap V3 pool"), shown next to the
+// ranked holders in the reports. The labels of the ranked holders are kept in `token.json`. An
+// optional category (e.g. `cex`, `bridge`) lets --exclude-categories leave whole groups of
+// addresses out of the ranking, see `exclusions.rs`.
+//
+// File format: `address,label[,category]` per line, as exported by most labeling datasets; a
+// header line and lines starting with `#` are skipped. Fields may be quoted.
+
+use anyhow::{Context, Result};
+use risc0_steel::alloy::primitives::Address;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressLabel {
+    pub label: String,
+    pub category: Option<String>, // Lowercase.
+}
+
+/// Loads a labels file. A later line for the same address replaces an earlier one.
+pub fn load_labels(path: &Path) -> Result<BTreeMap<Address, AddressLabel>> {
+    let contents = fs::read_to_string(path).with_context(|| format!("Failed to read labels file: {:?}", path))?;
+    let mut labels = BTreeMap::new();
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields = split_fields(line);
+        let entry = fields.first().map(String::as_str).unwrap_or_default();
+        if line_number == 0 && entry.eq_ignore_ascii_case("address") {
+            continue;
+        }
+        let address = Address::from_str(entry)
+            .with_context(|| format!("Invalid address on line {} of {:?}: {}", line_number + 1, path, entry))?;
+        let label = fields.get(1).cloned().unwrap_or_default();
+        let category = fields.get(2).map(|category| category.to_lowercase()).filter(|category| !category.is_empty());
+        if !label.is_empty() || category.is_some() {
+            labels.insert(address, AddressLabel { label, category });
+        }
+    }
+    Ok(labels)
+}
+
+/// The comma-separated fields of a CSV line, trimmed and unquoted (`""` in a quoted field is `"`).
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted || field.trim().is_empty() => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            c => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
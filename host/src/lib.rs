@@ -0,0 +1,51 @@
+//! Proves the Top-N holders of ERC20 tokens with RISC Zero and Steel.
+//!
+//! The `top-n-holders-host` binary is a thin command-line wrapper around this library. Services
+//! can embed the pipeline directly: build a [`Pipeline`] from [`RunArgs`] and prove
+//! [`ProofRequest`]s, each returning a verified [`ProofResult`].
+
+
+pub mod accounts;
+pub mod args;
+pub mod artifacts;
+pub mod backend;
+pub mod cache;
+mod checkpoint;
+pub mod diff;
+pub mod evm;
+mod ens;
+mod exclusions;
+pub mod failure;
+pub mod fixtures;
+pub mod history;
+mod index;
+mod ipfs;
+mod labels;
+mod mapping;
+pub mod merkle;
+pub mod metrics;
+pub mod pipeline;
+mod prefetch;
+pub mod progress;
+mod rate_limit;
+mod registry;
+pub mod report;
+mod retry;
+mod selection;
+mod snapshot;
+pub mod stages;
+mod store;
+mod subgraph;
+mod summary;
+mod webhook;
+
+pub use args::{
+    ArtifactArgs, BlockTag, ChainSelection, FetchArgs, HashFn, HolderSource, ProverArgs, ProverKind, ReceiptKind, RegistryArgs,
+    ReportFormat, RunArgs, SelectionArgs, SubmitArgs,
+};
+pub use backend::ProverBackend;
+pub use pipeline::{Pipeline, ProofRequest, ProofResult};
+pub use subgraph::HolderData;
+
+// The contract interfaces are shared with the guest, see `top_n_holders_core`.
+pub use top_n_holders_core::{IERC20, IMulticall3};
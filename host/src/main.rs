@@ -1,508 +1,588 @@
 // --- Existing Imports ---
 use anyhow::{Context, Result};
-use risc0_zkvm::{default_prover, ExecutorEnv};
-use serde::{Deserialize, Serialize};
-use std::str::FromStr; // For parsing Address with clap
-use std::fs; // For file system operations (cache)
-use std::path::Path;
-
-// For path manipulation (cache)
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 // --- Clap Imports ---
-use clap::Parser;
-
-// --- Alloy Imports ---
-use alloy::sol;
-use alloy::sol_types::SolCall;
-use alloy_primitives::address;
-// Needed for call struct SIGNATURE if logging
-
-// --- Risc0 Steel Imports ---
-use risc0_steel::{
-    alloy::primitives::{Address, U256}, // Steel re-exports alloy primitives
-    ethereum::{EthEvmEnv, ETH_MAINNET_CHAIN_SPEC}, // Choose appropriate chain spec
-    Contract, // The main steel contract interaction type
-};
-use url::Url; // For parsing URLs via clap
-
-// --- Reqwest Alias ---
-use reqwest::Client as SubgraphReqwestClient;
-use risc0_steel::ethereum::ETH_SEPOLIA_CHAIN_SPEC;
-use tracing::{error, info, trace, warn};
-// Import guest ELF and Image ID
-use top_n_holders_guest_methods::{TOP_N_HOLDERS_GUEST_ELF, TOP_N_HOLDERS_GUEST_ID};
+use clap::{ArgGroup, CommandFactory, Parser, Subcommand};
 
 // --- Logging Imports ---
-use tracing_subscriber::EnvFilter;
-use top_n_holders_core::{GuestInput, GuestOutput};
-// --- Struct Definitions ---
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct HolderData {
-    address: Address,
-    balance: U256,
+use tracing::info;
+use tracing_subscriber::{reload, EnvFilter};
+
+// --- Library Imports ---
+use risc0_steel::alloy::primitives::Address;
+use top_n_holders_core::{ChainName, MerkleLeaves};
+use top_n_holders_host::artifacts::ArtifactDir;
+use top_n_holders_host::failure::{self, Failure};
+use top_n_holders_host::fixtures::Fixture;
+use top_n_holders_host::history::History;
+use top_n_holders_host::merkle::{DistributorClaims, MerkleExport};
+use top_n_holders_host::report::Report;
+use top_n_holders_host::{backend, cache, diff, pipeline, progress, stages};
+use top_n_holders_host::{ArtifactArgs, FetchArgs, ProverArgs, ReportFormat, RunArgs, SelectionArgs, SubmitArgs};
+
+mod bench;
+mod config;
+mod daemon;
+mod estimate;
+mod server;
+
+// --- Clap Argument Parsing ---
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Prove Top-N ERC20 Token Holders using Subgraph and Risc0", long_about = None)]
+struct Cli {
+    /// Optional: TOML file with defaults for any argument, plus per-chain `[chains.<name>]` tables.
+    /// Command-line flags and environment variables take precedence over it.
+    #[arg(long, env = "CONFIG", global = true)]
+    config: Option<PathBuf>,
+
+    /// Optional: Hide progress bars (they are also hidden when stderr is not a terminal).
+    #[arg(long, env = "NO_PROGRESS", global = true, default_value_t = false)]
+    no_progress: bool,
+
+    /// Optional: Only log warnings and errors (overrides RUST_LOG) and hide progress bars. Results
+    /// are still printed to stdout; the exit code tells the outcome.
+    #[arg(long, env = "QUIET", global = true, default_value_t = false)]
+    quiet: bool,
+
+    #[command(subcommand)]
+    command: Command,
 }
 
-// SubgraphHolderResponse: Structure to deserialize individual holder entries from Subgraph.
-#[derive(Deserialize, Debug)]
-struct SubgraphHolderResponse {
-    // The 'id' field now holds the holder's address string
-    id: String,
-    balance: String,
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run all stages: fetch, preflight, prove and verify.
+    Run(RunArgs),
+    /// Keep re-proving on a schedule (--interval or --every-blocks), publishing each round's artifacts.
+    Daemon(DaemonArgs),
+    /// Serve an HTTP API that queues proofs for (token, chain, N) and returns their receipts.
+    Serve(ServeArgs),
+    /// Collect candidate holders at the proving block (writes holders.json).
+    Fetch(FetchArgs),
+    /// Select the required holders and preflight their balances (writes guest_input.json and evm_input.json).
+    Preflight(PreflightArgs),
+    /// Prove the guest on the preflighted input (writes receipt.json).
+    Prove(ProveArgs),
+    /// Execute the guest on the preflighted input and project the proving time and cost per backend.
+    Estimate(EstimateArgs),
+    /// Execute the guest on synthetic candidate sets of several sizes and report the cycles per holder.
+    Bench(BenchArgs),
+    /// Verify the stored receipt and print the guest's result.
+    Verify(VerifyArgs),
+    /// Compare the verified Top-N of two proofs of a token: entrants, dropouts, rank and balance changes.
+    Diff(DiffArgs),
+    /// Send the stored receipt's journal and seal to a contract.
+    Submit(SubmitArgs),
+    /// Write the stored receipt's image ID, journal and seal as Solidity constants and JSON, for Foundry tests.
+    GenFixtures(GenFixturesArgs),
+    /// Query the proof history recorded with --history-db.
+    History {
+        #[command(subcommand)]
+        action: HistoryCommand,
+    },
+    /// Inspect and clean up cached data.
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+    /// Print a shell completion script (e.g. `completions bash > /etc/bash_completion.d/top-n-holders-host`).
+    Completions {
+        shell: clap_complete::Shell,
+    },
+    /// Write manpages for the command and every subcommand.
+    Manpages {
+        /// Directory the pages are written to (e.g. /usr/local/share/man/man1).
+        #[arg(long, default_value = ".")]
+        out_dir: PathBuf,
+    },
 }
 
-// SubgraphResponse: Structure to deserialize the top-level Subgraph API response.
-#[derive(Deserialize, Debug)]
-struct SubgraphResponse {
-    data: SubgraphData,
+#[derive(Subcommand, Debug)]
+enum CacheCommand {
+    /// List cached entries with their block, age and size.
+    Ls {
+        /// Directory for cached data. Defaults to the platform cache directory.
+        #[arg(long, env = "CACHE_DIR")]
+        cache_dir: Option<PathBuf>,
+    },
+    /// Delete cached entries older than --max-age.
+    Prune {
+        /// Directory for cached data. Defaults to the platform cache directory.
+        #[arg(long, env = "CACHE_DIR")]
+        cache_dir: Option<PathBuf>,
+
+        /// Entries last modified longer ago than this are deleted (e.g. "12h", "7d").
+        #[arg(long, default_value = "7d", value_parser = humantime::parse_duration)]
+        max_age: Duration,
+
+        /// Only print what would be deleted.
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+    },
 }
 
-// SubgraphData: Structure to deserialize the 'data' part of the Subgraph response.
-#[derive(Deserialize, Debug)]
-struct SubgraphData {
-    #[serde(rename = "tokenHolders")] // Match the GraphQL query alias or field name
-    token_holders: Vec<SubgraphHolderResponse>,
+#[derive(Subcommand, Debug)]
+enum HistoryCommand {
+    /// List the recorded proofs, newest block first.
+    List {
+        /// Path to the SQLite proof history.
+        #[arg(long, env = "HISTORY_DB")]
+        history_db: PathBuf,
+
+        /// Optional: Only list proofs of this token. Not read from ERC20_ADDRESS, which may list
+        /// several tokens for `run`.
+        #[arg(long, env = "HISTORY_ERC20_ADDRESS", value_parser = Address::from_str)]
+        erc20_address: Option<Address>,
+
+        /// Optional: Only list proofs on this chain. Not read from CHAIN_SPEC, which may be `auto`
+        /// for `run`.
+        #[arg(long, env = "HISTORY_CHAIN_SPEC", value_parser = ChainName::from_str)]
+        chain_spec: Option<ChainName>,
+
+        /// Optional: Maximum number of proofs to list.
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+    /// Print a recorded proof with its journal and Top-N.
+    Show {
+        /// Path to the SQLite proof history.
+        #[arg(long, env = "HISTORY_DB")]
+        history_db: PathBuf,
+
+        /// Id of the proof, as listed by `history list`.
+        id: i64,
+    },
 }
 
-// --- Alloy setup for Contract Calls (used by steel) ---
-sol!(
-    interface IERC20 {
-        function balanceOf(address account) external view returns (uint256);
-        function totalSupply() external view returns (uint256);
-    }
+#[derive(clap::Args, Debug, Clone)]
+#[command(group(ArgGroup::new("schedule").required(true).args(["interval", "every_blocks"])))]
+struct DaemonArgs {
+    #[command(flatten)]
+    run: RunArgs,
 
-    // https://github.com/mds1/multicall
-    interface IMulticall3 {
-        struct Call3 {
-            address target;
-            bool allowFailure;
-            bytes callData;
-        }
+    /// Time between rounds (e.g. "6h").
+    #[arg(long, env = "DAEMON_INTERVAL", value_parser = humantime::parse_duration)]
+    interval: Option<Duration>,
 
-        function aggregate3(Call3[] calldata calls)
-            external
-            payable
-            returns (Result[] memory returnData);
+    /// Prove again once the chain is this many blocks past the last proven block.
+    #[arg(long, env = "DAEMON_EVERY_BLOCKS", value_parser = clap::value_parser!(u64).range(1..))]
+    every_blocks: Option<u64>,
 
-        struct Result {
-            bool success;
-            bytes returnData;
-        }
-    }
-);
+    /// Optional: How often to check the chain for new blocks with --every-blocks.
+    #[arg(long, env = "DAEMON_POLL_INTERVAL", default_value = "1m", value_parser = humantime::parse_duration)]
+    poll_interval: Duration,
 
-// --- Clap Argument Parsing ---
+    /// Optional: Address to serve Prometheus metrics on (e.g. 0.0.0.0:9100), at `/metrics`.
+    #[arg(long, env = "METRICS_ADDR")]
+    metrics_addr: Option<SocketAddr>,
+}
 
-#[derive(Parser, Debug)]
-#[command(author, version, about = "Prove Top-N ERC20 Token Holders using Subgraph and Risc0", long_about = None)]
-struct Args {
-    /// URL of the GraphQL Subgraph endpoint providing token holder data.
-    #[arg(long, env = "SUBGRAPH_URL")]
-    subgraph_url: String, // Keep as String, URL parsing might be too strict
+#[derive(clap::Args, Debug, Clone)]
+struct ServeArgs {
+    /// Optional: Address the HTTP API listens on.
+    #[arg(long, env = "LISTEN_ADDR", default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+
+    /// Defaults for every job. The token and N come from each request; the chain must match --chain-spec.
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct PreflightArgs {
+    /// URL(s) of the JSON-RPC endpoint for the Ethereum node (e.g., Infura, Alchemy): `http(s)://`,
+    /// `ws(s)://` or the path of a local node's IPC socket (e.g. `/var/lib/geth/geth.ipc`).
+    /// Repeat the flag (or comma-separate) to configure fallbacks, tried in order on failure.
+    #[arg(long, env = "RPC_URL", value_delimiter = ',', required = true)]
+    rpc_url: Vec<String>,
+
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Optional: The --chunk-size the proof will be chunked with. Every chunk then gets an EVM input
+    /// with only its own holders' state.
+    #[arg(long, env = "CHUNK_SIZE", value_parser = clap::value_parser!(u64).range(1..))]
+    chunk_size: Option<u64>,
+
+    #[command(flatten)]
+    artifacts: ArtifactArgs,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct ProveArgs {
+    #[command(flatten)]
+    prover: ProverArgs,
+
+    #[command(flatten)]
+    artifacts: ArtifactArgs,
+
+    /// Optional: Replay a saved Steel EVM input (an evm_input.json) instead of the preflighted one,
+    /// without any RPC access. It is copied into the artifacts directory first.
+    #[arg(long, env = "EVM_INPUT")]
+    evm_input: Option<PathBuf>,
+
+    /// Optional: The guest input (holder set) replayed with --evm-input. Defaults to the
+    /// guest_input.json next to it.
+    #[arg(long, env = "GUEST_INPUT", requires = "evm_input")]
+    guest_input: Option<PathBuf>,
+}
 
-    /// URL of the JSON-RPC endpoint for the Ethereum node (e.g., Infura, Alchemy).
-    #[arg(long, env = "RPC_URL")]
-    rpc_url: Url,
+#[derive(clap::Args, Debug, Clone)]
+struct EstimateArgs {
+    #[command(flatten)]
+    artifacts: ArtifactArgs,
 
-    /// Address of the ERC20 token contract to verify.
+    /// Optional: Proving throughput of the local CPU prover, in kHz (thousands of cycles per second).
+    #[arg(long, env = "ESTIMATE_CPU_KHZ", default_value_t = 10.0)]
+    cpu_khz: f64,
+
+    /// Optional: Proving throughput of the local GPU prover, in kHz.
+    #[arg(long, env = "ESTIMATE_GPU_KHZ", default_value_t = 250.0)]
+    gpu_khz: f64,
+
+    /// Optional: Proving throughput of Bonsai, in kHz.
+    #[arg(long, env = "ESTIMATE_BONSAI_KHZ", default_value_t = 1000.0)]
+    bonsai_khz: f64,
+
+    /// Optional: Hourly price of the CPU proving machine, in USD.
+    #[arg(long, env = "ESTIMATE_CPU_USD_PER_HOUR")]
+    cpu_usd_per_hour: Option<f64>,
+
+    /// Optional: Hourly price of the GPU proving machine, in USD.
+    #[arg(long, env = "ESTIMATE_GPU_USD_PER_HOUR")]
+    gpu_usd_per_hour: Option<f64>,
+
+    /// Optional: Bonsai price per million cycles, in USD.
+    #[arg(long, env = "ESTIMATE_BONSAI_USD_PER_MCYCLE")]
+    bonsai_usd_per_mcycle: Option<f64>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct BenchArgs {
+    /// URL(s) of the JSON-RPC endpoint to preflight against, typically a local fork of the chain
+    /// (e.g. `anvil --fork-url <RPC URL>`). Fallbacks as for `preflight`.
+    #[arg(long, env = "RPC_URL", value_delimiter = ',', required = true)]
+    rpc_url: Vec<String>,
+
+    /// Address of the ERC20 token contract whose balanceOf the guest calls.
     #[arg(long, env = "ERC20_ADDRESS", value_parser = Address::from_str)]
     erc20_address: Address,
 
-    /// The number 'N' for Top-N holders verification.
-    #[arg(long, env = "N_TOP_HOLDERS", value_parser = clap::value_parser!(usize))]
-    n_top_holders: usize,
+    /// Optional: The chain the RPC endpoint serves (a fork keeps the chain ID of the forked chain).
+    #[arg(long, env = "CHAIN_SPEC", default_value = "mainnet", value_parser = ChainName::from_str)]
+    chain_spec: ChainName,
+
+    /// Optional: Block to preflight at. Defaults to the latest block of the RPC endpoint.
+    #[arg(long, env = "BLOCK_NUMBER")]
+    block_number: Option<u64>,
+
+    /// Optional: Sizes of the candidate sets, comma-separated. Each set is preflighted and executed
+    /// in `<artifacts-dir>/bench-<size>/`.
+    #[arg(long, env = "BENCH_SIZES", value_delimiter = ',', default_value = "10,100,1000", value_parser = clap::value_parser!(u64).range(1..))]
+    sizes: Vec<u64>,
+
+    /// How balances are preflighted (--multicall3, concurrency, ...). N is the size of each set.
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    #[command(flatten)]
+    artifacts: ArtifactArgs,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct VerifyArgs {
+    #[command(flatten)]
+    artifacts: ArtifactArgs,
+
+    /// Optional: If the guest verified the ranking, write a report of the Top-N to this file.
+    #[arg(long, env = "REPORT_OUT")]
+    report_out: Option<PathBuf>,
 
-    /// Optional: Chain specification name (e.g., mainnet, sepolia).
-    /// See risc0_steel::ethereum::chain_spec for available specs.
-    #[arg(long, env = "CHAIN_SPEC")]
-    chain_spec: String,
+    /// Optional: Format of the --report-out report.
+    #[arg(long, env = "REPORT_FORMAT", value_enum, default_value_t = ReportFormat::Csv)]
+    report_format: ReportFormat,
 
-    /// Optional: Use Multicall3 for fetching balances. Defaults to false (fetch individually).
-    #[arg(long, env = "USE_MULTICALL3", default_value_t = false)]
-    multicall3: bool,
+    /// Optional: If the guest verified the ranking, write the OpenZeppelin standard Merkle tree over
+    /// the Top-N to this JSON file, with the root and every holder's proof.
+    #[arg(long, env = "MERKLE_OUT")]
+    merkle_out: Option<PathBuf>,
 
-    /// Optional: Cache Subgraph responses. Defaults to false.
-    #[arg(long, env = "CACHE_SUBGRAPH", default_value_t = false)]
-    cache_subgraph: bool,
+    /// Optional: Leaves of the --merkle-out tree: `address` or `address-balance`.
+    #[arg(long, env = "MERKLE_LEAVES", default_value = "address", value_parser = MerkleLeaves::from_str)]
+    merkle_leaves: MerkleLeaves,
+
+    /// Optional: If the guest verified the ranking, write the claims file of Uniswap's
+    /// merkle-distributor, with every holder's balance as their amount, to this JSON file.
+    #[arg(long, env = "DISTRIBUTOR_OUT")]
+    distributor_out: Option<PathBuf>,
 }
 
+#[derive(clap::Args, Debug, Clone)]
+struct DiffArgs {
+    /// Artifacts directory of the earlier proof.
+    #[arg(long, env = "DIFF_FROM")]
+    from: PathBuf,
+
+    /// Artifacts directory of the later proof, of the same token.
+    #[arg(long, env = "DIFF_TO")]
+    to: PathBuf,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct GenFixturesArgs {
+    #[command(flatten)]
+    artifacts: ArtifactArgs,
+
+    /// Directory the fixtures are written to (e.g. the `test/fixtures` of a Foundry project).
+    #[arg(long, env = "FIXTURES_DIR")]
+    fixtures_dir: PathBuf,
+
+    /// Optional: Name of the Solidity library and of the files (`<name>.sol`, `<name>.json`).
+    #[arg(long, env = "FIXTURE_NAME", default_value = "TopNHoldersFixture")]
+    fixture_name: String,
+}
+
+// --- Exit Codes ---
+// Stable per outcome, for shell pipelines and orchestrators. Invalid arguments exit with 2 (clap).
+const EXIT_FAILURE: u8 = 1; // Any other error, e.g. missing artifacts.
+const EXIT_GUEST_VERIFICATION_FAILED: u8 = 3;
+const EXIT_DATA_SOURCE_ERROR: u8 = 4;
+const EXIT_PROVER_ERROR: u8 = 5;
+
 // --- Main Host Logic ---
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     // Initialize tracing/logging
-    tracing_subscriber::fmt()
+    let builder = tracing_subscriber::fmt()
         .compact()
         .with_env_filter(EnvFilter::from_default_env()) // Use RUST_LOG env var
-        .init();
-
-    // Parse command-line arguments
-    let args = Args::parse();
-
-    // --- Configuration (from Args) ---
-    let erc20_contract_address = args.erc20_address;
-    let n = args.n_top_holders;
-    let rpc_url = args.rpc_url; // Already Url type
-    let subgraph_url = args.subgraph_url; // String
-
-    info!("Configuration:");
-    info!("ERC20 Contract: {}", erc20_contract_address);
-    info!("Subgraph URL: {}", subgraph_url);
-    info!("RPC URL: {}", rpc_url);
-    info!("Chain Spec: {}", args.chain_spec);
-    info!("N: {}", n);
-
-    // --- Cache Configuration ---
-    let cache_dir = Path::new("./tmp");
-    let cache_file_name = format!(
-        "{}-{:#x}.json",
-        args.chain_spec.to_lowercase(),
-        erc20_contract_address
-    );
-    let cache_file_path = cache_dir.join(cache_file_name);
-
-    // --- Attempt to Load from Cache or Fetch Data from Subgraph ---
-    // Stores addresses fetched from the Subgraph.
-    let mut all_subgraph_holders: Vec<HolderData>;
-
-    if args.cache_subgraph && cache_file_path.exists() {
-        info!("Cache found at {:?}. Loading holder addresses from cache...", cache_file_path);
-        let cached_data = fs::read_to_string(&cache_file_path)
-            .with_context(|| format!("Failed to read cache file: {:?}", cache_file_path))?;
-        // Deserialize as Vec<Address>.
-        all_subgraph_holders = serde_json::from_str(&cached_data)
-            .with_context(|| format!("Failed to deserialize cached data from {:?}", cache_file_path))?;
-        info!("Loaded {} holder addresses from cache.", all_subgraph_holders.len());
-
-    } else {
-        if args.cache_subgraph {
-            info!("Cache not found or --cache-subgraph not specified. Fetching holder addresses from Subgraph...");
-        } else {
-            info!("Fetching holder addresses from Subgraph (caching disabled)...");
+        .with_writer(|| progress::LogWriter) // Keeps log lines clear of progress bars
+        .with_filter_reloading(); // --quiet is only known once the arguments are parsed
+    let log_filter = builder.reload_handle();
+    builder.init();
+
+    match run(log_filter).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(match failure::failure_kind(&e) {
+                Some(Failure::GuestVerification) => EXIT_GUEST_VERIFICATION_FAILED,
+                Some(Failure::DataSource) => EXIT_DATA_SOURCE_ERROR,
+                Some(Failure::Prover) => EXIT_PROVER_ERROR,
+                None => EXIT_FAILURE,
+            })
         }
-        let subgraph_http_client = SubgraphReqwestClient::new();
-        let mut fetched_holders_list: Vec<HolderData> = Vec::new(); // Temporary list for fetching
-        // Use last_id for pagination instead of skip
-        let mut last_id = String::from(""); // Start with empty string for the first query
-        const PAGE_SIZE: usize = 1000;
-
-        loop {
-            // Updated GraphQL query to fetch only holder IDs (addresses)
-            let graphql_query_paginated = format!(
-                r#"{{
-                  tokenHolders(
-                    first: {},
-                    orderBy: id, # Order by ID for consistent pagination
-                    orderDirection: asc, # Ascending order for id_gt
-                    where: {{ token: "{}", id_gt: "{}" }}
-                  ) {{
-                    id # This is the holder's address
-                    balance
-                  }}
-                }}"#,
-                PAGE_SIZE,
-                // Subgraphs often expect lowercase addresses in IDs/filters
-                format!("{:#x}", erc20_contract_address).to_lowercase(),
-                last_id // Use the last fetched ID for the filter
-            );
-
-            let res = subgraph_http_client
-                .post(&subgraph_url)
-                .json(&serde_json::json!({ "query": graphql_query_paginated }))
-                .send()
-                .await
-                .context("Failed to send request to Subgraph")?;
+    }
+}
 
-            let status = res.status();
-            let body_text = res.text().await.context("Failed to read Subgraph response body")?;
+async fn run<S>(log_filter: reload::Handle<EnvFilter, S>) -> Result<()> {
+    // Load a local .env file; variables already set in the environment take precedence
+    match dotenvy::dotenv() {
+        Ok(path) => info!("Loaded environment from {:?}", path),
+        Err(e) if e.not_found() => {}
+        Err(e) => return Err(e).context("Failed to load .env file"),
+    }
 
-            if !status.is_success() {
-                anyhow::bail!(
-                    "Subgraph request failed with status: {}. Response body: {}",
-                    status,
-                    body_text
-                );
+    // Parse command-line arguments, with defaults from the config file
+    config::apply_config_file(Cli::command())?;
+    let cli = Cli::parse();
+    if cli.quiet {
+        log_filter.reload(EnvFilter::new("warn")).context("Failed to apply --quiet")?;
+    }
+    progress::set_enabled(!cli.no_progress && !cli.quiet);
+    if let Some(config) = &cli.config {
+        info!("Using config file {:?}", config);
+    }
+    match cli.command {
+        Command::Run(args) => pipeline::run(&args).await,
+        Command::Daemon(args) => daemon::run_daemon(&args).await,
+        Command::Serve(args) => server::serve(args).await,
+        Command::Fetch(args) => fetch(args).await.map_err(|e| failure::tag(e, Failure::DataSource)),
+        Command::Preflight(args) => {
+            let clients = stages::PreflightClients::new(&args.rpc_url);
+            stages::preflight(&clients, &args.selection, args.chunk_size, &ArtifactDir::new(&args.artifacts.artifacts_dir))
+                .await
+                .map(drop)
+                .map_err(|e| failure::tag(e, Failure::DataSource))
+        }
+        Command::Prove(args) => {
+            let artifacts = ArtifactDir::new(&args.artifacts.artifacts_dir);
+            if let Some(evm_input) = &args.evm_input {
+                let guest_input = args.guest_input.clone().unwrap_or_else(|| evm_input.with_file_name("guest_input.json"));
+                info!("Replaying {:?} with {:?}", evm_input, guest_input);
+                artifacts.import_preflight(&guest_input, evm_input)?;
             }
-
-            let response_body: SubgraphResponse = serde_json::from_str(&body_text)
-                .with_context(|| format!(
-                    "Failed to decode Subgraph JSON response. Status: {}. Body: {}",
-                    status,
-                    body_text
-                ))?;
-
-            let fetched_holders_page = response_body.data.token_holders;
-            let fetched_count = fetched_holders_page.len();
-            // Log fetched count without skip
-            info!("Fetched page with {} holder addresses (last_id='{}')", fetched_count, last_id);
-
-            if fetched_count == 0 {
-                // No more holders found
-                if last_id.is_empty() { // Check if this was the *first* query
-                    info!("No holders found for this token in the subgraph.");
-                } else {
-                    info!("Finished fetching all holder addresses.");
-                }
-                break;
+            if args.prover.execute_only {
+                return stages::execute(&artifacts, (&args.prover).into()).map(drop).map_err(|e| failure::tag(e, Failure::Prover));
             }
-
-            // Process fetched holders and update last_id
-            if let Some(last_holder) = fetched_holders_page.last() {
-                last_id = last_holder.id.clone(); // Update last_id for the next query
+            let backend = backend::from_args(&args.prover)?;
+            stages::prove(backend.as_ref(), &args.prover, &artifacts)
+                .map(drop)
+                .map_err(|e| failure::tag(e, Failure::Prover))
+        }
+        Command::Estimate(args) => estimate::estimate(&args).map_err(|e| failure::tag(e, Failure::Prover)),
+        Command::Bench(args) => bench::bench(&args).await,
+        Command::Verify(args) => {
+            let artifacts = ArtifactDir::new(&args.artifacts.artifacts_dir);
+            let proof = stages::verify(&artifacts).map_err(|e| failure::tag(e, Failure::Prover))?;
+            if !proof.output.verification_succeeded {
+                let err = match &proof.output.failure {
+                    Some(reason) => anyhow::anyhow!("The guest rejected the ranking: {}", reason),
+                    None => anyhow::anyhow!("The guest rejected the ranking"),
+                };
+                return Err(failure::tag(err, Failure::GuestVerification));
             }
-
-            for holder_response in fetched_holders_page {
-                let holder_address = Address::from_str(&holder_response.id)
-                    .with_context(|| format!("Failed to parse holder address from id: {}", holder_response.id))?;
-                let holder_balance = U256::from_str_radix(&holder_response.balance, 10)
-                    .with_context(|| format!("Failed to parse balance for {}", holder_response.id))?;
-
-                fetched_holders_list.push(HolderData { // Add to temporary list
-                    address: holder_address,
-                    balance: holder_balance,
-                });
+            if let Some(report_out) = &args.report_out {
+                let holders = artifacts.load_holders()?;
+                Report::new(holders.token, holders.chain_spec, &artifacts, &proof)?.write(args.report_format, report_out)?;
             }
-
-            // Break if the fetched count is less than the page size (last page)
-            if fetched_count < PAGE_SIZE { break; }
+            if let Some(merkle_out) = &args.merkle_out {
+                MerkleExport::new(&proof.output, &artifacts, args.merkle_leaves)?.write(merkle_out)?;
+            }
+            if let Some(distributor_out) = &args.distributor_out {
+                DistributorClaims::new(&proof.output, &artifacts)?.write(distributor_out)?;
+            }
+            Ok(())
+        }
+        Command::Diff(args) => diff::print(&ArtifactDir::new(&args.from), &ArtifactDir::new(&args.to)),
+        Command::Submit(args) => stages::submit(&args, &ArtifactDir::new(&args.artifacts.artifacts_dir)).await,
+        Command::GenFixtures(args) => {
+            let receipt = ArtifactDir::new(&args.artifacts.artifacts_dir).load_receipt()?;
+            Fixture::new(&receipt)?.write(&args.fixtures_dir, &args.fixture_name)
         }
-        info!("Fetched total {} holders from Subgraph.", fetched_holders_list.len());
-
-        // Assign fetched data to the main variable
-        all_subgraph_holders = fetched_holders_list;
-
-        // --- Write to Cache ---
-        if args.cache_subgraph {
-            info!("Writing fetched holder addresses to cache: {:?}", cache_file_path);
-            fs::create_dir_all(cache_dir)
-                .with_context(|| format!("Failed to create cache directory: {:?}", cache_dir))?;
-            // Serialize Vec<Address> for caching.
-            let cache_data = serde_json::to_string_pretty(&all_subgraph_holders)
-                .context("Failed to serialize holder addresses for caching")?;
-            fs::write(&cache_file_path, cache_data)
-                .with_context(|| format!("Failed to write cache file: {:?}", cache_file_path))?;
-            info!("Successfully wrote cache file.");
+        Command::History { action } => run_history_command(action),
+        Command::Cache { action } => run_cache_command(action),
+        Command::Completions { shell } => {
+            let mut command = Cli::command();
+            let name = command.get_name().to_string();
+            clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+            Ok(())
+        }
+        Command::Manpages { out_dir } => {
+            std::fs::create_dir_all(&out_dir).with_context(|| format!("Failed to create {:?}", out_dir))?;
+            clap_mangen::generate_to(Cli::command(), &out_dir)
+                .with_context(|| format!("Failed to write manpages to {:?}", out_dir))?;
+            info!("Wrote manpages to {:?}", out_dir);
+            Ok(())
         }
     }
+}
 
-    // Host no longer determines Top-N directly. Guest will do this.
-    info!(
-        "Subgraph fetch complete. {} holder addresses will be passed to the ZKVM guest.",
-        all_subgraph_holders.len()
-    );
-    info!("The guest will fetch balances on-chain, sort, verify total supply, and determine the Top {} holders.", n);
-
-    // --- Fetch Total Supply from Blockchain (using risc0-steel) ---
-    info!("Fetching total supply from blockchain via risc0-steel...");
-    let chain_spec = match args.chain_spec.to_lowercase().as_str() {
-        "mainnet" => &ETH_MAINNET_CHAIN_SPEC,
-        "sepolia" => &ETH_SEPOLIA_CHAIN_SPEC,
-        "gnosis" => &top_n_holders_core::GNOSIS_MAINNET_CHAIN_SPEC,
-
-        _ => panic!("Chain spec not supported"),
-    };
-
-    let mut env = EthEvmEnv::builder()
-        .rpc(rpc_url.clone()) // Ensure rpc_url is correctly passed
-        .chain_spec(chain_spec)
-        .build()
-        .await
-        .context("Failed to build EthEvmEnv from RPC")?;
-
-    let mut contract = Contract::preflight(erc20_contract_address, &mut env);
-
-    let call = IERC20::totalSupplyCall {};
-
-    info!(
-        "Calling {} on {}...",
-        IERC20::totalSupplyCall::SIGNATURE,
-        erc20_contract_address
-    );
-    let result_supply = contract // Renamed to avoid conflict if 'result' is used later for journal
-        .call_builder(&call)
-        .call()
-        .await
-        .context("Failed to call totalSupply via EthEvmEnv")?;
-
-    let onchain_total_supply: U256 = result_supply;
-
-    info!("On-chain Total Supply: {}", onchain_total_supply);
-
-    // --- Prepare Input for ZKVM Guest ---
-    // The host provides its initial claim for the top N addresses.
-    // This is at least N addresses from the subgraph, sorted by balance.
-    // But usually it requires more than N to ensure the guest can determine the top N.
-    // The guest will verify this claim by fetching balances and ensuring descending order.
-
-    // Sort holders by descending balance
-    all_subgraph_holders
-        .sort_by(|a, b| {
-            b.balance
-                .cmp(&a.balance) // Descending balance
-                .then_with(|| a.address.cmp(&b.address)) // Ascending address (tie-breaker)
-        });
-
-    // TODO: determine the holders required for the proof. Usually should be more than N.
-    let mut required_addresses_desc: Vec<Address> = Vec::new();
-    let mut accumulated_balance: U256 = U256::ZERO;
-    let mut last_holder_balance: U256 = U256::ZERO;
-    let mut threshold_balance: Option<U256> = None;
-    let mut i = 0;
-    for holder in all_subgraph_holders.iter() {
-        accumulated_balance += holder.balance;
-        last_holder_balance = holder.balance;
-        i += 1;
-        if i == n {
-            threshold_balance = Some(holder.balance);
+// --- Proof History Commands ---
+fn run_history_command(command: HistoryCommand) -> Result<()> {
+    match command {
+        HistoryCommand::List { history_db, erc20_address, chain_spec, limit } => {
+            let proofs = History::open(&history_db)?.list(chain_spec, erc20_address, limit)?;
+            println!("{:>6} {:<10} {:<42} {:>10} {:>5} {:<8} {:<66} {:>12}", "ID", "CHAIN", "TOKEN", "BLOCK", "N", "RESULT", "JOURNAL DIGEST", "AGE");
+            for proof in &proofs {
+                let age = SystemTime::now()
+                    .duration_since(UNIX_EPOCH + Duration::from_secs(proof.recorded_at))
+                    .unwrap_or_default();
+                println!(
+                    "{:>6} {:<10} {:<42} {:>10} {:>5} {:<8} {:<66} {:>12}",
+                    proof.id,
+                    proof.chain,
+                    proof.token.to_string(),
+                    proof.block,
+                    proof.n,
+                    if proof.verified { "verified" } else { "rejected" },
+                    proof.journal_digest,
+                    humantime::format_duration(Duration::from_secs(age.as_secs())).to_string()
+                );
+            }
+            println!("{} proofs", proofs.len());
         }
-
-        required_addresses_desc.push(holder.address);
-        if let Some(threshold) = threshold_balance {
-            let remainder = onchain_total_supply - accumulated_balance;
-            trace!("#{} Holder: {} - Balance: {}, Threshold: {}, Remainder: {}", i, holder.address, holder.balance, threshold, remainder);
-            trace!("{} < {}", threshold, remainder);
-            if threshold > remainder {
-                break;
+        HistoryCommand::Show { history_db, id } => {
+            let history = History::open(&history_db)?;
+            let proof = history.proof(id)?.with_context(|| format!("No proof #{} in {:?}", id, history_db))?;
+            println!("Proof #{}", proof.id);
+            println!("Chain:          {}", proof.chain);
+            println!("Token:          {}", proof.token);
+            println!("Block:          {}", proof.block);
+            println!("N:              {}", proof.n);
+            println!("Result:         {}", if proof.verified { "verified" } else { "rejected by the guest" });
+            println!("Image ID:       {}", proof.image_id);
+            println!("Journal digest: {}", proof.journal_digest);
+            println!("Journal:        0x{}", hex::encode(&proof.journal));
+            println!("Artifacts:      {}", proof.artifacts_dir.display());
+            println!("Recorded at:    {}", humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(proof.recorded_at)));
+            println!();
+            println!("{:>4}  {:<42}  {:>40}", "RANK", "ADDRESS", "BALANCE (BASE UNITS)");
+            for holder in history.holders(id)? {
+                println!(
+                    "{:>4}  {:<42}  {:>40}",
+                    holder.rank,
+                    holder.address.to_string(),
+                    holder.balance.map_or_else(|| "?".to_string(), |balance| balance.to_string())
+                );
             }
         }
     }
+    Ok(())
+}
 
-    let actual_n_for_slicing = std::cmp::min(n, required_addresses_desc.len());
-    let top_n_addresses: Vec<Address> = required_addresses_desc.iter().take(actual_n_for_slicing).cloned().collect();
-    let extra_addresses: Vec<Address> = required_addresses_desc.iter().skip(actual_n_for_slicing).cloned().collect();
-
-    info!("Top-N addresses ({}): {:?}", top_n_addresses.len(), top_n_addresses);
-    info!("Extra addresses required for proof ({}): {:?}", extra_addresses.len(), extra_addresses);
-    info!("Accumulated/Last holder balance: {} / {}", accumulated_balance, last_holder_balance);
-
-    info!("Required holders ({}): {:?}", required_addresses_desc.len(), required_addresses_desc);
-
-    info!("Fetching balances for required addresses from blockchain via risc0-steel...");
-
-    if args.multicall3 {
-        info!("Using Multicall3 to fetch balances...");
-        // --- Multicall3 Setup ---
-        // Address of the Multicall3 contract (same on most chains)
-        // https://github.com/mds1/multicall
-        const MULTICALL3_ADDRESS: Address = address!("0xcA11bde05977b3631167028862bE2a173976CA11");
-
-        let mut multicall_contract = Contract::preflight(MULTICALL3_ADDRESS, &mut env);
-
-        let calls: Vec<IMulticall3::Call3> = required_addresses_desc
-            .iter()
-            .map(|&addr| {
-                let balance_of_call = IERC20::balanceOfCall { account: addr };
-                IMulticall3::Call3 {
-                    target: erc20_contract_address, // The ERC20 token contract
-                    allowFailure: true, // Allow individual calls to fail
-                    callData: balance_of_call.abi_encode().into(),
-                }
-            })
-            .collect();
-
-        let aggregate_call = IMulticall3::aggregate3Call { calls };
-
-        info!("Preparing to call aggregate3 on Multicall3 contract at {}", MULTICALL3_ADDRESS);
-        let multicall_results = multicall_contract
-            .call_builder(&aggregate_call)
-            .call()
-            .await
-            .context("Failed to call aggregate3 on Multicall3 contract")?;
-
-        info!("Multicall3 aggregate3 call successful. Processing {} results...", multicall_results.len());
-
-        for (i, result) in multicall_results.iter().enumerate() {
-            let holder_address = required_addresses_desc[i]; // Assuming order is preserved
-            if result.success {
-                match IERC20::balanceOfCall::abi_decode_returns(&result.returnData) {
-                    Ok(decoded_balance) => {
-                        info!("Successfully fetched balance for {}: {}", holder_address, decoded_balance);
-                    }
-                    Err(e) => {
-                        error!("Failed to decode balanceOf return data for {}: {:?}", holder_address, e);
-                    }
-                }
-            } else {
-                info!("balanceOf call failed for address {} in multicall", holder_address);
+// --- Cache Maintenance Commands ---
+fn run_cache_command(command: CacheCommand) -> Result<()> {
+    match command {
+        CacheCommand::Ls { cache_dir } => {
+            let cache_dir = cache_dir.unwrap_or_else(cache::default_cache_dir);
+            let entries = cache::list_entries(&cache_dir)?;
+            println!("Cache directory: {}", cache_dir.display());
+            println!("{:<20} {:<56} {:>10} {:>12} {:>10}", "CATEGORY", "KEY", "BLOCK", "AGE", "SIZE");
+            for entry in &entries {
+                println!(
+                    "{:<20} {:<56} {:>10} {:>12} {:>10}",
+                    entry.category,
+                    entry.key,
+                    entry.block.map(|block| block.to_string()).unwrap_or_else(|| "-".to_string()),
+                    humantime::format_duration(Duration::from_secs(entry.age().as_secs())).to_string(),
+                    cache::format_size(entry.size)
+                );
             }
+            let total: u64 = entries.iter().map(|entry| entry.size).sum();
+            println!("{} entries, {}", entries.len(), cache::format_size(total));
         }
-    } else {
-        info!("Fetching balances individually (not using Multicall3)...");
-        let mut individual_balances: Vec<(Address, U256)> = Vec::new(); // To store fetched balances if needed
-
-        for (i, &holder_address) in required_addresses_desc.iter().enumerate() {
-            info!("Fetching balance for address {} ({}/{})", holder_address, i + 1, required_addresses_desc.len());
-            let balance_of_call = IERC20::balanceOfCall { account: holder_address };
-            let mut individual_contract_instance = Contract::preflight(erc20_contract_address, &mut env);
-
-            match individual_contract_instance
-                .call_builder(&balance_of_call)
-                .call()
-                .await
-            {
-                Ok(result_balance) => {
-                    let balance: U256 = result_balance;
-                    info!("Successfully fetched balance for {}: {}", holder_address, balance);
-                    individual_balances.push((holder_address, balance));
-                    // As before, this is mostly for pre-warming the EVM state for the guest.
-                }
-                Err(e) => {
-                    error!("Failed to fetch balance for {}: {:?}", holder_address, e);
-                    // Decide how to handle individual errors, e.g., push a zero balance or skip
+        CacheCommand::Prune { cache_dir, max_age, dry_run } => {
+            let cache_dir = cache_dir.unwrap_or_else(cache::default_cache_dir);
+            let mut freed = 0;
+            let mut removed = 0;
+            for entry in cache::list_entries(&cache_dir)?.iter().filter(|entry| entry.age() > max_age) {
+                println!(
+                    "{} {} ({})",
+                    if dry_run { "Would remove" } else { "Removing" },
+                    entry.path.display(),
+                    cache::format_size(entry.size)
+                );
+                if !dry_run {
+                    cache::remove_entry(entry)?;
                 }
+                freed += entry.size;
+                removed += 1;
             }
+            println!(
+                "{} {} entries, {}",
+                if dry_run { "Would free" } else { "Freed" },
+                removed,
+                cache::format_size(freed)
+            );
         }
-        info!("Finished fetching balances individually for {} addresses.", required_addresses_desc.len());
     }
+    Ok(())
+}
 
-    let guest_input = GuestInput {
-        required_addresses_desc,
-        n,
-        erc20_contract_address,
-        chain_spec_name: args.chain_spec.clone(), // Pass chain spec name
+// --- Fetch Stage ---
+async fn fetch(args: FetchArgs) -> Result<()> {
+    let &[token] = args.erc20_address.as_slice() else {
+        anyhow::bail!("`fetch` takes a single --erc20-address; use `run` to process several tokens");
     };
-
-    let evm_input = env.into_input().await?;
-
-    info!("Executing and proving with Risk Zero zkVM...");
-    let exec_env = ExecutorEnv::builder()
-        .write(&evm_input)?
-        .write(&guest_input)?
-        .build()?;
-
-    let prover = default_prover();
-    info!("Running the prover...");
-    let prove_info = prover.prove(exec_env, TOP_N_HOLDERS_GUEST_ELF)?;
-    let receipt = prove_info.receipt;
-    info!("Proof generated successfully!");
-
-    receipt.verify(TOP_N_HOLDERS_GUEST_ID)?;
-    info!("Receipt verified locally successfully!");
-
-    // Decode GuestOutput from the journal.
-    let guest_output: GuestOutput = receipt.journal.decode()
-        .context("Failed to decode GuestOutput from ZKVM journal")?;
-
-    info!("Verification Result (from ZK proof journal):");
-    info!("Guest Verification Succeeded: {}", guest_output.verification_succeeded);
-    info!("Guest Determined Top {} Addresses: {:?}", n, guest_output.final_top_n_addresses);
-    info!("(Proof implies guest correctly fetched balances, sorted, checked total supply, and compared against host's claimed Top {} addresses)", n);
-
-    info!("Data for On-Chain Verification:");
-    info!("Image ID: {:?}", TOP_N_HOLDERS_GUEST_ID);
-    info!("Journal (Hex): 0x{}", hex::encode(&receipt.journal.bytes));
-
-    if guest_output.verification_succeeded {
-        info!("Conclusion: The ZK proof confirms the guest correctly determined the Top {} holders, verified total supply, and that these match the host's initial claim.", n);
-        info!("The determined Top {} addresses by the guest are: {:?}", n, guest_output.final_top_n_addresses);
-    } else {
-        error!("Conclusion: The ZK proof indicates a discrepancy or failure in guest execution.");
-        error!("This could be due to: total supply mismatch, or the guest's determined Top-N differs from the host's claimed Top-N, or other internal guest error.");
-        if !guest_output.final_top_n_addresses.is_empty() {
-             warn!("Guest's determined Top {} addresses (if available): {:?}", n, guest_output.final_top_n_addresses);
-        } else {
-            warn!("Guest did not determine/output Top-N addresses, or an earlier error occurred (e.g., balance fetch, total supply mismatch).");
-        }
-    }
-
+    let clients = stages::FetchClients::new(&args).await?;
+    let block_number = stages::resolve_block(&clients, args.block_number, args.block_tag).await?;
+    let artifacts = ArtifactDir::new(&args.artifacts.artifacts_dir);
+    stages::fetch(&args, &clients, token, block_number, &artifacts).await?;
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,104 @@
+// --- Subgraph Response Mapping ---
+// Describes where holder data lives in a Subgraph schema, so that schemas other than
+// `tokenHolders { id balance }` can be queried without code changes. Field paths are
+// dot-separated, e.g. `account.id` selects `account { id }`.
+
+use anyhow::{Context, Result};
+use risc0_steel::alloy::primitives::U256;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone)]
+pub struct ResponseMapping {
+    pub collection: String,    // Entity collection to paginate, e.g. `tokenHolders`.
+    pub token_field: String,   // Filter field for the token; empty for single-token subgraphs.
+    pub holder_path: String,   // Path to the holder address (or composite id), e.g. `account.id`.
+    pub balance_path: String,  // Path to the raw balance, e.g. `value`.
+}
+
+impl Default for ResponseMapping {
+    fn default() -> Self {
+        Self {
+            collection: "tokenHolders".to_string(),
+            token_field: "token".to_string(),
+            holder_path: "id".to_string(),
+            balance_path: "balance".to_string(),
+        }
+    }
+}
+
+/// One entity of a page, reduced to the values the fetcher needs.
+pub struct MappedHolder {
+    pub entity_id: String, // Entity `id`, used as the pagination cursor.
+    pub holder: String,
+    pub balance: U256,
+}
+
+impl ResponseMapping {
+    /// The GraphQL selection set for one entity. Always includes `id` for pagination.
+    pub fn selection(&self) -> String {
+        let mut tree = SelectionTree::default();
+        for path in ["id", self.holder_path.as_str(), self.balance_path.as_str()] {
+            tree.insert(path.split('.'));
+        }
+        tree.render()
+    }
+
+    /// Extracts the entities of the configured collection from a query's `data`.
+    pub fn map_page(&self, data: &Value) -> Result<Vec<MappedHolder>> {
+        let entities = data
+            .get(&self.collection)
+            .and_then(Value::as_array)
+            .with_context(|| format!("Subgraph response has no `{}` list", self.collection))?;
+
+        entities
+            .iter()
+            .map(|entity| {
+                let entity_id = lookup(entity, "id")?;
+                let holder = lookup(entity, &self.holder_path)?;
+                let balance = lookup(entity, &self.balance_path)?;
+                let balance = U256::from_str_radix(&balance, 10)
+                    .with_context(|| format!("Failed to parse balance for {}", entity_id))?;
+                Ok(MappedHolder { entity_id, holder, balance })
+            })
+            .collect()
+    }
+}
+
+/// Resolves a dot-separated path to a string (numbers are rendered in decimal).
+fn lookup(entity: &Value, path: &str) -> Result<String> {
+    let value = path
+        .split('.')
+        .try_fold(entity, |value, key| value.get(key))
+        .with_context(|| format!("Subgraph entity has no field `{}`: {}", path, entity))?;
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Number(number) => Ok(number.to_string()),
+        _ => anyhow::bail!("Field `{}` is not a string or number: {}", path, value),
+    }
+}
+
+#[derive(Default)]
+struct SelectionTree(BTreeMap<String, SelectionTree>);
+
+impl SelectionTree {
+    fn insert<'a>(&mut self, mut path: impl Iterator<Item = &'a str>) {
+        if let Some(field) = path.next() {
+            self.0.entry(field.to_string()).or_default().insert(path);
+        }
+    }
+
+    fn render(&self) -> String {
+        self.0
+            .iter()
+            .map(|(field, children)| {
+                if children.0.is_empty() {
+                    field.clone()
+                } else {
+                    format!("{} {{ {} }}", field, children.render())
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
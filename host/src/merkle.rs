@@ -0,0 +1,259 @@
+// --- Merkle Exports ---
+// `--merkle-out` turns the verified Top-N into an airdrop allowlist: the OpenZeppelin standard
+// Merkle tree over the holders (see `top_n_holders_core::merkle_tree`), with the root and every
+// holder's proof for `MerkleProof.verify`. The `tree` member is a `StandardMerkleTree.dump()`, so
+// `StandardMerkleTree.load()` of @openzeppelin/merkle-tree reads it as is.
+//
+// With --commit-merkle-root the guest committed the root, and the export must arrive at the same
+// one; a different root means the tree was built over other leaves (--merkle-leaves).
+//
+// `--distributor-out` writes the claims file of Uniswap's merkle-distributor instead, as its
+// `generate-merkle-root` script does: every holder may claim their balance at the proven block.
+// Its tree is built differently (packed leaves, odd nodes promoted), so the guest's root does not
+// apply to it.
+
+use anyhow::{Context, Result};
+use risc0_steel::alloy::primitives::{keccak256, Address, B256, U256};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+
+use top_n_holders_core::{merkle_leaf, merkle_tree, GuestOutput, MerkleLeaves};
+
+use crate::artifacts::ArtifactDir;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MerkleExport {
+    pub root: B256,
+    pub root_proven: bool, // Committed by the guest (--commit-merkle-root).
+    pub leaf_encoding: &'static [&'static str],
+    pub holders: Vec<MerkleHolder>,
+    pub tree: StandardTreeDump,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MerkleHolder {
+    pub rank: usize,
+    pub address: Address,
+    pub balance: String, // Base units.
+    pub leaf: B256,
+    pub proof: Vec<B256>,
+}
+
+/// The `standard-v1` dump format of @openzeppelin/merkle-tree.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StandardTreeDump {
+    pub format: &'static str,
+    pub leaf_encoding: &'static [&'static str],
+    pub tree: Vec<B256>,
+    pub values: Vec<StandardTreeValue>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StandardTreeValue {
+    pub value: Vec<String>,
+    pub tree_index: usize,
+}
+
+impl MerkleExport {
+    /// The tree over the verified holders of `output` with `leaves`, with the balances from the
+    /// artifacts' `token.json`.
+    pub fn new(output: &GuestOutput, artifacts: &ArtifactDir, leaves: MerkleLeaves) -> Result<Self> {
+        anyhow::ensure!(output.verification_succeeded, "Not exporting a Merkle tree for a proof the guest rejected");
+        let metadata = artifacts
+            .load_token()?
+            .context("No token.json with the preflighted balances in the artifacts; run `preflight` again")?;
+        let holders: Vec<(Address, U256)> = output
+            .final_top_n_addresses
+            .iter()
+            .map(|address| (*address, metadata.balances.get(address).copied().unwrap_or_default()))
+            .collect();
+        let leaf_hashes: Vec<B256> =
+            holders.iter().map(|(address, balance)| merkle_leaf(leaves, *address, *balance)).collect();
+        let tree = merkle_tree(&leaf_hashes);
+        let root = tree.first().copied().context("The verified Top-N is empty")?;
+        if let Some(committed) = output.top_n_merkle_root {
+            anyhow::ensure!(
+                committed == root,
+                "The guest committed the Merkle root {}, but the tree with {} leaves has the root {}; the proof was made with other --merkle-leaves",
+                committed,
+                leaves,
+                root
+            );
+        }
+
+        let tree_index = |leaf: &B256| tree.iter().rposition(|node| node == leaf).expect("every leaf is in the tree");
+        let mut values = Vec::with_capacity(holders.len());
+        let mut ranked = Vec::with_capacity(holders.len());
+        for (i, ((address, balance), leaf)) in holders.iter().zip(&leaf_hashes).enumerate() {
+            let index = tree_index(leaf);
+            let value = match leaves {
+                MerkleLeaves::Address => vec![address.to_string()],
+                MerkleLeaves::AddressBalance => vec![address.to_string(), balance.to_string()],
+            };
+            values.push(StandardTreeValue { value, tree_index: index });
+            ranked.push(MerkleHolder {
+                rank: i + 1,
+                address: *address,
+                balance: balance.to_string(),
+                leaf: *leaf,
+                proof: proof(&tree, index),
+            });
+        }
+
+        Ok(Self {
+            root,
+            root_proven: output.top_n_merkle_root.is_some(),
+            leaf_encoding: leaves.encoding(),
+            holders: ranked,
+            tree: StandardTreeDump { format: "standard-v1", leaf_encoding: leaves.encoding(), tree, values },
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(self)?).with_context(|| format!("Failed to write {:?}", path))?;
+        info!("Wrote the Merkle tree of the Top {} (root {}) to {:?}", self.holders.len(), self.root, path);
+        Ok(())
+    }
+}
+
+/// The claims file of Uniswap's merkle-distributor. Amounts are hex strings, like the script's.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DistributorClaims {
+    pub merkle_root: B256,
+    pub token_total: String,
+    pub claims: BTreeMap<String, DistributorClaim>, // By checksummed address.
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DistributorClaim {
+    pub index: usize,
+    pub amount: String,
+    pub proof: Vec<B256>,
+}
+
+impl DistributorClaims {
+    /// The claims of the verified holders of `output` to their balances from the artifacts'
+    /// `token.json`. Holders without a balance cannot claim anything and are left out.
+    pub fn new(output: &GuestOutput, artifacts: &ArtifactDir) -> Result<Self> {
+        anyhow::ensure!(output.verification_succeeded, "Not exporting claims for a proof the guest rejected");
+        let metadata = artifacts
+            .load_token()?
+            .context("No token.json with the preflighted balances in the artifacts; run `preflight` again")?;
+        // The script indexes the accounts in the order of their checksummed addresses.
+        let mut accounts: Vec<(String, Address, U256)> = Vec::with_capacity(output.final_top_n_addresses.len());
+        for address in &output.final_top_n_addresses {
+            match metadata.balances.get(address).filter(|balance| !balance.is_zero()) {
+                Some(balance) => accounts.push((address.to_string(), *address, *balance)),
+                None => warn!("Leaving {} out of the claims: it has no balance", address),
+            }
+        }
+        anyhow::ensure!(!accounts.is_empty(), "None of the verified holders has a balance to claim");
+        accounts.sort();
+
+        let leaves: Vec<B256> = accounts
+            .iter()
+            .enumerate()
+            .map(|(index, (_, address, amount))| distributor_leaf(index, *address, *amount))
+            .collect();
+        let layers = distributor_layers(&leaves);
+        let token_total = accounts.iter().fold(U256::ZERO, |sum, (_, _, amount)| sum.saturating_add(*amount));
+        let claims = accounts
+            .into_iter()
+            .zip(&leaves)
+            .enumerate()
+            .map(|(index, ((checksummed, _, amount), leaf))| {
+                let claim = DistributorClaim { index, amount: hex_amount(amount), proof: distributor_proof(&layers, leaf) };
+                (checksummed, claim)
+            })
+            .collect();
+        Ok(Self { merkle_root: layers.last().expect("at least one layer")[0], token_total: hex_amount(token_total), claims })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(self)?).with_context(|| format!("Failed to write {:?}", path))?;
+        info!("Wrote {} merkle-distributor claims (root {}) to {:?}", self.claims.len(), self.merkle_root, path);
+        Ok(())
+    }
+}
+
+/// keccak256(abi.encodePacked(uint256 index, address account, uint256 amount))
+fn distributor_leaf(index: usize, address: Address, amount: U256) -> B256 {
+    let mut packed = Vec::with_capacity(84);
+    packed.extend_from_slice(&U256::from(index).to_be_bytes::<32>());
+    packed.extend_from_slice(address.as_slice());
+    packed.extend_from_slice(&amount.to_be_bytes::<32>());
+    keccak256(packed)
+}
+
+/// The layers of the distributor's tree, from the sorted leaves up to the root. Pairs hash in
+/// sorted order; the odd node out of a layer moves up unchanged.
+fn distributor_layers(leaves: &[B256]) -> Vec<Vec<B256>> {
+    let mut layer = leaves.to_vec();
+    layer.sort();
+    layer.dedup();
+    let mut layers = vec![layer];
+    while layers.last().expect("at least one layer").len() > 1 {
+        let next = layers
+            .last()
+            .expect("at least one layer")
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => sorted_hash(*a, *b),
+                [a] => *a,
+                _ => unreachable!("chunks of two"),
+            })
+            .collect();
+        layers.push(next);
+    }
+    layers
+}
+
+fn distributor_proof(layers: &[Vec<B256>], leaf: &B256) -> Vec<B256> {
+    let mut index = layers[0].iter().position(|node| node == leaf).expect("every leaf is in the tree");
+    let mut proof = Vec::new();
+    for layer in &layers[..layers.len() - 1] {
+        if let Some(sibling) = layer.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        index /= 2;
+    }
+    proof
+}
+
+fn sorted_hash(a: B256, b: B256) -> B256 {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    keccak256([first.as_slice(), second.as_slice()].concat())
+}
+
+/// `amount` as an even-length hex string, like ethers' `BigNumber.toHexString()`.
+fn hex_amount(amount: U256) -> String {
+    let hex = format!("{:x}", amount);
+    if hex.len() % 2 == 1 {
+        format!("0x0{}", hex)
+    } else {
+        format!("0x{}", hex)
+    }
+}
+
+/// The proof of the node at `index`: its siblings up to the root.
+fn proof(tree: &[B256], mut index: usize) -> Vec<B256> {
+    let mut proof = Vec::new();
+    while index > 0 {
+        let sibling = if index % 2 == 1 { index + 1 } else { index - 1 };
+        proof.push(tree[sibling]);
+        index = (index - 1) / 2;
+    }
+    proof
+}
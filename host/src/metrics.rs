@@ -0,0 +1,101 @@
+// --- Prometheus Metrics ---
+// Counters and timings of the pipeline, recorded in every mode and exposed on `/metrics` by the
+// daemon (--metrics-addr) and the HTTP API (`serve`), so operators can alert on degraded Subgraph or prover performance.
+
+use anyhow::{Context, Result};
+use axum::http::header::CONTENT_TYPE;
+use axum::routing::get;
+use axum::Router;
+use prometheus::{
+    exponential_buckets, register_histogram, register_int_counter, register_int_counter_vec, register_int_gauge,
+    Histogram, IntCounter, IntCounterVec, IntGauge, TextEncoder, TEXT_FORMAT,
+};
+use std::sync::LazyLock;
+use tokio::net::TcpListener;
+
+// Durations range from seconds (cached fetches) to hours (large proofs).
+fn duration_buckets() -> Vec<f64> {
+    exponential_buckets(1.0, 2.0, 16).unwrap_or_default()
+}
+
+pub static FETCH_DURATION: LazyLock<Histogram> = LazyLock::new(|| {
+    register_histogram!(
+        "top_n_holders_fetch_duration_seconds",
+        "Time spent collecting the candidate holders of a token",
+        duration_buckets()
+    )
+    .expect("metric registration")
+});
+
+pub static SUBGRAPH_PAGES: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!("top_n_holders_subgraph_pages_total", "Subgraph pages fetched")
+        .expect("metric registration")
+});
+
+pub static PREFLIGHT_CALLS: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!("top_n_holders_preflight_calls_total", "Contract calls preflighted via Steel")
+        .expect("metric registration")
+});
+
+pub static PROVER_CYCLES: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!("top_n_holders_prover_cycles", "User cycles of the most recent guest execution")
+        .expect("metric registration")
+});
+
+pub static PROOF_DURATION: LazyLock<Histogram> = LazyLock::new(|| {
+    register_histogram!(
+        "top_n_holders_proof_duration_seconds",
+        "Time spent proving one token",
+        duration_buckets()
+    )
+    .expect("metric registration")
+});
+
+pub static PROOFS_REUSED: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!("top_n_holders_proofs_reused_total", "Proofs reused from the proof cache")
+        .expect("metric registration")
+});
+
+pub static PROOFS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "top_n_holders_proofs_total",
+        "Tokens processed, by outcome (success, failure)",
+        &["outcome"]
+    )
+    .expect("metric registration")
+});
+
+pub static ROUNDS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "top_n_holders_daemon_rounds_total",
+        "Daemon rounds, by outcome (success, failure)",
+        &["outcome"]
+    )
+    .expect("metric registration")
+});
+
+pub fn outcome<T>(result: &Result<T>) -> &'static str {
+    if result.is_ok() {
+        "success"
+    } else {
+        "failure"
+    }
+}
+
+/// Serves `/metrics` until the process exits.
+pub async fn serve(listener: TcpListener) -> Result<()> {
+    axum::serve(listener, routes()).await.context("Metrics server failed")
+}
+
+/// The `/metrics` route, for servers that expose other endpoints as well.
+pub fn routes<S: Clone + Send + Sync + 'static>() -> Router<S> {
+    Router::new().route("/metrics", get(render))
+}
+
+async fn render() -> ([(axum::http::HeaderName, &'static str); 1], String) {
+    let mut body = String::new();
+    if let Err(e) = TextEncoder::new().encode_utf8(&prometheus::gather(), &mut body) {
+        body = format!("# failed to encode metrics: {}\n", e);
+    }
+    ([(CONTENT_TYPE, TEXT_FORMAT)], body)
+}
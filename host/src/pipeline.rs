@@ -0,0 +1,533 @@
+// --- Proving Pipeline ---
+// All stages in one go; the artifacts are still written, so a failed stage can be retried alone.
+// Several tokens are proven one after the other at the same block, sharing the RPC and Subgraph
+// clients (and the preflight's checks of the block). A failing token does not stop the others. With --cache-proofs, a verified proof of
+// the same request is reused instead of proving again. With --history-db, every verified proof is
+// recorded in the proof history. With --registry-contract, every verified proof is sent to the
+// registry contract, and a token only succeeds once its update is confirmed.
+//
+// `Pipeline` is also the entry point for services embedding the library: it proves typed
+// `ProofRequest`s and returns the verified `ProofResult`.
+
+use anyhow::{Context, Result};
+use risc0_steel::alloy::primitives::{Address, B256};
+use risc0_zkvm::sha::Digest;
+use risc0_zkvm::Receipt;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{error, info, warn};
+
+use top_n_holders_core::{address_set_hash, merkle_leaf, merkle_root, ChainName, GuestInput, GuestOutput};
+
+use crate::args::{FetchArgs, RunArgs, SelectionArgs};
+use crate::artifacts::{ArtifactDir, TokenArtifact};
+use crate::backend::{self, ProverBackend};
+use crate::cache::{self, CachedProof, ProofCacheDir};
+use crate::exclusions;
+use crate::labels;
+use crate::failure::{self, Failure};
+use crate::history::History;
+use crate::ipfs::Ipfs;
+use crate::merkle::{DistributorClaims, MerkleExport};
+use crate::metrics;
+use crate::registry::Registry;
+use crate::report::{self, Report};
+use crate::stages::{self, FetchClients, PreflightClients, VerifiedProof};
+use crate::store::ArtifactStore;
+use crate::webhook::{ProofNotification, Webhook};
+
+/// Outcome of proving one token.
+pub type TokenResult = (Address, Result<VerifiedProof>);
+
+/// Outcome of checking one token (`--check`, `--execute-only`).
+pub type CheckResult = (Address, Result<GuestInput>);
+
+/// A proof of the Top-N holders of one token.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProofRequest {
+    pub token: Address,
+    pub n: usize,
+    /// Block to prove at. Defaults to the block selected by the pipeline's `block_tag`.
+    pub block_number: Option<u64>,
+}
+
+/// The verified proof of a `ProofRequest`.
+#[derive(Debug, Clone)]
+pub struct ProofResult {
+    pub token: Address,
+    pub block_number: u64,
+    pub output: GuestOutput,
+    pub journal_digest: Digest, // SHA-256 of the journal, as checked by on-chain verifiers.
+    pub receipt: Receipt,
+}
+
+/// Runs the stages with one set of options and shared clients.
+pub struct Pipeline {
+    args: RunArgs,
+    clients: FetchClients,
+    preflight_clients: PreflightClients,
+    backend: Box<dyn ProverBackend>,
+    webhook: Option<Webhook>,
+    ipfs: Option<Ipfs>,
+    store: Option<ArtifactStore>,
+    registry: Option<Registry>,
+}
+
+impl Pipeline {
+    pub async fn new(args: RunArgs) -> Result<Self> {
+        stages::check_chunk_options(args.prover.chunk_size, args.selection.commit_hhi, args.selection.commit_merkle_root)?;
+        stages::check_chunk_prover_options(&args.prover)?;
+        let clients = FetchClients::new(&args.fetch).await?;
+        let preflight_clients = PreflightClients::new(&args.fetch.rpc_url);
+        let backend = backend::from_args(&args.prover)?;
+        let webhook = args.webhook_url.clone().map(Webhook::new).transpose()?;
+        let ipfs = args.ipfs_api.clone().map(Ipfs::new).transpose()?;
+        let store = args
+            .artifact_store
+            .clone()
+            .map(|url| ArtifactStore::new(url, &args.artifact_store_option))
+            .transpose()?;
+        let default_rpc_url = args.fetch.rpc_url.first().map(String::as_str);
+        let registry = Registry::new(&args.registry, default_rpc_url, args.prover.receipt_kind).await?;
+        Ok(Self { args, clients, preflight_clients, backend, webhook, ipfs, store, registry })
+    }
+
+    /// Proves with `backend` instead of the one selected by --prover-backend.
+    pub fn with_backend(mut self, backend: Box<dyn ProverBackend>) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    pub fn args(&self) -> &RunArgs {
+        &self.args
+    }
+
+    /// The chain the pipeline proves on, as given or detected by --chain-spec.
+    pub fn chain(&self) -> ChainName {
+        self.clients.chain()
+    }
+
+    /// Resolves `block_number`, or the block selected by the configured block tag.
+    pub async fn resolve_block(&self, block_number: Option<u64>) -> Result<u64> {
+        stages::resolve_block(&self.clients, block_number, self.args.fetch.block_tag).await
+    }
+
+    /// Proves one request, with the artifacts in `artifacts_dir`.
+    pub async fn prove(&self, request: &ProofRequest, artifacts_dir: &Path) -> Result<ProofResult> {
+        check_n(request.n, &self.args.fetch)?;
+        let block_number = self.resolve_block(request.block_number).await?;
+        let selection = SelectionArgs { n_top_holders: Some(request.n), ..self.args.selection.clone() };
+        let artifacts = ArtifactDir::new(artifacts_dir);
+        let proof = self.prove_token(&selection, request.token, block_number, &artifacts).await?;
+        Ok(ProofResult {
+            token: request.token,
+            block_number,
+            output: proof.output,
+            journal_digest: proof.journal_digest,
+            receipt: artifacts.load_receipt()?,
+        })
+    }
+
+    /// Proves every configured token at `block_number`, with the artifacts below `artifacts_root`.
+    pub async fn prove_tokens(&self, block_number: u64, artifacts_root: &Path) -> Vec<TokenResult> {
+        let tokens = &self.args.fetch.erc20_address;
+        let mut results = Vec::with_capacity(tokens.len());
+        for (i, &token) in tokens.iter().enumerate() {
+            let artifacts = self.token_artifacts(i, artifacts_root);
+            let result = self.prove_token(&self.args.selection, token, block_number, &artifacts).await;
+            let result = result.and_then(|proof| {
+                self.write_report(token, &artifacts, &proof)?;
+                self.write_merkle_tree(token, &artifacts, &proof)?;
+                self.write_distributor_claims(token, &artifacts, &proof)?;
+                Ok(proof)
+            });
+            if let Ok(proof) = &result {
+                self.publish(token, &artifacts, proof).await;
+            }
+            let result = match result {
+                Ok(proof) => self.update_registry(token, &artifacts, &proof).await.map(|()| proof),
+                Err(e) => Err(e),
+            };
+            results.push((token, result));
+        }
+        results
+    }
+
+    /// Writes the --report-out report of a token the guest verified.
+    fn write_report(&self, token: Address, artifacts: &ArtifactDir, proof: &VerifiedProof) -> Result<()> {
+        let Some(report_out) = &self.args.report_out else {
+            return Ok(());
+        };
+        if !proof.output.verification_succeeded {
+            return Ok(());
+        }
+        let path = self.output_path(report_out, token);
+        Report::new(token, self.chain(), artifacts, proof)?.write(self.args.report_format, &path)
+    }
+
+    /// Writes the --merkle-out tree of a token the guest verified.
+    fn write_merkle_tree(&self, token: Address, artifacts: &ArtifactDir, proof: &VerifiedProof) -> Result<()> {
+        let Some(merkle_out) = &self.args.merkle_out else {
+            return Ok(());
+        };
+        if !proof.output.verification_succeeded {
+            return Ok(());
+        }
+        let path = self.output_path(merkle_out, token);
+        MerkleExport::new(&proof.output, artifacts, self.args.selection.merkle_leaves)?.write(&path)
+    }
+
+    /// Writes the --distributor-out claims of a token the guest verified.
+    fn write_distributor_claims(&self, token: Address, artifacts: &ArtifactDir, proof: &VerifiedProof) -> Result<()> {
+        let Some(distributor_out) = &self.args.distributor_out else {
+            return Ok(());
+        };
+        if !proof.output.verification_succeeded {
+            return Ok(());
+        }
+        let path = self.output_path(distributor_out, token);
+        DistributorClaims::new(&proof.output, artifacts)?.write(&path)
+    }
+
+    /// Publishes the bundle of a token the guest verified: pinned to --ipfs-api (printing its CID)
+    /// and uploaded to --artifact-store. Failures are only logged.
+    async fn publish(&self, token: Address, artifacts: &ArtifactDir, proof: &VerifiedProof) {
+        if (self.ipfs.is_none() && self.store.is_none()) || !proof.output.verification_succeeded {
+            return;
+        }
+        let report = match Report::new(token, self.chain(), artifacts, proof) {
+            Ok(report) => report,
+            Err(e) => {
+                warn!("Failed to publish the proof of {}: {:#}", token, e);
+                return;
+            }
+        };
+        if let Some(ipfs) = &self.ipfs {
+            match ipfs.pin_proof(artifacts, &report).await {
+                Ok(cid) => println!("IPFS bundle of {}: ipfs://{}", token, cid),
+                Err(e) => warn!("Failed to pin the proof of {} to IPFS: {:#}", token, e),
+            }
+        }
+        if let Some(store) = &self.store {
+            let n = self.args.selection.n_top_holders.unwrap_or(report.n);
+            if let Err(e) = store.upload_proof(n, artifacts, &report).await {
+                warn!("Failed to upload the proof of {} to the artifact store: {:#}", token, e);
+            }
+        }
+    }
+
+    /// Sends the proof of a token the guest verified to --registry-contract.
+    async fn update_registry(&self, token: Address, artifacts: &ArtifactDir, proof: &VerifiedProof) -> Result<()> {
+        let Some(registry) = &self.registry else {
+            return Ok(());
+        };
+        if !proof.output.verification_succeeded {
+            return Ok(());
+        }
+        let receipt = artifacts.load_receipt()?;
+        registry
+            .update(&receipt)
+            .await
+            .with_context(|| format!("Failed to update the registry with the proof of {}", token))
+    }
+
+    /// Where an output file of `token` goes: `path`, or with several tokens `report::token_path`.
+    fn output_path(&self, path: &Path, token: Address) -> PathBuf {
+        match self.args.fetch.erc20_address.len() {
+            1 => path.to_path_buf(),
+            _ => report::token_path(path, token),
+        }
+    }
+
+    /// Fetches and preflights every configured token, which includes the guest's ranking checks,
+    /// without proving (`--check`). With --execute-only, the guest is executed as well.
+    pub async fn check_tokens(&self, block_number: u64, artifacts_root: &Path) -> Vec<CheckResult> {
+        let tokens = &self.args.fetch.erc20_address;
+        let mut results = Vec::with_capacity(tokens.len());
+        for (i, &token) in tokens.iter().enumerate() {
+            let artifacts = self.token_artifacts(i, artifacts_root);
+            let result = async {
+                stages::fetch(&self.args.fetch, &self.clients, token, block_number, &artifacts)
+                    .await
+                    .map_err(|e| failure::tag(e, Failure::DataSource))?;
+                let guest_input = stages::preflight(&self.preflight_clients, &self.args.selection, self.args.prover.chunk_size, &artifacts)
+                    .await
+                    .map_err(|e| failure::tag(e, Failure::DataSource))?;
+                if self.args.prover.execute_only {
+                    stages::execute(&artifacts, (&self.args.prover).into()).map_err(|e| failure::tag(e, Failure::Prover))?;
+                }
+                Ok(guest_input)
+            }
+            .await;
+            if let Err(e) = &result {
+                error!("Checking {} failed: {:#}", token, e);
+            }
+            results.push((token, result));
+        }
+        results
+    }
+
+    /// The artifacts of the `index`-th configured token, logging which token is processed.
+    fn token_artifacts(&self, index: usize, artifacts_root: &Path) -> ArtifactDir {
+        let tokens = &self.args.fetch.erc20_address;
+        if tokens.len() > 1 {
+            info!("=== Token {}/{}: {} ===", index + 1, tokens.len(), tokens[index]);
+            ArtifactDir::for_token(artifacts_root, tokens[index])
+        } else {
+            ArtifactDir::new(artifacts_root)
+        }
+    }
+
+    /// Runs all stages for one token, recording the outcome in the metrics and the webhook.
+    async fn prove_token(
+        &self,
+        selection: &SelectionArgs,
+        token: Address,
+        block_number: u64,
+        artifacts: &ArtifactDir,
+    ) -> Result<VerifiedProof> {
+        let result = self.run_stages(selection, token, block_number, artifacts).await;
+        metrics::PROOFS.with_label_values(&[metrics::outcome(&result)]).inc();
+        match &result {
+            Ok(proof) => self.record_history(token, artifacts, proof),
+            Err(e) => error!("Proving {} failed: {:#}", token, e),
+        }
+        if let Some(webhook) = &self.webhook {
+            let n = selection.n_top_holders.unwrap_or_default();
+            webhook
+                .notify(&ProofNotification::new(token, self.chain(), block_number, n, &result))
+                .await;
+        }
+        result
+    }
+
+    async fn run_stages(
+        &self,
+        selection: &SelectionArgs,
+        token: Address,
+        block_number: u64,
+        artifacts: &ArtifactDir,
+    ) -> Result<VerifiedProof> {
+        let proof_cache = match selection.n_top_holders {
+            Some(n) if self.args.cache_proofs => Some(self.proof_cache(token, n)),
+            _ => None,
+        };
+        if let Some(proof_cache) = &proof_cache {
+            if let Some(proof) = self.reuse_proof(proof_cache, selection, block_number, artifacts)? {
+                return Ok(proof);
+            }
+        }
+
+        let timer = metrics::FETCH_DURATION.start_timer();
+        stages::fetch(&self.args.fetch, &self.clients, token, block_number, artifacts)
+            .await
+            .map_err(|e| failure::tag(e, Failure::DataSource))?;
+        timer.observe_duration();
+        stages::preflight(&self.preflight_clients, selection, self.args.prover.chunk_size, artifacts)
+            .await
+            .map_err(|e| failure::tag(e, Failure::DataSource))?;
+        stages::prove(self.backend.as_ref(), &self.args.prover, artifacts)
+            .map_err(|e| failure::tag(e, Failure::Prover))?;
+        let proof = stages::verify(artifacts).map_err(|e| failure::tag(e, Failure::Prover))?;
+
+        if let Some(proof_cache) = &proof_cache {
+            let cached = CachedProof::new(artifacts.load_receipt()?, artifacts.load_token()?);
+            if let Err(e) = cached.store(&proof_cache.entry_path(block_number)) {
+                warn!("Failed to cache the proof: {:#}", e);
+            }
+        }
+        Ok(proof)
+    }
+
+    /// Records a verified proof in the --history-db. A failure to record does not fail the proof.
+    fn record_history(&self, token: Address, artifacts: &ArtifactDir, proof: &VerifiedProof) {
+        let Some(history_db) = &self.args.history_db else {
+            return;
+        };
+        let recorded = History::open(history_db).and_then(|mut history| history.record(self.chain(), token, artifacts, proof));
+        if let Err(e) = recorded {
+            warn!("Failed to record the proof of {} in the history: {:#}", token, e);
+        }
+    }
+
+    /// The last block with a recorded verified proof of every configured token, if --history-db is set.
+    pub fn last_recorded_block(&self) -> Result<Option<u64>> {
+        let (Some(history_db), Some(n)) = (&self.args.history_db, self.args.selection.n_top_holders) else {
+            return Ok(None);
+        };
+        History::open(history_db)?.last_block(self.chain(), &self.args.fetch.erc20_address, n)
+    }
+
+    fn proof_cache(&self, token: Address, n: usize) -> ProofCacheDir {
+        let cache_dir = self.args.fetch.cache_dir.clone().unwrap_or_else(cache::default_cache_dir);
+        let receipt_kind = format!("{:?}", self.args.prover.receipt_kind).to_lowercase();
+        ProofCacheDir::new(&cache_dir, self.chain().name(), token, n, &receipt_kind)
+    }
+
+    /// Copies a cached proof of `block_number` into `artifacts` and verifies it, if there is one
+    /// for the current guest and exclusions.
+    fn reuse_proof(
+        &self,
+        proof_cache: &ProofCacheDir,
+        selection: &SelectionArgs,
+        block_number: u64,
+        artifacts: &ArtifactDir,
+    ) -> Result<Option<VerifiedProof>> {
+        let path = proof_cache.entry_path(block_number);
+        let Some(cached) = CachedProof::load(&path)? else {
+            return Ok(None);
+        };
+        // A proof of another guest build, or with other exclusions, answers a different question.
+        let output = match stages::verify_receipt(&cached.receipt) {
+            Ok((output, _)) => output,
+            Err(e) => {
+                warn!("Ignoring cached proof {:?}, it does not verify against the current guest: {:#}", path, e);
+                return Ok(None);
+            }
+        };
+        let labels = match &selection.labels_file {
+            Some(labels_file) => labels::load_labels(labels_file)?,
+            None => Default::default(),
+        };
+        let excluded_addresses = exclusions::excluded_addresses(selection, &labels)?;
+        if output.excluded_addresses != excluded_addresses {
+            info!("Not reusing cached proof {:?}: it was proven with other exclusions", path);
+            return Ok(None);
+        }
+        if output.fork_overrides != selection.fork_override {
+            info!("Not reusing cached proof {:?}: it was proven with other fork overrides", path);
+            return Ok(None);
+        }
+        let sanctioned_addresses = exclusions::sanctioned_addresses(selection)?;
+        let sanctions_list_hash = (!sanctioned_addresses.is_empty()).then(|| address_set_hash(&sanctioned_addresses));
+        if output.sanctions_list_hash != sanctions_list_hash {
+            info!("Not reusing cached proof {:?}: it was proven with another sanctions list", path);
+            return Ok(None);
+        }
+        if output.top_n_hhi.is_some() != selection.commit_hhi {
+            info!("Not reusing cached proof {:?}: it was proven with another --commit-hhi", path);
+            return Ok(None);
+        }
+        if output.top_n_merkle_root != self.merkle_root(&output, selection, cached.token.as_ref()) {
+            info!("Not reusing cached proof {:?}: it was proven with another --commit-merkle-root or --merkle-leaves", path);
+            return Ok(None);
+        }
+
+        info!("Reusing cached proof {:?} (block {})", path, block_number);
+        metrics::PROOFS_REUSED.inc();
+        artifacts.store_receipt(&cached.receipt)?;
+        if let Some(token) = &cached.token {
+            artifacts.store_token(token)?;
+        }
+        stages::verify(artifacts).map(Some)
+    }
+
+    /// The Merkle root the guest would commit for the holders of `output` with the current
+    /// options, given the balances of the cached `token.json`.
+    fn merkle_root(&self, output: &GuestOutput, selection: &SelectionArgs, token: Option<&TokenArtifact>) -> Option<B256> {
+        if !selection.commit_merkle_root {
+            return None;
+        }
+        let balances = &token?.balances;
+        let leaves: Vec<B256> = output
+            .final_top_n_addresses
+            .iter()
+            .map(|address| merkle_leaf(selection.merkle_leaves, *address, balances.get(address).copied().unwrap_or_default()))
+            .collect();
+        Some(merkle_root(&leaves))
+    }
+}
+
+pub async fn run(args: &RunArgs) -> Result<()> {
+    require_tokens(args)?;
+    require_n(args)?;
+    let pipeline = Pipeline::new(args.clone()).await?;
+    let block_number = pipeline.resolve_block(args.fetch.block_number).await?;
+    let artifacts_root = &args.fetch.artifacts.artifacts_dir;
+    if args.check || args.prover.execute_only {
+        let results = pipeline.check_tokens(block_number, artifacts_root).await;
+        let failures: Vec<&anyhow::Error> = results.iter().filter_map(|(_, result)| result.as_ref().err()).collect();
+        if !failures.is_empty() {
+            let err = anyhow::anyhow!("{} of {} token(s) would fail to prove", failures.len(), results.len());
+            return Err(tag_common(err, &failures));
+        }
+        info!("Check passed for {} token(s) at block {}; the proofs would succeed.", results.len(), block_number);
+        return Ok(());
+    }
+    let results = pipeline.prove_tokens(block_number, artifacts_root).await;
+    check_results(block_number, &results)
+}
+
+/// Rejects `--check` and `--execute-only` in the modes that keep proving (daemon, serve).
+pub fn reject_dry_run(args: &RunArgs, mode: &str) -> Result<()> {
+    anyhow::ensure!(!args.check, "--check is not supported by `{}`; use `run --check`", mode);
+    anyhow::ensure!(
+        !args.prover.execute_only,
+        "--execute-only is not supported by `{}`; use `run --execute-only`",
+        mode
+    );
+    Ok(())
+}
+
+pub fn require_tokens(args: &RunArgs) -> Result<()> {
+    anyhow::ensure!(!args.fetch.erc20_address.is_empty(), "At least one --erc20-address is required");
+    Ok(())
+}
+
+pub fn require_n(args: &RunArgs) -> Result<()> {
+    let n = args.selection.n_top_holders.context("--n-top-holders is required")?;
+    check_n(n, &args.fetch)
+}
+
+/// Rejects an N that the holder source cannot support, before any RPC or Subgraph work. The
+/// candidate count itself is only known after the fetch, see `stages::preflight`.
+pub fn check_n(n: usize, fetch: &FetchArgs) -> Result<()> {
+    anyhow::ensure!(n > 0, "N must be at least 1");
+    anyhow::ensure!(
+        n as u64 <= fetch.max_candidates,
+        "N ({}) exceeds --max-candidates ({}): the fetch keeps too few candidates to rank the Top {}. Raise --max-candidates.",
+        n,
+        fetch.max_candidates,
+        n
+    );
+    Ok(())
+}
+
+/// Logs a summary of a multi-token run and fails if any token failed.
+pub fn check_results(block_number: u64, results: &[TokenResult]) -> Result<()> {
+    if results.len() > 1 {
+        info!("Summary (block {}):", block_number);
+        for (token, result) in results {
+            match result {
+                Ok(proof) => match &proof.output.failure {
+                    None => info!("  {}: verified (Top {})", token, proof.output.final_top_n_addresses.len()),
+                    Some(failure) => info!("  {}: verification failed: {}", token, failure),
+                },
+                Err(e) => info!("  {}: error: {:#}", token, e),
+            }
+        }
+    }
+    let failures: Vec<&anyhow::Error> = results.iter().filter_map(|(_, result)| result.as_ref().err()).collect();
+    if !failures.is_empty() {
+        let err = anyhow::anyhow!("{} of {} token(s) failed", failures.len(), results.len());
+        return Err(tag_common(err, &failures));
+    }
+    let unverified = results
+        .iter()
+        .filter(|(_, result)| result.as_ref().is_ok_and(|proof| !proof.output.verification_succeeded))
+        .count();
+    if unverified > 0 {
+        let err = anyhow::anyhow!("The guest rejected the ranking of {} of {} token(s)", unverified, results.len());
+        return Err(failure::tag(err, Failure::GuestVerification));
+    }
+    Ok(())
+}
+
+/// Tags `err` with the failure kind shared by all `failures`, if they share one.
+fn tag_common(err: anyhow::Error, failures: &[&anyhow::Error]) -> anyhow::Error {
+    let kinds: Vec<Option<Failure>> = failures.iter().map(|e| failure::failure_kind(e)).collect();
+    match kinds.first() {
+        Some(&Some(kind)) if kinds.iter().all(|k| *k == Some(kind)) => failure::tag(err, kind),
+        _ => err,
+    }
+}
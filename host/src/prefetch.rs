@@ -0,0 +1,146 @@
+// --- Storage Prefetching ---
+// Steel preflights calls one after the other on a single environment, and every `balanceOf`
+// waits for its own storage reads. Before the individual preflight, the slots each call reads are
+// looked up (eth_createAccessList) and fetched (eth_getStorageAt) concurrently; the provider handed
+// to Steel then answers those reads from memory. Prefetching is only a warm-up: a slot that could
+// not be prefetched is read by Steel as usual.
+//
+// With Multicall3, the access list of a whole `aggregate3` batch is looked up in one request
+// instead of one per holder.
+//
+// Generic over the network, since OP Stack environments need an OP provider.
+
+use alloy::network::{Ethereum, Network, TransactionBuilder};
+use alloy::providers::{DynProvider, Provider, ProviderCall, RootProvider, RpcWithBlock};
+use alloy::sol_types::SolCall;
+use anyhow::{Context, Result};
+use futures::{stream, StreamExt};
+use risc0_steel::alloy::primitives::{Address, Bytes, StorageValue, U256};
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock};
+use tracing::{info, warn};
+
+use crate::rate_limit::RateLimiter;
+use crate::IERC20;
+
+/// A provider serving prefetched storage slots from memory.
+///
+/// Only used for one Steel environment, so the slots are served regardless of the requested
+/// block: they were all fetched at the environment's block.
+#[derive(Clone)]
+pub struct PrefetchProvider<N: Network = Ethereum> {
+    inner: DynProvider<N>,
+    storage: Arc<RwLock<HashMap<(Address, U256), StorageValue>>>,
+}
+
+impl<N: Network> PrefetchProvider<N> {
+    pub fn new(inner: DynProvider<N>) -> Self {
+        Self { inner, storage: Arc::default() }
+    }
+
+    /// Fetches the storage read by `balanceOf(holder)` for every holder, with up to `concurrency`
+    /// requests in flight.
+    pub async fn prefetch_balances(
+        &self,
+        token: Address,
+        holders: &[Address],
+        block_number: u64,
+        concurrency: usize,
+        rate_limiter: &RateLimiter,
+    ) {
+        let results: Vec<Result<Vec<((Address, U256), StorageValue)>>> = stream::iter(holders)
+            .map(|&holder| {
+                let input = IERC20::balanceOfCall { account: holder }.abi_encode().into();
+                let label = format!("balanceOf({})", holder);
+                async move { self.fetch_call_slots(token, input, &label, block_number, 1, rate_limiter).await }
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+
+        let mut failed = 0;
+        let mut storage = self.storage.write().unwrap_or_else(PoisonError::into_inner);
+        for result in results {
+            match result {
+                Ok(slots) => storage.extend(slots),
+                Err(e) => {
+                    warn!("{:#}", e);
+                    failed += 1;
+                }
+            }
+        }
+        info!("Prefetched {} storage slots for {} holders ({} failed)", storage.len(), holders.len(), failed);
+    }
+
+    /// Fetches the storage read by calling `to` with `input` (e.g. a Multicall3 `aggregate3`),
+    /// with up to `concurrency` slots fetched at once. Returns the number of slots.
+    pub async fn prefetch_call(
+        &self,
+        to: Address,
+        input: Bytes,
+        label: &str,
+        block_number: u64,
+        concurrency: usize,
+        rate_limiter: &RateLimiter,
+    ) -> Result<usize> {
+        let slots = self.fetch_call_slots(to, input, label, block_number, concurrency, rate_limiter).await?;
+        let count = slots.len();
+        self.storage.write().unwrap_or_else(PoisonError::into_inner).extend(slots);
+        Ok(count)
+    }
+
+    async fn fetch_call_slots(
+        &self,
+        to: Address,
+        input: Bytes,
+        label: &str,
+        block_number: u64,
+        concurrency: usize,
+        rate_limiter: &RateLimiter,
+    ) -> Result<Vec<((Address, U256), StorageValue)>> {
+        let call = N::TransactionRequest::default().with_to(to).with_input(input);
+        rate_limiter.wait().await;
+        let access_list = self
+            .inner
+            .create_access_list(&call)
+            .number(block_number)
+            .await
+            .with_context(|| format!("Failed to look up the storage read by {}", label))?
+            .access_list;
+
+        let keys = access_list.0.into_iter().flat_map(|item| {
+            let address = item.address;
+            item.storage_keys.into_iter().map(move |key| (address, U256::from_be_bytes(key.0)))
+        });
+        stream::iter(keys)
+            .map(|(address, key)| async move {
+                rate_limiter.wait().await;
+                let value = self
+                    .inner
+                    .get_storage_at(address, key)
+                    .number(block_number)
+                    .await
+                    .with_context(|| format!("Failed to prefetch storage of {} for {}", address, label))?;
+                Ok(((address, key), value))
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect::<Vec<Result<_>>>()
+            .await
+            .into_iter()
+            .collect()
+    }
+}
+
+impl<N: Network> Provider<N> for PrefetchProvider<N> {
+    fn root(&self) -> &RootProvider<N> {
+        self.inner.root()
+    }
+
+    fn get_storage_at(&self, address: Address, key: U256) -> RpcWithBlock<(Address, U256), StorageValue> {
+        let prefetched = self.storage.read().unwrap_or_else(PoisonError::into_inner).get(&(address, key)).copied();
+        match prefetched {
+            Some(value) => RpcWithBlock::new_provider(move |_block| ProviderCall::ready(Ok(value))),
+            None => self.inner.get_storage_at(address, key),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+// --- Progress Reporting ---
+// Progress bars for the long-running parts of the pipeline: Subgraph pages, balance preflight
+// and proving. Bars are drawn on stderr (and not at all if it is not a terminal); log lines are
+// written through `LogWriter`, which moves the bars out of the way while a line is printed.
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+static BARS: LazyLock<MultiProgress> = LazyLock::new(MultiProgress::new);
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Turns progress bars off (--no-progress); later bars are created hidden.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// A bar for `len` known steps, e.g. balances to preflight.
+pub fn counter(len: u64, message: &'static str) -> ProgressBar {
+    add(ProgressBar::new(len), "{msg} [{bar:40}] {human_pos}/{human_len} ({eta} left)", message)
+}
+
+/// A counter without a known total, e.g. holders fetched from a Subgraph.
+pub fn items(message: &'static str, unit: &'static str) -> ProgressBar {
+    let template = format!("{{spinner}} {{msg}}: {{human_pos}} {} ({{per_sec}}) [{{elapsed}}]", unit);
+    add(ProgressBar::new_spinner(), &template, message)
+}
+
+/// A spinner for work without measurable progress, e.g. a running prover.
+pub fn spinner(message: String) -> ProgressBar {
+    let bar = add(ProgressBar::new_spinner(), "{spinner} {msg} [{elapsed}]", message);
+    bar.enable_steady_tick(Duration::from_millis(250));
+    bar
+}
+
+fn add(bar: ProgressBar, template: &str, message: impl Into<std::borrow::Cow<'static, str>>) -> ProgressBar {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return ProgressBar::hidden();
+    }
+    let style = ProgressStyle::with_template(template).unwrap_or_else(|_| ProgressStyle::default_bar());
+    BARS.add(bar.with_style(style).with_message(message))
+}
+
+/// Log output (stdout) that suspends the progress bars while writing.
+pub struct LogWriter;
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        BARS.suspend(|| io::stdout().write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
@@ -0,0 +1,29 @@
+// --- RPC Rate Limiting ---
+// Paces preflight RPC requests below --rpc-rate-limit requests per second. Shared by concurrent
+// tasks, so the limit holds for the run as a whole.
+
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::{Interval, MissedTickBehavior};
+
+pub struct RateLimiter {
+    interval: Option<Mutex<Interval>>, // None: unlimited.
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: Option<u32>) -> Self {
+        let interval = requests_per_second.map(|requests_per_second| {
+            let mut interval = tokio::time::interval(Duration::from_secs(1) / requests_per_second.max(1));
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            Mutex::new(interval)
+        });
+        Self { interval }
+    }
+
+    /// Waits until the next request may be sent.
+    pub async fn wait(&self) {
+        if let Some(interval) = &self.interval {
+            interval.lock().await.tick().await;
+        }
+    }
+}
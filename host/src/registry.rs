@@ -0,0 +1,119 @@
+// --- Registry Updates ---
+// With --registry-contract, every proof that `run` or the daemon verifies is sent on-chain right
+// away, as `updateTopN(bytes journal, bytes seal)` (or --registry-function) on a consumer or
+// registry contract, so that the contract always holds the latest verified Top-N. `submit` does
+// the same for one stored receipt by hand; this runs unattended:
+// - The transactions of a run share one signer whose nonces are tracked locally, so the updates
+//   of several tokens go out back to back without waiting for each other's inclusion.
+// - Fees are estimated per transaction; above --registry-max-fee-gwei an update is not sent.
+// - An update only counts once its transaction has --registry-confirmations confirmations. A
+//   revert or a timeout fails the token, like a failed proof.
+
+use alloy::network::TransactionBuilder;
+use alloy::providers::{DynProvider, Provider, ProviderBuilder};
+use alloy::rpc::types::TransactionRequest;
+use alloy::signers::local::PrivateKeySigner;
+use anyhow::{Context, Result};
+use risc0_steel::alloy::primitives::Address;
+use risc0_zkvm::Receipt;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::args::{ReceiptKind, RegistryArgs};
+use crate::stages;
+
+const WEI_PER_GWEI: f64 = 1e9;
+
+pub struct Registry {
+    provider: DynProvider,
+    contract: Address,
+    function: String,
+    confirmations: u64,
+    timeout: Duration,
+    max_fee_per_gas: Option<u128>, // Wei.
+    gas_limit: Option<u64>,
+}
+
+impl Registry {
+    /// The registry configured by `args`, if any, sending through `default_rpc_url` unless
+    /// --registry-rpc-url is given.
+    pub async fn new(args: &RegistryArgs, default_rpc_url: Option<&str>, receipt_kind: ReceiptKind) -> Result<Option<Self>> {
+        let Some(contract) = args.registry_contract else {
+            return Ok(None);
+        };
+        if receipt_kind != ReceiptKind::Groth16 {
+            warn!("--registry-contract needs Groth16 receipts to verify on-chain, except in dev mode; use --receipt-kind groth16");
+        }
+        let private_key = args.registry_private_key.as_deref().context("--registry-contract needs --registry-private-key")?;
+        let signer: PrivateKeySigner = private_key.parse().context("Invalid --registry-private-key")?;
+        let sender = signer.address();
+        let rpc_url = args
+            .registry_rpc_url
+            .as_deref()
+            .or(default_rpc_url)
+            .context("--registry-contract needs --registry-rpc-url or --rpc-url")?;
+        let provider = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .with_gas_estimation()
+            .with_cached_nonce_management()
+            .fetch_chain_id()
+            .wallet(signer)
+            .connect(rpc_url)
+            .await
+            .with_context(|| format!("Failed to connect to the registry RPC endpoint {}", rpc_url))?
+            .erased();
+        info!("Verified proofs will update the registry {} from {}", contract, sender);
+        Ok(Some(Self {
+            provider,
+            contract,
+            function: args.registry_function.clone(),
+            confirmations: args.registry_confirmations,
+            timeout: args.registry_timeout,
+            max_fee_per_gas: args.registry_max_fee_gwei.map(|gwei| (gwei * WEI_PER_GWEI) as u128),
+            gas_limit: args.registry_gas_limit,
+        }))
+    }
+
+    /// Sends the journal and seal of `receipt` to the registry and waits for the confirmations.
+    pub async fn update(&self, receipt: &Receipt) -> Result<()> {
+        let (function, calldata) = stages::submit_calldata(&self.function, receipt)?;
+        let mut tx = TransactionRequest::default().with_to(self.contract).with_input(calldata);
+        if let Some(gas_limit) = self.gas_limit {
+            tx = tx.with_gas_limit(gas_limit);
+        }
+        if let Some(max_fee_per_gas) = self.max_fee_per_gas {
+            let fees = self.provider.estimate_eip1559_fees().await.context("Failed to estimate the fees")?;
+            anyhow::ensure!(
+                fees.max_fee_per_gas <= max_fee_per_gas,
+                "Not updating the registry: the fee of {:.2} gwei is above --registry-max-fee-gwei",
+                fees.max_fee_per_gas as f64 / WEI_PER_GWEI
+            );
+            tx = tx
+                .with_max_fee_per_gas(fees.max_fee_per_gas)
+                .with_max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
+        }
+
+        let pending = self
+            .provider
+            .send_transaction(tx)
+            .await
+            .with_context(|| format!("Failed to send the {} transaction", function.signature()))?;
+        let tx_hash = *pending.tx_hash();
+        info!("Registry update sent: {} (waiting for {} confirmations)", tx_hash, self.confirmations);
+        let tx_receipt = pending
+            .with_required_confirmations(self.confirmations)
+            .with_timeout(Some(self.timeout))
+            .get_receipt()
+            .await
+            .with_context(|| format!("Registry update {} was not confirmed", tx_hash))?;
+        anyhow::ensure!(tx_receipt.status(), "Registry update {} reverted", tx_hash);
+        info!(
+            "Registry {} updated in block {:?} (transaction {}, {} gas)",
+            self.contract,
+            tx_receipt.block_number,
+            tx_hash,
+            tx_receipt.gas_used
+        );
+        Ok(())
+    }
+}
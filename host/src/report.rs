@@ -0,0 +1,263 @@
+// --- Reports ---
+// `--report-out` writes the verified Top-N to a file, separate from the logs:
+// - `csv`: rank, address, balance in token units, percent of the total supply and account type
+//   (eoa, contract or proxy, see `accounts.rs`), --labels-file label and the ENS name on mainnet,
+//   for analysts.
+// - `json`: a versioned document for dashboards and data warehouses, with the token metadata, the
+//   proven block, the holders, concentration stats and where the receipt is.
+// Like the summary, the balances are the preflighted ones from `token.json`, which the guest read
+// at the proven block.
+//
+// The concentration stats are over the verified holders' balances: the Gini coefficient (0 for
+// equal balances, approaching 1 when one holder has everything) and the Herfindahl-Hirschman index
+// of their shares of the ranked supply (total supply less the excluded balances), on the 0-10,000
+// scale. With --commit-hhi the HHI is the one the guest committed, i.e. proven.
+//
+// The JSON schema only changes compatibly (new fields) within a `version`. Amounts are decimal
+// strings in base units, since they exceed the integers most JSON consumers can represent.
+
+use anyhow::{Context, Result};
+use risc0_steel::alloy::primitives::{Address, B256, U256};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use top_n_holders_core::{hhi_ppm, ChainName};
+
+use crate::accounts::{AccountInfo, AccountKind};
+use crate::args::ReportFormat;
+use crate::artifacts::ArtifactDir;
+use crate::stages::VerifiedProof;
+use crate::summary::{format_amount, percent};
+
+/// Version of the JSON report schema.
+pub const REPORT_VERSION: u32 = 1;
+
+/// Decimals of the percentages; a whole percent is far too coarse for the tail of a Top-N.
+const PERCENT_PRECISION: u32 = 6;
+
+/// The JSON report of a verified proof.
+#[derive(Serialize, Debug, Clone)]
+pub struct Report {
+    pub version: u32,
+    pub token: TokenInfo,
+    pub block: BlockInfo,
+    pub n: usize,
+    pub excluded_addresses: Vec<Address>, // Addresses the ranking was proven without.
+    pub excluded_addresses_hash: B256,    // Committed by the guest, see `address_set_hash`.
+    pub sanctions_list_hash: Option<B256>, // Of the list no holder is on (--sanctions-file).
+    pub merkle_root: Option<B256>,         // Of the holders, committed with --commit-merkle-root.
+    pub holders: Vec<RankedHolder>,
+    pub concentration: Concentration,
+    pub proof: ProofInfo,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TokenInfo {
+    pub address: Address,
+    pub chain: ChainName,
+    pub symbol: Option<String>,
+    pub decimals: Option<u8>,
+    pub total_supply: String, // Base units.
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BlockInfo {
+    pub number: u64,
+    pub commitment_digest: B256, // The block hash (or beacon root) the proof commits to.
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RankedHolder {
+    pub rank: usize,
+    pub address: Address,
+    pub balance: String,                   // Base units.
+    pub amount: String,                    // Token units, or base units if the decimals are unknown.
+    pub percent_of_supply: Option<String>, // None for a zero supply.
+    pub account: Option<AccountKind>,      // None if it could not be classified.
+    pub proxy_implementation: Option<Address>,
+    pub ens_name: Option<String>,          // The verified primary name, on mainnet.
+    pub label: Option<String>,             // From --labels-file.
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Concentration {
+    pub top_n_balance: String, // Base units.
+    pub top_n_percent_of_supply: Option<String>,
+    pub gini: Option<f64>, // None without any balance.
+    pub hhi: f64,          // 0-10,000.
+    pub hhi_proven: bool,  // Committed by the guest (--commit-hhi).
+    pub contracts: Option<usize>, // Holders that are contracts; None if none could be classified.
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ProofInfo {
+    pub receipt: PathBuf,
+    pub image_id: String,
+    pub journal_digest: String, // SHA-256 of the journal, as checked by on-chain verifiers.
+}
+
+impl Report {
+    /// The report of `proof`, with the token metadata and balances from `artifacts`.
+    pub fn new(token: Address, chain: ChainName, artifacts: &ArtifactDir, proof: &VerifiedProof) -> Result<Self> {
+        let output = &proof.output;
+        anyhow::ensure!(output.verification_succeeded, "Not writing a report for a proof the guest rejected");
+        let metadata = artifacts
+            .load_token()?
+            .context("No token.json with the preflighted balances in the artifacts; run `preflight` again")?;
+        let share = |balance: U256| percent(balance, metadata.total_supply, PERCENT_PRECISION);
+
+        let balances: Vec<U256> = output
+            .final_top_n_addresses
+            .iter()
+            .map(|address| metadata.balances.get(address).copied().unwrap_or_default())
+            .collect();
+        let holders = output
+            .final_top_n_addresses
+            .iter()
+            .zip(&balances)
+            .enumerate()
+            .map(|(i, (&address, &balance))| RankedHolder {
+                rank: i + 1,
+                address,
+                balance: balance.to_string(),
+                amount: format_amount(balance, metadata.decimals),
+                percent_of_supply: share(balance),
+                account: metadata.accounts.get(&address).map(|info| info.kind),
+                proxy_implementation: metadata.accounts.get(&address).and_then(|info| info.proxy_implementation),
+                ens_name: metadata.ens_names.get(&address).cloned(),
+                label: metadata.labels.get(&address).cloned(),
+            })
+            .collect();
+        let top_n_balance = balances.iter().fold(U256::ZERO, |sum, &balance| sum.saturating_add(balance));
+        let excluded_balance = output
+            .excluded_addresses
+            .iter()
+            .map(|address| metadata.balances.get(address).copied().unwrap_or_default())
+            .fold(U256::ZERO, |sum, balance| sum.saturating_add(balance));
+        let hhi = output
+            .top_n_hhi
+            .unwrap_or_else(|| hhi_ppm(&balances, metadata.total_supply.saturating_sub(excluded_balance)));
+
+        let (block_number, _) = output.commitment.decode_id();
+        let receipt = artifacts.receipt_path();
+        Ok(Self {
+            version: REPORT_VERSION,
+            token: TokenInfo {
+                address: token,
+                chain,
+                symbol: metadata.symbol.clone(),
+                decimals: metadata.decimals,
+                total_supply: metadata.total_supply.to_string(),
+            },
+            block: BlockInfo { number: block_number.saturating_to(), commitment_digest: output.commitment.digest },
+            n: output.final_top_n_addresses.len(),
+            excluded_addresses: output.excluded_addresses.clone(),
+            excluded_addresses_hash: output.excluded_addresses_hash,
+            sanctions_list_hash: output.sanctions_list_hash,
+            merkle_root: output.top_n_merkle_root,
+            holders,
+            concentration: Concentration {
+                top_n_balance: top_n_balance.to_string(),
+                top_n_percent_of_supply: share(top_n_balance),
+                gini: gini(&balances),
+                hhi: hhi as f64 / 100.0,
+                hhi_proven: output.top_n_hhi.is_some(),
+                contracts: (!metadata.accounts.is_empty()).then(|| {
+                    metadata.accounts.values().filter(|info| info.kind == AccountKind::Contract).count()
+                }),
+            },
+            proof: ProofInfo {
+                receipt: fs::canonicalize(&receipt).unwrap_or(receipt),
+                image_id: proof.image_id.to_string(),
+                journal_digest: format!("0x{}", proof.journal_digest),
+            },
+        })
+    }
+
+    /// Writes the report to `path` in `format`.
+    pub fn write(&self, format: ReportFormat, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+        }
+        let file = File::create(path).with_context(|| format!("Failed to create {:?}", path))?;
+        let mut writer = BufWriter::new(file);
+        match format {
+            ReportFormat::Csv => self.write_csv(&mut writer),
+            ReportFormat::Json => serde_json::to_writer_pretty(&mut writer, self).map_err(Into::into),
+        }
+        .and_then(|()| writer.flush())
+        .with_context(|| format!("Failed to write {:?}", path))?;
+        info!("Wrote the report of the Top {} to {:?}", self.n, path);
+        Ok(())
+    }
+
+    /// The files published for the proof (--ipfs-api, --artifact-store): the receipt, the raw
+    /// journal and this report as JSON, with their names.
+    pub fn bundle(&self, artifacts: &ArtifactDir) -> Result<Vec<(&'static str, Vec<u8>)>> {
+        let receipt_path = artifacts.receipt_path();
+        let receipt = fs::read(&receipt_path).with_context(|| format!("Failed to read {:?}", receipt_path))?;
+        Ok(vec![
+            ("receipt.json", receipt),
+            ("journal.bin", artifacts.load_receipt()?.journal.bytes),
+            ("report.json", serde_json::to_vec_pretty(self)?),
+        ])
+    }
+
+    fn write_csv(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writeln!(writer, "rank,address,balance,percent_of_supply,account,label,ens_name")?;
+        for holder in &self.holders {
+            let account = holder.account.map(|kind| AccountInfo { kind, proxy_implementation: holder.proxy_implementation });
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{}",
+                holder.rank,
+                holder.address,
+                holder.amount,
+                holder.percent_of_supply.as_deref().unwrap_or_default(),
+                account.as_ref().map_or("", AccountInfo::tag),
+                csv_field(holder.label.as_deref().unwrap_or_default()),
+                csv_field(holder.ens_name.as_deref().unwrap_or_default())
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// `value` quoted for CSV if it needs to be, for labels and names (which anyone can set).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The Gini coefficient of `balances`, computed from the balances scaled down to 53 bits (the
+/// mantissa of an f64) relative to the largest one.
+fn gini(balances: &[U256]) -> Option<f64> {
+    let max = balances.iter().max()?;
+    let shift = max.bit_len().saturating_sub(53);
+    let mut values: Vec<f64> = balances.iter().map(|balance| (*balance >> shift).to::<u64>() as f64).collect();
+    values.sort_by(f64::total_cmp);
+    let sum: f64 = values.iter().sum();
+    if sum == 0.0 {
+        return None;
+    }
+    // Over the ascending values x_1..x_n: G = 2 * sum(i * x_i) / (n * sum(x)) - (n + 1) / n
+    let n = values.len() as f64;
+    let weighted: f64 = values.iter().enumerate().map(|(i, value)| (i + 1) as f64 * value).sum();
+    Some(2.0 * weighted / (n * sum) - (n + 1.0) / n)
+}
+
+/// The report path of `token` in a run of several tokens: `report.csv` -> `report-<token>.csv`.
+pub fn token_path(path: &Path, token: Address) -> PathBuf {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(extension) => format!("{}-{}.{}", stem, token, extension.to_string_lossy()),
+        None => format!("{}-{}", stem, token),
+    };
+    path.with_file_name(name)
+}
@@ -0,0 +1,108 @@
+// --- Retry Policy ---
+// Exponential backoff with jitter for transient network failures (HTTP 429/5xx, timeouts).
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,       // Total attempts, including the first one.
+    pub initial_backoff: Duration, // Delay before the first retry, doubled on every further retry.
+    pub max_backoff: Duration,   // Upper bound for a single delay (before jitter).
+    pub max_delay: Duration,     // Upper bound for any wait, after jitter and server hints.
+    pub jitter: f64,             // Random +/- fraction applied to each delay, 0.0..=1.0.
+}
+
+impl RetryPolicy {
+    /// Delay to wait after the given (1-based) failed attempt, at most `max_delay`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .initial_backoff
+            .saturating_mul(1u32 << exponent)
+            .min(self.max_backoff);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        delay.mul_f64(1.0 + rand::random_range(-jitter..=jitter)).min(self.max_delay)
+    }
+
+    /// Delay to wait after the given failed attempt when the server asked for `retry_after`: the
+    /// longer of the two, at most `max_delay`.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        self.backoff(attempt).max(retry_after.unwrap_or_default()).min(self.max_delay)
+    }
+}
+
+/// Marker attached (as context) to errors that are worth retrying.
+#[derive(Debug)]
+pub struct Transient {
+    pub retry_after: Option<Duration>, // Server-provided hint, e.g. from a `Retry-After` header.
+}
+
+impl fmt::Display for Transient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "transient error")
+    }
+}
+
+/// Marks `err` as transient so that [`with_retry`] retries it.
+pub fn transient(err: anyhow::Error, retry_after: Option<Duration>) -> anyhow::Error {
+    err.context(Transient { retry_after })
+}
+
+/// Runs `op` until it succeeds, fails with a non-transient error, or attempts run out.
+pub async fn with_retry<T, F, Fut>(policy: &RetryPolicy, label: &str, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        let err = match op().await {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+        wait_for_retry(policy, label, attempt, err).await?;
+        attempt += 1;
+    }
+}
+
+/// Like [`with_retry`], for operations that need mutable access to `state` (e.g. a Steel
+/// environment), which a plain closure cannot lend to the futures it returns.
+pub async fn with_retry_mut<S, T, F>(policy: &RetryPolicy, label: &str, state: &mut S, mut op: F) -> Result<T>
+where
+    F: for<'a> FnMut(&'a mut S) -> BoxFuture<'a, Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        let err = match op(state).await {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+        wait_for_retry(policy, label, attempt, err).await?;
+        attempt += 1;
+    }
+}
+
+/// Sleeps before the next attempt, or returns `err` if it is not transient or attempts ran out.
+async fn wait_for_retry(policy: &RetryPolicy, label: &str, attempt: u32, err: anyhow::Error) -> Result<()> {
+    let retry_after = err
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<Transient>())
+        .map(|t| t.retry_after);
+    match retry_after {
+        Some(retry_after) if attempt < policy.max_attempts => {
+            let delay = policy.delay(attempt, retry_after);
+            warn!(
+                "{} failed (attempt {}/{}): {:#}. Retrying in {:?}...",
+                label, attempt, policy.max_attempts, err, delay
+            );
+            tokio::time::sleep(delay).await;
+            Ok(())
+        }
+        _ => Err(err),
+    }
+}
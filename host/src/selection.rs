@@ -0,0 +1,249 @@
+// --- Candidate Selection ---
+// Bounded top-K collection of holders, so that streaming through millions of holders only
+// keeps the largest balances in memory, and lazy ranking of the candidates, so that picking the
+// holders a proof requires does not sort all of them.
+
+use risc0_steel::alloy::primitives::{Address, U256};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+use tracing::trace;
+
+use top_n_holders_core::covers_remainder;
+
+use crate::subgraph::{HolderData, HolderSink};
+
+/// Orders holders by rank: higher balance first, ties broken by ascending address.
+/// This is the same order the required addresses are handed to the guest in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Ranked(HolderData);
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .balance
+            .cmp(&other.0.balance)
+            .then_with(|| other.0.address.cmp(&self.0.address))
+    }
+}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Keeps the `capacity` best-ranked holders seen so far, one entry per address.
+pub struct TopHolders {
+    capacity: usize,
+    heap: BinaryHeap<Reverse<Ranked>>, // Min-heap: the worst kept holder is on top.
+    kept: HashSet<Address>,            // Addresses currently in `heap`.
+    seen: usize,
+    duplicates: usize,
+}
+
+impl TopHolders {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            heap: BinaryHeap::with_capacity(capacity.min(1 << 20)),
+            kept: HashSet::new(),
+            seen: 0,
+            duplicates: 0,
+        }
+    }
+
+    /// Adds a holder. A repeated address keeps its larger balance.
+    pub fn push(&mut self, holder: HolderData) {
+        self.seen += 1;
+        if self.kept.contains(&holder.address) {
+            self.duplicates += 1;
+            let address = holder.address;
+            let kept_balance = self
+                .heap
+                .iter()
+                .find(|Reverse(Ranked(kept))| kept.address == address)
+                .map(|Reverse(Ranked(kept))| kept.balance);
+            if kept_balance.is_some_and(|kept_balance| kept_balance >= holder.balance) {
+                return;
+            }
+            // Duplicates are rare, so a linear removal is fine.
+            self.heap.retain(|Reverse(Ranked(kept))| kept.address != address);
+            self.kept.remove(&address);
+        }
+
+        let ranked = Ranked(holder);
+        if self.heap.len() < self.capacity {
+            self.kept.insert(ranked.0.address);
+            self.heap.push(Reverse(ranked));
+        } else if self.heap.peek().is_some_and(|Reverse(worst)| ranked > *worst) {
+            if let Some(Reverse(Ranked(evicted))) = self.heap.pop() {
+                self.kept.remove(&evicted.address);
+            }
+            self.kept.insert(ranked.0.address);
+            self.heap.push(Reverse(ranked));
+        }
+    }
+
+    /// Number of repeated addresses that were merged.
+    pub fn duplicates(&self) -> usize {
+        self.duplicates
+    }
+
+    /// Whether holders were dropped because the capacity was reached.
+    pub fn is_truncated(&self) -> bool {
+        self.seen - self.duplicates > self.heap.len()
+    }
+
+    /// The kept holders, best-ranked first.
+    pub fn into_sorted_desc(self) -> Vec<HolderData> {
+        // Ascending order of `Reverse<Ranked>` is descending rank.
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(Ranked(holder))| holder)
+            .collect()
+    }
+}
+
+impl HolderSink for TopHolders {
+    fn push_page(&mut self, holders: Vec<HolderData>) {
+        for holder in holders {
+            self.push(holder);
+        }
+    }
+}
+
+/// The holders, best-ranked first. Building the heap is linear and every holder taken costs
+/// O(log H), so taking the K holders a proof requires out of H candidates is O(H + K log H).
+pub struct RankedHolders {
+    heap: BinaryHeap<Ranked>, // Max-heap: the best remaining holder is on top.
+}
+
+impl RankedHolders {
+    pub fn new(holders: impl IntoIterator<Item = HolderData>) -> Self {
+        Self { heap: holders.into_iter().map(Ranked).collect() }
+    }
+}
+
+impl Iterator for RankedHolders {
+    type Item = HolderData;
+
+    fn next(&mut self) -> Option<HolderData> {
+        self.heap.pop().map(|Ranked(holder)| holder)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.heap.len(), Some(self.heap.len()))
+    }
+}
+
+/// The holders a proof of the Top-N requires, see [`select_required`].
+pub struct RequiredHolders {
+    pub holders: Vec<HolderData>, // Best-ranked first.
+    pub complete: bool,           // Whether they cover enough of the supply; otherwise all were taken.
+}
+
+/// Takes holders in rank order until the balance of the N-th exceeds the supply the holders taken
+/// leave over: no holder left out can then outrank the Top-N. This is the guest's coverage rule,
+/// see [`covers_remainder`], so the holders taken are the shortest list the guest accepts.
+pub fn select_required(holders: impl IntoIterator<Item = HolderData>, n: usize, ranked_supply: U256) -> RequiredHolders {
+    let mut required: Vec<HolderData> = Vec::new();
+    let mut accumulated_balance = U256::ZERO;
+    for holder in RankedHolders::new(holders) {
+        accumulated_balance += holder.balance;
+        required.push(holder);
+        if n > 0 && required.len() >= n {
+            let threshold = required[n - 1].balance;
+            // A stale holder source may claim more than the supply; the guest rejects that.
+            let remainder = ranked_supply.saturating_sub(accumulated_balance);
+            let i = required.len();
+            trace!("#{} Holder: {} - Threshold: {}, Remainder: {}", i, required[i - 1].address, threshold, remainder);
+            if covers_remainder(threshold, remainder) {
+                return RequiredHolders { holders: required, complete: true };
+            }
+        }
+    }
+    RequiredHolders { holders: required, complete: false }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Holders 1, 2, ... with `balances`, in that order.
+    fn holders(balances: &[u64]) -> Vec<HolderData> {
+        balances
+            .iter()
+            .enumerate()
+            .map(|(i, balance)| HolderData { address: Address::with_last_byte(i as u8 + 1), balance: U256::from(*balance) })
+            .collect()
+    }
+
+    fn addresses(holders: &[HolderData]) -> Vec<u8> {
+        holders.iter().map(|holder| holder.address.0[19]).collect()
+    }
+
+    #[test]
+    fn keeps_the_best_ranked_holders() {
+        let mut top = TopHolders::new(3);
+        top.push_page(holders(&[10, 50, 30, 20, 40]));
+        assert!(top.is_truncated());
+        assert_eq!(addresses(&top.into_sorted_desc()), [2, 5, 3]);
+
+        let mut top = TopHolders::new(5);
+        top.push_page(holders(&[10, 20]));
+        assert!(!top.is_truncated());
+        assert_eq!(addresses(&top.into_sorted_desc()), [2, 1]);
+    }
+
+    #[test]
+    fn ties_rank_by_ascending_address() {
+        let mut top = TopHolders::new(2);
+        top.push_page(holders(&[10, 10, 10]).into_iter().rev().collect());
+        assert_eq!(addresses(&top.into_sorted_desc()), [1, 2]);
+        assert_eq!(addresses(&RankedHolders::new(holders(&[5, 10, 10, 5])).collect::<Vec<_>>()), [2, 3, 1, 4]);
+    }
+
+    #[test]
+    fn repeated_addresses_keep_the_larger_balance() {
+        let mut top = TopHolders::new(2);
+        top.push_page(holders(&[30, 20]));
+        top.push(HolderData { address: Address::with_last_byte(2), balance: U256::from(40) });
+        top.push(HolderData { address: Address::with_last_byte(1), balance: U256::from(5) });
+        assert_eq!(top.duplicates(), 2);
+        assert!(!top.is_truncated());
+        let kept = top.into_sorted_desc();
+        assert_eq!(addresses(&kept), [2, 1]);
+        assert_eq!(kept[0].balance, U256::from(40));
+        assert_eq!(kept[1].balance, U256::from(30));
+    }
+
+    #[test]
+    fn takes_holders_until_the_nth_covers_the_remainder() {
+        // Top-1 of 40/30/10 (supply 80): 40 does not exceed the 40 left over, 40 exceeds 10.
+        let required = select_required(holders(&[10, 40, 30]), 1, U256::from(80));
+        assert!(required.complete);
+        assert_eq!(addresses(&required.holders), [2, 3]);
+
+        // The balance of the N-th decides, not the last one taken: 30 exceeds the 5 left over.
+        let required = select_required(holders(&[40, 30, 25, 5]), 2, U256::from(100));
+        assert!(required.complete);
+        assert_eq!(addresses(&required.holders), [1, 2, 3]);
+
+        // All of the supply taken: nothing is left to cover.
+        let required = select_required(holders(&[60, 40]), 2, U256::from(100));
+        assert!(required.complete);
+        assert_eq!(addresses(&required.holders), [1, 2]);
+    }
+
+    #[test]
+    fn takes_every_holder_if_the_remainder_stays_uncovered() {
+        let required = select_required(holders(&[10, 10, 10]), 1, U256::from(100));
+        assert!(!required.complete);
+        assert_eq!(required.holders.len(), 3);
+
+        let required = select_required(holders(&[50]), 2, U256::from(50));
+        assert!(!required.complete);
+        assert_eq!(required.holders.len(), 1);
+    }
+}
@@ -0,0 +1,259 @@
+// --- HTTP API ---
+// `serve` turns the host into a proving service. Clients queue proofs for a token and N; a single
+// worker proves the jobs one after the other (proving saturates the machine), each at the requested
+// `block_number` or else the block resolved when the job starts, with its artifacts in
+// `<artifacts dir>/jobs/<id>/`.
+//
+//     POST /proofs                  {"token": "0x…", "n": 10, "chain": "gnosis"}  -> 202 {"id": 1}
+//     GET  /proofs/{id}             Job status and, once finished, the result
+//     GET  /proofs/{id}/receipt     The receipt (JSON)
+//     GET  /proofs/{id}/journal     The raw journal bytes
+//     GET  /metrics                 Prometheus metrics
+//
+// The server proves on the chain given (or detected, with `auto`) by --chain-spec; `chain` in a
+// request is optional and is only checked against it. Job state is kept in memory; artifacts stay on disk across restarts.
+
+use anyhow::{Context, Result};
+use axum::extract::{Path as UrlPath, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use risc0_steel::alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use top_n_holders_core::{ChainName, VerificationFailure};
+use top_n_holders_host::artifacts::ArtifactDir;
+use top_n_holders_host::pipeline::{check_n, reject_dry_run};
+use top_n_holders_host::{metrics, Pipeline, ProofRequest, ProofResult};
+
+use crate::ServeArgs;
+
+type ApiResult<T> = std::result::Result<T, (StatusCode, String)>;
+
+#[derive(Deserialize)]
+struct JobRequest {
+    #[serde(flatten)]
+    proof: ProofRequest,
+    chain: Option<ChainName>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Serialize, Clone)]
+struct Job {
+    id: u64,
+    token: Address,
+    chain_spec: ChainName,
+    n: usize,
+    status: JobStatus,
+    block: Option<u64>,
+    verification_succeeded: Option<bool>,
+    failure: Option<VerificationFailure>, // Why the guest rejected the ranking, if it did.
+    journal_digest: Option<String>, // Hex SHA-256 of the journal.
+    top_n_addresses: Vec<Address>,
+    error: Option<String>,
+}
+
+impl Job {
+    fn finish(&mut self, result: &Result<ProofResult>) {
+        match result {
+            Ok(proof) => {
+                self.status = JobStatus::Succeeded;
+                self.block = Some(proof.block_number);
+                self.verification_succeeded = Some(proof.output.verification_succeeded);
+                self.failure = proof.output.failure.clone();
+                self.journal_digest = Some(format!("0x{}", proof.journal_digest));
+                self.top_n_addresses = proof.output.final_top_n_addresses.clone();
+            }
+            Err(e) => {
+                self.status = JobStatus::Failed;
+                self.error = Some(format!("{:#}", e));
+            }
+        }
+    }
+}
+
+struct AppState {
+    pipeline: Pipeline,
+    jobs_dir: PathBuf,
+    jobs: Mutex<HashMap<u64, Job>>,
+    next_id: AtomicU64,
+    queue: mpsc::UnboundedSender<u64>,
+}
+
+impl AppState {
+    fn job(&self, id: u64) -> ApiResult<Job> {
+        let jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        jobs.get(&id).cloned().ok_or((StatusCode::NOT_FOUND, format!("Unknown job {}", id)))
+    }
+
+    fn update(&self, id: u64, change: impl FnOnce(&mut Job)) -> Option<Job> {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        let job = jobs.get_mut(&id)?;
+        change(job);
+        Some(job.clone())
+    }
+
+    fn artifacts_dir(&self, id: u64) -> PathBuf {
+        self.jobs_dir.join(id.to_string())
+    }
+}
+
+pub async fn serve(args: ServeArgs) -> Result<()> {
+    reject_dry_run(&args.run, "serve")?;
+    if !args.run.fetch.erc20_address.is_empty() || args.run.selection.n_top_holders.is_some() {
+        warn!("--erc20-address and --n-top-holders are ignored by `serve`; every request names its token and N");
+    }
+    if args.run.report_out.is_some() || args.run.merkle_out.is_some() || args.run.distributor_out.is_some() {
+        warn!("--report-out, --merkle-out and --distributor-out are ignored by `serve`; the artifacts of every job are in its job directory");
+    }
+    if args.run.ipfs_api.is_some() || args.run.artifact_store.is_some() {
+        warn!("--ipfs-api and --artifact-store are ignored by `serve`; fetch the receipts from the job endpoints");
+    }
+    if args.run.registry.registry_contract.is_some() {
+        warn!("--registry-contract is ignored by `serve`; submit the receipt of a job with `submit`");
+    }
+    let jobs_dir = args.run.fetch.artifacts.artifacts_dir.join("jobs");
+    let (queue, queued) = mpsc::unbounded_channel();
+    let mut run_args = args.run;
+    run_args.prover.yes = true; // Requests are the confirmation.
+    run_args.registry.registry_contract = None;
+    let pipeline = Pipeline::new(run_args).await?;
+    let state = Arc::new(AppState {
+        next_id: AtomicU64::new(first_free_id(&jobs_dir)?),
+        jobs_dir,
+        pipeline,
+        jobs: Mutex::new(HashMap::new()),
+        queue,
+    });
+    tokio::spawn(work(state.clone(), queued));
+
+    let app = Router::new()
+        .route("/proofs", post(create_job))
+        .route("/proofs/{id}", get(get_job))
+        .route("/proofs/{id}/receipt", get(get_receipt))
+        .route("/proofs/{id}/journal", get(get_journal))
+        .with_state(state)
+        .merge(metrics::routes());
+    let listener = TcpListener::bind(args.listen)
+        .await
+        .with_context(|| format!("Failed to bind the HTTP API to {}", args.listen))?;
+    info!("Serving the HTTP API on http://{}", args.listen);
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+            info!("Interrupted. Stopping the server.");
+        })
+        .await
+        .context("HTTP API server failed")
+}
+
+/// Job ids continue after the job directories of earlier runs, so old artifacts are never reused.
+fn first_free_id(jobs_dir: &Path) -> Result<u64> {
+    if !jobs_dir.exists() {
+        return Ok(1);
+    }
+    let mut last_id = 0;
+    for entry in fs::read_dir(jobs_dir).with_context(|| format!("Failed to read {:?}", jobs_dir))? {
+        if let Some(id) = entry?.file_name().to_str().and_then(|name| name.parse::<u64>().ok()) {
+            last_id = last_id.max(id);
+        }
+    }
+    Ok(last_id + 1)
+}
+
+/// Proves the queued jobs one at a time.
+async fn work(state: Arc<AppState>, mut queued: mpsc::UnboundedReceiver<u64>) {
+    while let Some(id) = queued.recv().await {
+        let Some(job) = state.update(id, |job| job.status = JobStatus::Running) else {
+            continue;
+        };
+        info!("Starting job {}: Top {} of {}", id, job.n, job.token);
+        let request = ProofRequest { token: job.token, n: job.n, block_number: job.block };
+        let result = state.pipeline.prove(&request, &state.artifacts_dir(id)).await;
+        if let Some(job) = state.update(id, |job| job.finish(&result)) {
+            info!("Job {} finished: {:?}", id, job.status);
+        }
+    }
+}
+
+async fn create_job(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<JobRequest>,
+) -> ApiResult<(StatusCode, Json<serde_json::Value>)> {
+    let chain_spec = state.pipeline.chain();
+    if let Some(chain) = request.chain {
+        if chain != chain_spec {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("This server proves on `{}`, not `{}`", chain_spec, chain),
+            ));
+        }
+    }
+    let request = request.proof;
+    check_n(request.n, &state.pipeline.args().fetch).map_err(|e| (StatusCode::BAD_REQUEST, format!("{:#}", e)))?;
+
+    let id = state.next_id.fetch_add(1, Ordering::Relaxed);
+    let job = Job {
+        id,
+        token: request.token,
+        chain_spec,
+        n: request.n,
+        status: JobStatus::Queued,
+        block: request.block_number,
+        verification_succeeded: None,
+        failure: None,
+        journal_digest: None,
+        top_n_addresses: Vec::new(),
+        error: None,
+    };
+    state.jobs.lock().unwrap_or_else(|e| e.into_inner()).insert(id, job);
+    state
+        .queue
+        .send(id)
+        .map_err(|_| (StatusCode::SERVICE_UNAVAILABLE, "The prover has stopped".to_string()))?;
+    info!("Queued job {}: Top {} of {}", id, request.n, request.token);
+    Ok((StatusCode::ACCEPTED, Json(serde_json::json!({ "id": id }))))
+}
+
+async fn get_job(State(state): State<Arc<AppState>>, UrlPath(id): UrlPath<u64>) -> ApiResult<Json<Job>> {
+    state.job(id).map(Json)
+}
+
+async fn get_receipt(State(state): State<Arc<AppState>>, UrlPath(id): UrlPath<u64>) -> ApiResult<impl IntoResponse> {
+    let receipt = finished_receipt(&state, id)?;
+    Ok(Json(receipt))
+}
+
+async fn get_journal(State(state): State<Arc<AppState>>, UrlPath(id): UrlPath<u64>) -> ApiResult<impl IntoResponse> {
+    let receipt = finished_receipt(&state, id)?;
+    Ok(([(CONTENT_TYPE, "application/octet-stream")], receipt.journal.bytes))
+}
+
+/// The receipt of a succeeded job.
+fn finished_receipt(state: &AppState, id: u64) -> ApiResult<risc0_zkvm::Receipt> {
+    let job = state.job(id)?;
+    if job.status != JobStatus::Succeeded {
+        return Err((StatusCode::CONFLICT, format!("Job {} has no receipt (status: {:?})", id, job.status)));
+    }
+    ArtifactDir::new(&state.artifacts_dir(id))
+        .load_receipt()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)))
+}
@@ -0,0 +1,133 @@
+// --- Snapshot.org Score Exports ---
+// Loads a candidate set from a Snapshot strategy score export instead of a Subgraph.
+//
+// Accepted formats:
+//   * score API responses: `{"result": {"scores": [{"0xabc..": 12.5, ..}, ..]}}`
+//   * a list of per-strategy score maps: `[{"0xabc..": 12.5}, {"0xabc..": 3}]`
+//   * a single score map: `{"0xabc..": 12.5}`
+//   * CSV with `address,score` rows (a header row is optional)
+// Scores of the same address across strategies are summed. Snapshot reports scores in token
+// units, so they are scaled by `10^decimals` to raw balances comparable to `totalSupply`.
+
+use anyhow::{Context, Result};
+use risc0_steel::alloy::primitives::{Address, U256};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+use crate::subgraph::HolderData;
+
+/// Loads holders from a Snapshot score export (JSON or CSV, chosen by file extension).
+pub fn load_snapshot_scores(path: &Path, decimals: u8) -> Result<Vec<HolderData>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read Snapshot export: {:?}", path))?;
+
+    let is_csv = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    let scores: Vec<(String, String)> = if is_csv {
+        parse_csv(&contents)?
+    } else {
+        let json: Value = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse Snapshot export as JSON: {:?}", path))?;
+        parse_json(&json)?
+    };
+
+    let mut balances: BTreeMap<Address, U256> = BTreeMap::new();
+    for (address, score) in scores {
+        let holder_address = Address::from_str(address.trim())
+            .with_context(|| format!("Failed to parse address from Snapshot export: {}", address))?;
+        let holder_balance = parse_decimal_units(score.trim(), decimals)
+            .with_context(|| format!("Failed to parse Snapshot score for {}: {}", address, score))?;
+        *balances.entry(holder_address).or_default() += holder_balance;
+    }
+
+    info!("Loaded {} holders from Snapshot export {:?}", balances.len(), path);
+    Ok(balances
+        .into_iter()
+        .map(|(address, balance)| HolderData { address, balance })
+        .collect())
+}
+
+fn parse_json(json: &Value) -> Result<Vec<(String, String)>> {
+    // Unwrap the score API envelope if present.
+    let scores = json
+        .pointer("/result/scores")
+        .or_else(|| json.get("scores"))
+        .unwrap_or(json);
+
+    let maps: Vec<&Map<String, Value>> = match scores {
+        Value::Array(strategies) => strategies
+            .iter()
+            .map(|strategy| strategy.as_object().context("Expected an object of address -> score per strategy"))
+            .collect::<Result<_>>()?,
+        Value::Object(map) => vec![map],
+        _ => anyhow::bail!("Unsupported Snapshot export: expected an object or an array of objects"),
+    };
+
+    let mut scores = Vec::new();
+    for map in maps {
+        for (address, score) in map {
+            let score = match score {
+                Value::Number(number) => number.to_string(),
+                Value::String(text) => text.clone(),
+                _ => anyhow::bail!("Unsupported score value for {}: {}", address, score),
+            };
+            scores.push((address.clone(), score));
+        }
+    }
+    Ok(scores)
+}
+
+fn parse_csv(contents: &str) -> Result<Vec<(String, String)>> {
+    let mut scores = Vec::new();
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut columns = line.split(',').map(|column| column.trim().trim_matches('"'));
+        let (Some(address), Some(score)) = (columns.next(), columns.next()) else {
+            anyhow::bail!("Line {}: expected `address,score`", line_number + 1);
+        };
+        // Skip a header row.
+        if line_number == 0 && !address.starts_with("0x") {
+            continue;
+        }
+        scores.push((address.to_string(), score.to_string()));
+    }
+    Ok(scores)
+}
+
+/// Parses a decimal number (optionally with an exponent, e.g. `1.5e3`) into raw units,
+/// truncating digits beyond `decimals`.
+fn parse_decimal_units(value: &str, decimals: u8) -> Result<U256> {
+    let (mantissa, exponent) = match value.find(['e', 'E']) {
+        Some(pos) => (&value[..pos], value[pos + 1..].parse::<i64>().context("Invalid exponent")?),
+        None => (value, 0),
+    };
+    anyhow::ensure!(!mantissa.starts_with('-'), "Negative scores are not supported");
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{}{}", integer.trim_start_matches('+'), fraction);
+    anyhow::ensure!(
+        !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()),
+        "Not a decimal number"
+    );
+
+    // value = digits * 10^(exponent - fraction.len()); scaled by 10^decimals.
+    let shift = exponent - fraction.len() as i64 + decimals as i64;
+    let digits = U256::from_str_radix(&digits, 10)?;
+    let ten = U256::from(10);
+    if shift >= 0 {
+        let factor = ten.checked_pow(U256::from(shift)).context("Score out of range")?;
+        digits.checked_mul(factor).context("Score out of range")
+    } else {
+        match ten.checked_pow(U256::from(-shift)) {
+            Some(divisor) => Ok(digits / divisor),
+            None => Ok(U256::ZERO),
+        }
+    }
+}
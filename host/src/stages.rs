@@ -0,0 +1,1751 @@
+// --- Pipeline Stages ---
+// fetch -> preflight -> prove -> verify (-> submit). Each stage reads the artifacts of the
+// previous one from the artifacts directory and stores its own, see `artifacts.rs`.
+
+use alloy::dyn_abi::{DynSolValue, JsonAbiExt};
+use alloy::json_abi::Function;
+use alloy::network::{Network, TransactionBuilder};
+use alloy::providers::{DynProvider, Provider, ProviderBuilder};
+use alloy::rpc::types::{BlockId, TransactionRequest};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::sol_types::SolCall;
+use anyhow::{Context, Result};
+use risc0_steel::{
+    alloy::primitives::{Address, Bytes, U256},
+    ethereum::{EthChainSpec, EthEvmEnv},
+    Commitment,
+};
+use risc0_zkvm::sha::{Digest, Digestible};
+use risc0_zkvm::{default_executor, ExecutorEnv, ExecutorEnvBuilder, Receipt};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+use tracing::{error, info, warn};
+
+use top_n_holders_core::{
+    active_spec_id, rank_holders, with_fork_overrides, AggregateOutput, ChainName, ChunkOutput, ChunkRange, CycleProfile,
+    GuestInput, GuestOutput, SpecId, VerificationFailure,
+};
+use top_n_holders_guest_methods::{
+    TOP_N_HOLDERS_AGGREGATE_ELF, TOP_N_HOLDERS_AGGREGATE_ID, TOP_N_HOLDERS_GUEST_ELF, TOP_N_HOLDERS_GUEST_ID,
+};
+
+use crate::accounts;
+use crate::artifacts::{ArtifactDir, HolderArtifact, TokenArtifact};
+use crate::backend::ProverBackend;
+use crate::cache::{self, CachedHolders, HolderCacheDir};
+use crate::checkpoint::FetchCheckpoint;
+use crate::ens;
+use crate::evm::{EvmInput, PreflightEnv};
+use crate::exclusions;
+use crate::failure::{self, Failure};
+use crate::index::HolderIndex;
+use crate::labels;
+use crate::mapping::ResponseMapping;
+use crate::prefetch::PrefetchProvider;
+use crate::rate_limit::RateLimiter;
+use crate::{metrics, progress, summary};
+use crate::retry::{transient, with_retry, with_retry_mut, RetryPolicy};
+use crate::selection::{select_required, TopHolders};
+use crate::subgraph::{HolderData, SubgraphClient};
+use crate::args::{BlockTag, ChainSelection, FetchArgs, HolderSource, ProverArgs, ReceiptKind, SelectionArgs, SubmitArgs};
+use crate::{IERC20, IMulticall3};
+
+// --- Fetch ---
+
+/// Connections shared by all tokens of a run.
+pub struct FetchClients {
+    providers: Vec<DynProvider>, // One per --rpc-url, in order of preference.
+    active_provider: AtomicUsize, // Index of the provider picked by `select_provider`.
+    subgraph: Option<SubgraphClient>, // None without --subgraph-url.
+    chain: ChainName, // Detected from the RPC endpoints with `--chain-spec auto`.
+}
+
+impl FetchClients {
+    pub async fn new(args: &FetchArgs) -> Result<Self> {
+        anyhow::ensure!(!args.rpc_url.is_empty(), "At least one --rpc-url is required");
+        let mut providers = Vec::with_capacity(args.rpc_url.len());
+        for rpc_url in &args.rpc_url {
+            providers.push(connect(rpc_url).await?);
+        }
+        let chain = match args.chain_spec {
+            ChainSelection::Chain(chain) => chain,
+            ChainSelection::Auto => detect_chain(&providers).await?,
+        };
+        let subgraph = if args.subgraph_url.is_empty() {
+            None
+        } else {
+            let retry_policy = RetryPolicy {
+                max_attempts: args.subgraph_max_attempts,
+                initial_backoff: Duration::from_millis(args.subgraph_backoff_ms),
+                max_backoff: Duration::from_millis(args.subgraph_max_backoff_ms),
+                max_delay: Duration::from_millis(args.subgraph_max_delay_ms),
+                jitter: args.subgraph_jitter,
+            };
+            let subgraph_client = SubgraphClient::new(
+                args.subgraph_url.clone(),
+                Duration::from_secs(args.subgraph_timeout_secs),
+                retry_policy,
+            )?
+            .keep_zero_balances(args.keep_zero)
+            .response_mapping(ResponseMapping {
+                collection: args.subgraph_collection.clone(),
+                token_field: args.subgraph_token_field.clone(),
+                holder_path: args.subgraph_holder_field.clone(),
+                balance_path: args.subgraph_balance_field.clone(),
+            });
+            Some(subgraph_client)
+        };
+        Ok(Self {
+            providers,
+            active_provider: AtomicUsize::new(0),
+            subgraph,
+            chain,
+        })
+    }
+
+    /// The chain the run proves on.
+    pub fn chain(&self) -> ChainName {
+        self.chain
+    }
+
+    fn provider(&self) -> &DynProvider {
+        &self.providers[self.active_provider.load(Ordering::Relaxed)]
+    }
+
+    /// Switches to the first RPC endpoint (in --rpc-url order) that answers on the right chain.
+    /// Called once per run (or daemon round), before any other request. Returns the latest block.
+    async fn select_provider(&self) -> Result<u64> {
+        let mut last_error = None;
+        for (i, provider) in self.providers.iter().enumerate() {
+            let latest_block = match ensure_chain(provider, self.chain).await {
+                Ok(()) => provider.get_block_number().await.context("Failed to fetch the latest block number"),
+                Err(e) => Err(e),
+            };
+            match latest_block {
+                Ok(latest_block) => {
+                    if self.active_provider.swap(i, Ordering::Relaxed) != i {
+                        info!("Using RPC endpoint #{}", i + 1);
+                    }
+                    return Ok(latest_block);
+                }
+                Err(e) => {
+                    warn!("RPC endpoint #{} failed: {:#}", i + 1, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("No RPC endpoint configured")))
+            .context("No RPC endpoint is available")
+    }
+
+    fn subgraph(&self) -> Result<&SubgraphClient> {
+        self.subgraph
+            .as_ref()
+            .context("At least one Subgraph URL (--subgraph-url) is required")
+    }
+}
+
+/// Connects to an RPC endpoint over HTTP, WebSocket or IPC, depending on the URL (or socket path).
+pub async fn connect(rpc_url: &str) -> Result<DynProvider> {
+    let provider = ProviderBuilder::new()
+        .connect(rpc_url)
+        .await
+        .with_context(|| format!("Failed to connect to RPC endpoint {}", rpc_url))?;
+    Ok(provider.erased())
+}
+
+/// Like `connect`, for the OP Stack environment of OP Stack chains.
+#[cfg(feature = "op")]
+async fn connect_op(rpc_url: &str) -> Result<DynProvider<op_alloy_network::Optimism>> {
+    let provider = ProviderBuilder::new()
+        .disable_recommended_fillers()
+        .network::<op_alloy_network::Optimism>()
+        .connect(rpc_url)
+        .await
+        .with_context(|| format!("Failed to connect to RPC endpoint {}", rpc_url))?;
+    Ok(provider.erased())
+}
+
+/// The supported chain with the chain id reported by the first answering RPC endpoint, for
+/// `--chain-spec auto`.
+async fn detect_chain(providers: &[DynProvider]) -> Result<ChainName> {
+    let mut last_error = None;
+    for (i, provider) in providers.iter().enumerate() {
+        match provider.get_chain_id().await.context("Failed to fetch the chain id (eth_chainId)") {
+            Ok(chain_id) => {
+                let chain = ChainName::from_chain_id(chain_id).with_context(|| {
+                    format!("RPC endpoint #{} serves chain id {}, which no chain spec is registered for", i + 1, chain_id)
+                })?;
+                info!("Detected chain spec {} (chain id {}) from RPC endpoint #{}", chain, chain_id, i + 1);
+                return Ok(chain);
+            }
+            Err(e) => {
+                warn!("RPC endpoint #{} failed: {:#}", i + 1, e);
+                last_error = Some(e);
+            }
+        }
+    }
+    Err(last_error.unwrap_or_else(|| anyhow::anyhow!("No RPC endpoint configured")))
+        .context("--chain-spec auto: no RPC endpoint reported its chain id")
+}
+
+/// Fails fast if the RPC endpoint serves another chain than the chain spec. A mismatch otherwise
+/// shows up as confusing failures deep in preflight.
+async fn ensure_chain<N: Network>(provider: &impl Provider<N>, chain: ChainName) -> Result<()> {
+    let expected = chain.chain_id();
+    let actual = provider.get_chain_id().await.context("Failed to fetch the chain id (eth_chainId)")?;
+    anyhow::ensure!(
+        actual == expected,
+        "The RPC endpoint serves chain id {}, but --chain-spec {} is chain id {}",
+        actual,
+        chain,
+        expected
+    );
+    Ok(())
+}
+
+/// Fails fast if the token has no code at the proving block, i.e. it is an EOA, lives on another
+/// chain or was not deployed yet. Otherwise this only surfaces when `totalSupply` reverts in preflight.
+async fn ensure_contract(clients: &FetchClients, token: Address, block_number: u64) -> Result<()> {
+    let code = clients
+        .provider()
+        .get_code_at(token)
+        .number(block_number)
+        .await
+        .with_context(|| format!("Failed to fetch the code of {}", token))?;
+    anyhow::ensure!(
+        !code.is_empty(),
+        "{} has no contract code at block {}. Check --erc20-address and that --rpc-url is on the token's chain.",
+        token,
+        block_number
+    );
+    Ok(())
+}
+
+/// Resolves the proving block from --block-number or --block-tag.
+/// Fixed before fetching, so that the Subgraph is queried at the block the preflight later runs at.
+pub async fn resolve_block(clients: &FetchClients, block_number: Option<u64>, block_tag: BlockTag) -> Result<u64> {
+    let latest_block = clients.select_provider().await?;
+    let block_number = match (block_number, block_tag) {
+        (Some(block_number), _) => {
+            anyhow::ensure!(
+                block_number <= latest_block,
+                "--block-number {} is ahead of the latest block {}",
+                block_number,
+                latest_block
+            );
+            info!("Proving block: {} (pinned, {} blocks behind latest)", block_number, latest_block - block_number);
+            block_number
+        }
+        (None, BlockTag::Latest) => {
+            info!("Proving block: {} (latest)", latest_block);
+            latest_block
+        }
+        (None, block_tag) => {
+            let block_number = clients
+                .provider()
+                .get_block_by_number(block_tag.into())
+                .await
+                .with_context(|| format!("Failed to fetch the {:?} block", block_tag))?
+                .with_context(|| format!("RPC endpoint has no {:?} block", block_tag))?
+                .header
+                .number;
+            info!(
+                "Proving block: {} ({:?}, {} blocks behind latest)",
+                block_number,
+                block_tag,
+                latest_block.saturating_sub(block_number)
+            );
+            block_number
+        }
+    };
+    Ok(block_number)
+}
+
+pub async fn fetch(
+    args: &FetchArgs,
+    clients: &FetchClients,
+    erc20_contract_address: Address,
+    block_number: u64,
+    artifacts: &ArtifactDir,
+) -> Result<HolderArtifact> {
+    let started = Instant::now();
+    info!("Configuration:");
+    info!("ERC20 Contract: {}", erc20_contract_address);
+    info!("Holder Source: {:?}", args.source);
+    info!("Subgraph URL(s): {}", args.subgraph_url.join(", "));
+    info!("RPC URL(s): {}", args.rpc_url.join(", "));
+    info!("Chain Spec: {}", clients.chain());
+    info!("Block: {}", block_number);
+
+    let chain = clients.chain();
+    let chain_id = chain.chain_id();
+    let log_provider = clients.provider();
+    ensure_contract(clients, erc20_contract_address, block_number).await?;
+
+    // --- Holder Index ---
+    let mut holder_index = args.holder_index.as_deref().map(HolderIndex::open).transpose()?;
+    let indexed_block = match &holder_index {
+        Some(index) => index.synced_block(chain_id, erc20_contract_address)?,
+        None => None,
+    };
+    if let Some(indexed_block) = indexed_block.filter(|&indexed_block| indexed_block > block_number) {
+        warn!("Holder index is synced to block {}, past the proving block {}. Reseeding it.", indexed_block, block_number);
+    }
+
+    // --- Cache Configuration ---
+    let cache_dir = args.cache_dir.clone().unwrap_or_else(cache::default_cache_dir);
+    let holder_cache = HolderCacheDir::new(&cache_dir, "subgraph", chain.name(), erc20_contract_address);
+
+    // Cached holders count as fresh under the same lag tolerance as a live Subgraph.
+    let cached_holders = if args.cache_subgraph && args.source == HolderSource::Subgraph {
+        holder_cache.latest_at_or_before(block_number)?.filter(|(cache_file_path, cached)| {
+            match cached.staleness(block_number, args.max_subgraph_lag, args.cache_max_age) {
+                Some(reason) => {
+                    info!("Cache at {:?} is stale ({}). Refetching.", cache_file_path, reason);
+                    false
+                }
+                None => true,
+            }
+        })
+    } else {
+        None
+    };
+
+    // --- Attempt to Load from Cache or Fetch Data from Subgraph ---
+    // Stores addresses fetched from the Subgraph.
+    let mut all_subgraph_holders: Vec<HolderData>;
+    // Set when only the top --max-candidates holders were kept while streaming.
+    let mut candidates_truncated = false;
+
+    if args.source == HolderSource::Snapshot {
+        let snapshot_file = args.snapshot_file.as_deref().context("--snapshot-file is required for --source snapshot")?;
+        info!("Loading holder candidates from Snapshot export {:?}...", snapshot_file);
+        all_subgraph_holders = crate::snapshot::load_snapshot_scores(snapshot_file, args.snapshot_decimals)?;
+        // Scores are only used to rank candidates; the guest checks the actual balances at the proving block.
+        warn!("Snapshot scores are not pinned to block {}; stale scores may fail the guest ordering check.", block_number);
+
+    } else if let Some(index) = holder_index
+        .as_mut()
+        .filter(|_| indexed_block.is_some_and(|indexed_block| indexed_block <= block_number))
+    {
+        info!("Syncing holder index from block {:?} to {}...", indexed_block, block_number);
+        index
+            .sync_transfers(log_provider, chain_id, erc20_contract_address, block_number, args.index_log_chunk)
+            .await?;
+        all_subgraph_holders = index.holders(chain_id, erc20_contract_address)?;
+        info!("Loaded {} holders from the holder index.", all_subgraph_holders.len());
+
+    } else if let Some((cache_file_path, cached)) = cached_holders {
+        info!(
+            "Cache found at {:?} (block {}, {}s old). Loading holder addresses from cache...",
+            cache_file_path,
+            cached.block,
+            cached.age().as_secs()
+        );
+        all_subgraph_holders = cached.holders;
+        info!("Loaded {} holder addresses from cache.", all_subgraph_holders.len());
+
+    } else {
+        if args.cache_subgraph {
+            info!("No usable cache found. Fetching holder addresses from Subgraph...");
+        } else {
+            info!("Fetching holder addresses from Subgraph (caching disabled)...");
+        }
+        let subgraph_client = clients.subgraph()?;
+
+        // --- Resume from Checkpoint ---
+        // An unfinished fetch is continued at its own block, as long as that block is recent enough.
+        let checkpoint_root = FetchCheckpoint::root(&cache_dir, chain.name(), erc20_contract_address);
+        let checkpoint_block = if args.no_checkpoints {
+            None
+        } else {
+            FetchCheckpoint::latest_block(&checkpoint_root, block_number)?
+                .filter(|&checkpoint_block| block_number - checkpoint_block <= args.max_subgraph_lag)
+        };
+        if let Some(checkpoint_block) = checkpoint_block {
+            info!("Found an unfinished Subgraph fetch at block {}. Resuming it.", checkpoint_block);
+        }
+
+        // --- Verify Subgraph Freshness ---
+        let subgraph_block = subgraph_client
+            .select_fresh_endpoint(checkpoint_block.unwrap_or(block_number), args.max_subgraph_lag, args.allow_stale)
+            .await?;
+        if subgraph_block < block_number {
+            warn!(
+                "Reading holders at Subgraph block {}, {} blocks behind the proving block {}. Balances that changed in between may fail the guest ordering check.",
+                subgraph_block,
+                block_number - subgraph_block,
+                block_number
+            );
+        }
+
+        let checkpoint = if args.no_checkpoints {
+            None
+        } else {
+            Some(FetchCheckpoint::open(&checkpoint_root, subgraph_block)?)
+        };
+        // Without a cache or index to fill, only the best-ranked holders need to be kept.
+        if !args.cache_subgraph && holder_index.is_none() {
+            let top_holders = Mutex::new(TopHolders::new(args.max_candidates as usize));
+            subgraph_client
+                .stream_token_holders(
+                    erc20_contract_address,
+                    Some(subgraph_block),
+                    args.subgraph_concurrency as usize,
+                    checkpoint.as_ref(),
+                    &top_holders,
+                )
+                .await?;
+            let top_holders = top_holders.into_inner().unwrap_or_else(PoisonError::into_inner);
+            if top_holders.duplicates() > 0 {
+                warn!("Merged {} duplicate holder entries.", top_holders.duplicates());
+            }
+            candidates_truncated = top_holders.is_truncated();
+            all_subgraph_holders = top_holders.into_sorted_desc();
+            info!("Kept the top {} holders as candidates.", all_subgraph_holders.len());
+        } else {
+            let fetched_holders_list = subgraph_client
+                .fetch_token_holders(
+                    erc20_contract_address,
+                    Some(subgraph_block),
+                    args.subgraph_concurrency as usize,
+                    checkpoint.as_ref(),
+                )
+                .await?;
+
+            // Assign fetched data to the main variable
+            all_subgraph_holders = fetched_holders_list;
+        }
+
+        // --- Write to Cache ---
+        // Written before the index sync, so the cache holds the Subgraph view at its own block.
+        if args.cache_subgraph {
+            let cache_file_path = holder_cache.entry_path(subgraph_block);
+            info!("Writing fetched holder addresses to cache: {:?}", cache_file_path);
+            CachedHolders::new(subgraph_block, all_subgraph_holders.clone()).store(&cache_file_path)?;
+        }
+        if let Some(checkpoint) = checkpoint {
+            checkpoint.remove()?;
+        }
+
+        // --- Seed the Holder Index ---
+        // The index starts from the Subgraph snapshot and catches up to the proving block via logs.
+        if let Some(index) = holder_index.as_mut() {
+            index.seed(chain_id, erc20_contract_address, subgraph_block, &all_subgraph_holders)?;
+            index
+                .sync_transfers(log_provider, chain_id, erc20_contract_address, block_number, args.index_log_chunk)
+                .await?;
+            all_subgraph_holders = index.holders(chain_id, erc20_contract_address)?;
+        }
+    }
+
+    info!("Fetch complete. {} candidate holders collected at block {}.", all_subgraph_holders.len(), block_number);
+
+    let holders = HolderArtifact {
+        chain_spec: chain,
+        token: erc20_contract_address,
+        block: block_number,
+        candidates_truncated,
+        holders: all_subgraph_holders,
+    };
+    artifacts.store_holders(&holders)?;
+    artifacts.record_timing("fetch", started.elapsed())?;
+    Ok(holders)
+}
+
+// --- Preflight ---
+
+/// Connections to the --rpc-url endpoints for the preflight, shared by all tokens of a run. The
+/// checks of the chain and its active fork are shared too: they depend on the endpoint and the
+/// block only, and the tokens of a run are preflighted at the same block.
+///
+/// Every token still gets a Steel environment of its own, which fetches the block header again:
+/// the EVM input holds the state of every call preflighted in the environment, and each token is
+/// proven on its own.
+pub struct PreflightClients {
+    rpc_urls: Vec<String>,
+    providers: Vec<OnceCell<DynProvider>>, // Connected on first use, one per --rpc-url.
+    #[cfg(feature = "op")]
+    op_providers: Vec<OnceCell<DynProvider<op_alloy_network::Optimism>>>,
+    checked: Mutex<HashSet<(usize, u64)>>, // (Endpoint, block) pairs whose chain and fork checks passed.
+}
+
+impl PreflightClients {
+    pub fn new(rpc_urls: &[String]) -> Self {
+        Self {
+            rpc_urls: rpc_urls.to_vec(),
+            providers: rpc_urls.iter().map(|_| OnceCell::new()).collect(),
+            #[cfg(feature = "op")]
+            op_providers: rpc_urls.iter().map(|_| OnceCell::new()).collect(),
+            checked: Mutex::default(),
+        }
+    }
+
+    async fn provider(&self, endpoint: usize) -> Result<DynProvider> {
+        self.providers[endpoint].get_or_try_init(|| connect(&self.rpc_urls[endpoint])).await.cloned()
+    }
+
+    #[cfg(feature = "op")]
+    async fn op_provider(&self, endpoint: usize) -> Result<DynProvider<op_alloy_network::Optimism>> {
+        self.op_providers[endpoint].get_or_try_init(|| connect_op(&self.rpc_urls[endpoint])).await.cloned()
+    }
+
+    fn is_checked(&self, endpoint: usize, block_number: u64) -> bool {
+        self.checked.lock().unwrap_or_else(PoisonError::into_inner).contains(&(endpoint, block_number))
+    }
+
+    fn set_checked(&self, endpoint: usize, block_number: u64) {
+        self.checked.lock().unwrap_or_else(PoisonError::into_inner).insert((endpoint, block_number));
+    }
+}
+
+/// Rejects a --chunk-size with options the aggregation guest cannot prove: the chunks commit no
+/// balances, so it could not compute the HHI or the Merkle root. Checked before any RPC work.
+pub fn check_chunk_options(chunk_size: Option<u64>, commit_hhi: bool, commit_merkle_root: bool) -> Result<()> {
+    if chunk_size.is_some() {
+        anyhow::ensure!(!commit_hhi, "--commit-hhi is not supported with --chunk-size");
+        anyhow::ensure!(!commit_merkle_root, "--commit-merkle-root is not supported with --chunk-size");
+    }
+    Ok(())
+}
+
+/// Rejects a --chunk-size with prover options the chunks would not honor: chunks are always proven
+/// as succinct poseidon2 receipts so that the aggregation guest can verify them, and a chunk that
+/// hits a guest error has no journal to aggregate.
+pub fn check_chunk_prover_options(args: &ProverArgs) -> Result<()> {
+    if args.chunk_size.is_some() {
+        anyhow::ensure!(args.hashfn.is_none(), "--hashfn is not supported with --chunk-size");
+        anyhow::ensure!(!args.prove_guest_errors, "--prove-guest-errors is not supported with --chunk-size");
+    }
+    Ok(())
+}
+
+/// Rejects chains whose proofs contracts could not check. Checked before any Steel work.
+/// Arbitrum: BLOCKHASH does not return L2 block hashes (and NUMBER is an L1 block number
+/// estimate), so a contract on Arbitrum cannot validate the commitment to an L2 block. The proof
+/// is still sound off-chain, which --offchain-commitment acknowledges.
+pub fn check_chain_commitment(chain: ChainName, block_number: u64, offchain_commitment: bool) -> Result<()> {
+    if chain == ChainName::Arbitrum {
+        anyhow::ensure!(
+            offchain_commitment,
+            "Contracts on Arbitrum cannot check a commitment to L2 block {}: BLOCKHASH does not return L2 block \
+             hashes. Pass --offchain-commitment to prove it for off-chain verification.",
+            block_number
+        );
+    }
+    Ok(())
+}
+
+/// Preflights with the first RPC endpoint, failing over to the next one (in --rpc-url order)
+/// if it fails. The Steel state cannot be shared between endpoints, so a failover starts over.
+///
+/// With a `chunk_size` (the prover's --chunk-size), the EVM input of every chunk is preflighted too.
+pub async fn preflight(
+    clients: &PreflightClients,
+    args: &SelectionArgs,
+    chunk_size: Option<u64>,
+    artifacts: &ArtifactDir,
+) -> Result<GuestInput> {
+    check_chunk_options(chunk_size, args.commit_hhi, args.commit_merkle_root)?;
+    let started = Instant::now();
+    let rpc_urls = &clients.rpc_urls;
+    for i in 0..rpc_urls.len() {
+        match preflight_with(clients, i, args, chunk_size, artifacts).await {
+            Ok(guest_input) => {
+                artifacts.record_timing("preflight", started.elapsed())?;
+                return Ok(guest_input);
+            }
+            Err(e) if i + 1 < rpc_urls.len() => {
+                warn!("Preflight with RPC endpoint #{} failed: {:#}. Failing over to endpoint #{}.", i + 1, e, i + 2);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    anyhow::bail!("At least one --rpc-url is required")
+}
+
+async fn preflight_with(
+    clients: &PreflightClients,
+    endpoint: usize,
+    args: &SelectionArgs,
+    chunk_size: Option<u64>,
+    artifacts: &ArtifactDir,
+) -> Result<GuestInput> {
+    let holders = artifacts.load_holders()?;
+    check_chain_commitment(holders.chain_spec, holders.block, args.offchain_commitment)?;
+    let n = args.n_top_holders.context("--n-top-holders is required")?;
+
+    info!("Preflighting {} candidates of {} at block {} (N: {})", holders.holders.len(), holders.token, holders.block, n);
+    info!("The guest will fetch balances on-chain, sort, verify total supply, and determine the Top {} holders.", n);
+
+    // --- Build the Steel Environment ---
+    // Pinned to the block the holders were fetched for. OP Stack chains need their own environment.
+    // Built by `preflight_env`, which needs another one for each chunk of a chunked proof.
+    #[cfg(feature = "op")]
+    if let Some(op_chain_spec) = top_n_holders_core::op_chain_spec(holders.chain_spec) {
+        anyhow::ensure!(args.fork_override.is_empty(), "--fork-override is not supported on OP Stack chains");
+        let provider = PrefetchProvider::new(clients.op_provider(endpoint).await?);
+        if !clients.is_checked(endpoint, holders.block) {
+            ensure_chain(&provider, holders.chain_spec).await?;
+            clients.set_checked(endpoint, holders.block);
+        }
+        let (block_number, env_provider) = (holders.block, &provider);
+        let build_env = move || async move {
+            risc0_op_steel::OpEvmEnv::builder()
+                .provider(env_provider.clone())
+                .chain_spec(op_chain_spec)
+                .block_number(block_number)
+                .build()
+                .await
+                .context("Failed to build OpEvmEnv from RPC")
+        };
+        return preflight_env(build_env, &provider, holders, n, args, chunk_size, artifacts).await;
+    }
+
+    let provider = PrefetchProvider::new(clients.provider(endpoint).await?);
+    let eth_chain_spec = top_n_holders_core::chain_spec(holders.chain_spec)
+        .with_context(|| format!("No Ethereum chain spec for {}", holders.chain_spec))?;
+    // The guest applies the same overrides, see `GuestInput::fork_overrides`.
+    let eth_chain_spec = with_fork_overrides(eth_chain_spec, &args.fork_override).map_err(anyhow::Error::msg)?;
+    if !args.fork_override.is_empty() {
+        info!("Applying fork overrides: {:?}", args.fork_override);
+    }
+    if !clients.is_checked(endpoint, holders.block) {
+        ensure_chain(&provider, holders.chain_spec).await?;
+        check_active_fork(&provider, holders.chain_spec, &eth_chain_spec, holders.block).await?;
+        clients.set_checked(endpoint, holders.block);
+    }
+    let (block_number, env_provider, env_chain_spec) = (holders.block, &provider, &eth_chain_spec);
+    let build_env = move || async move {
+        EthEvmEnv::builder()
+            .provider(env_provider.clone())
+            .chain_spec(env_chain_spec)
+            .block_number(block_number)
+            .build()
+            .await
+            .context("Failed to build EthEvmEnv from RPC")
+    };
+    preflight_env(build_env, &provider, holders, n, args, chunk_size, artifacts).await
+}
+
+/// Fails if the chain spec puts the proving block under another fork than its header shows, e.g.
+/// because of a placeholder fork timestamp. The proof would otherwise run under the wrong EVM rules.
+///
+/// Only for chains whose headers follow Ethereum's, where each of these forks adds a header field
+/// from its first block on.
+async fn check_active_fork(
+    provider: &PrefetchProvider,
+    chain: ChainName,
+    spec: &EthChainSpec,
+    block_number: u64,
+) -> Result<()> {
+    use ChainName::*;
+    if !matches!(chain, Mainnet | Sepolia | Holesky | Hoodi | Gnosis | Chiado) {
+        return Ok(());
+    }
+    let header = provider
+        .get_block_by_number(block_number.into())
+        .await
+        .with_context(|| format!("Failed to fetch block {}", block_number))?
+        .with_context(|| format!("RPC endpoint has no block {}", block_number))?
+        .header;
+    let header_fork = if header.requests_hash.is_some() {
+        Some(SpecId::PRAGUE)
+    } else if header.parent_beacon_block_root.is_some() {
+        Some(SpecId::CANCUN)
+    } else if header.withdrawals_root.is_some() {
+        Some(SpecId::SHANGHAI)
+    } else if header.base_fee_per_gas.is_some() {
+        Some(SpecId::LONDON)
+    } else {
+        None
+    };
+    let active = active_spec_id(spec, block_number, header.timestamp);
+    // The newest of the forks a header shows that the spec's active fork includes.
+    let spec_fork = active.and_then(|active| {
+        [SpecId::PRAGUE, SpecId::CANCUN, SpecId::SHANGHAI, SpecId::LONDON].into_iter().find(|fork| *fork <= active)
+    });
+    let describe = |fork: Option<SpecId>| fork.map_or("pre-London".to_string(), |fork| format!("{:?}", fork));
+    anyhow::ensure!(
+        spec_fork == header_fork,
+        "The {} chain spec puts block {} under {}, but its header is a {} header. A fork activation in the \
+         chain spec is likely wrong; correct it with --fork-override.",
+        chain,
+        block_number,
+        describe(active),
+        describe(header_fork)
+    );
+    Ok(())
+}
+
+/// The preflight proper, in the Steel environment of the chain family.
+async fn preflight_env<N: Network, E: PreflightEnv, F: Future<Output = Result<E>>>(
+    build_env: impl Fn() -> F,
+    provider: &PrefetchProvider<N>,
+    holders: HolderArtifact,
+    n: usize,
+    args: &SelectionArgs,
+    chunk_size: Option<u64>,
+    artifacts: &ArtifactDir,
+) -> Result<GuestInput> {
+    let HolderArtifact {
+        chain_spec: chain,
+        token: erc20_contract_address,
+        block: block_number,
+        candidates_truncated,
+        holders: mut all_subgraph_holders,
+    } = holders;
+    let env_started = Instant::now();
+    let mut env = build_env().await?;
+    anyhow::ensure!(
+        env.block_number() == block_number,
+        "Steel environment was built at block {} instead of the pinned block {}",
+        env.block_number(),
+        block_number
+    );
+    let env_duration = env_started.elapsed();
+    info!("Steel environment built at block {} in {:?}", block_number, env_duration);
+
+    // --- Fetch Total Supply from Blockchain (using risc0-steel) ---
+    info!("Fetching total supply from blockchain via risc0-steel...");
+    // Failed calls are retried: a hole in the warmed state only surfaces when the guest runs.
+    let retry_policy = RetryPolicy {
+        max_attempts: args.preflight_max_attempts,
+        initial_backoff: Duration::from_millis(args.preflight_backoff_ms),
+        max_backoff: Duration::from_secs(30),
+        max_delay: Duration::from_secs(60),
+        jitter: 0.2,
+    };
+
+    info!(
+        "Calling {} on {}...",
+        IERC20::totalSupplyCall::SIGNATURE,
+        erc20_contract_address
+    );
+    let result_supply = with_retry_mut(&retry_policy, "totalSupply preflight", &mut env, move |env| {
+        Box::pin(async move {
+            metrics::PREFLIGHT_CALLS.inc();
+            env.call(erc20_contract_address, IERC20::totalSupplyCall {}).await.map_err(preflight_error)
+        })
+    })
+    .await
+    .context("Failed to call totalSupply via the Steel environment")?;
+
+    let onchain_total_supply: U256 = result_supply;
+
+    info!("On-chain Total Supply: {}", onchain_total_supply);
+
+    // --- Excluded Addresses ---
+    // Excluded holders are not ranked. Their balances are preflighted too, since the guest
+    // takes them out of the supply the remaining holders are ranked against.
+    let labels = match &args.labels_file {
+        Some(labels_file) => labels::load_labels(labels_file)?,
+        None => Default::default(),
+    };
+    let excluded_addresses = exclusions::excluded_addresses(args, &labels)?;
+    let sanctioned_addresses = exclusions::sanctioned_addresses(args)?;
+    let excluded: HashSet<Address> = excluded_addresses.iter().copied().collect();
+    let candidates_before = all_subgraph_holders.len();
+    all_subgraph_holders.retain(|holder| !excluded.contains(&holder.address));
+    anyhow::ensure!(
+        n <= all_subgraph_holders.len(),
+        "N ({}) exceeds the {} candidate holders of {} at block {}{}. Lower --n-top-holders.",
+        n,
+        all_subgraph_holders.len(),
+        erc20_contract_address,
+        block_number,
+        match candidates_before - all_subgraph_holders.len() {
+            0 => String::new(),
+            dropped => format!(" ({} more were excluded)", dropped),
+        }
+    );
+
+    // Paces the individual balanceOf calls (and the prefetching) below --rpc-rate-limit.
+    let rate_limiter = RateLimiter::new(args.rpc_rate_limit);
+
+    // Every preflighted balance, for checking the ranking before proving.
+    let mut preflight_balances: HashMap<Address, U256> = HashMap::new();
+    let mut excluded_balance = U256::ZERO;
+    for &excluded_address in &excluded_addresses {
+        rate_limiter.wait().await;
+        let balance = with_retry_mut(&retry_policy, "balanceOf preflight", &mut env, move |env| {
+            Box::pin(async move {
+                metrics::PREFLIGHT_CALLS.inc();
+                let call = IERC20::balanceOfCall { account: excluded_address };
+                env.call(erc20_contract_address, call).await.map_err(preflight_error)
+            })
+        })
+        .await
+        .with_context(|| format!("Failed to fetch balance of excluded address {}", excluded_address))?;
+        excluded_balance += balance;
+        preflight_balances.insert(excluded_address, balance);
+    }
+    let ranked_supply = onchain_total_supply
+        .checked_sub(excluded_balance)
+        .context("Excluded balances exceed the total supply")?;
+    if !excluded_addresses.is_empty() {
+        info!(
+            "Excluded {} addresses ({} candidates dropped) holding {}. Ranking against the remaining supply of {}.",
+            excluded_addresses.len(),
+            candidates_before - all_subgraph_holders.len(),
+            excluded_balance,
+            ranked_supply
+        );
+    }
+
+    // --- Prepare Input for ZKVM Guest ---
+    // The host provides its claim for the top N addresses: the holders by descending balance, up
+    // to the first one (the N-th or later) where the balance of the N-th exceeds what is left of
+    // the supply, see `top_n_holders_core::covers_remainder`. Usually that is more than N holders.
+    // The guest verifies the claim by fetching the balances, ensuring descending order and
+    // checking the same bound.
+    //
+    // The holder source may lag behind the proving block. So the first selection ranks by its
+    // balances, and once these holders are preflighted, the candidates are ranked again by their
+    // on-chain balances. Holders this brings in are preflighted in turn, until every required
+    // holder is; the claim then reflects the state at the proving block.
+    let balance_preflight = BalancePreflight {
+        args,
+        chain,
+        token: erc20_contract_address,
+        block_number,
+        retry_policy,
+        rate_limiter: &rate_limiter,
+    };
+    let mut rounds = 0;
+    let (guest_input, required) = loop {
+        let ranked = all_subgraph_holders.iter().map(|holder| HolderData {
+            address: holder.address,
+            balance: preflight_balances.get(&holder.address).copied().unwrap_or(holder.balance),
+        });
+        let required = select_required(ranked, n, ranked_supply);
+        if !required.complete && candidates_truncated {
+            anyhow::bail!(
+                "The top {} candidates do not cover enough of the total supply to prove the Top {}. Increase --max-candidates.",
+                all_subgraph_holders.len(),
+                n
+            );
+        }
+
+        // The guest's checks of the input itself, before the balances are preflighted for it.
+        let guest_input = GuestInput::builder(erc20_contract_address, chain)
+            .holders(required.holders.iter().map(|holder| holder.address).collect())
+            .n(n)
+            .excluded_addresses(excluded_addresses.clone())
+            .fork_overrides(args.fork_override.clone())
+            .commit_hhi(args.commit_hhi)
+            .commit_merkle_root(args.commit_merkle_root.then_some(args.merkle_leaves))
+            .sanctioned_addresses(sanctioned_addresses.clone())
+            .build()
+            .map_err(|e| anyhow::anyhow!("Invalid guest input: {}", e))?;
+
+        let missing: Vec<Address> = guest_input
+            .required_addresses_desc
+            .iter()
+            .copied()
+            .filter(|address| !preflight_balances.contains_key(address))
+            .collect();
+        if missing.is_empty() {
+            break (guest_input, required);
+        }
+        if rounds > 0 {
+            info!("Ranked by the on-chain balances, the Top {} requires {} more holder(s).", n, missing.len());
+        }
+        rounds += 1;
+        preflight_holder_balances(&mut env, provider, &missing, balance_preflight, &mut preflight_balances).await?;
+    };
+
+    let top_n_addresses = &guest_input.required_addresses_desc[..n];
+    let extra_addresses = &guest_input.required_addresses_desc[n..];
+    let accumulated_balance: U256 = required.holders.iter().map(|holder| holder.balance).sum();
+    let last_holder_balance = required.holders.last().map(|holder| holder.balance).unwrap_or_default();
+    info!("Top-N addresses ({}): {:?}", top_n_addresses.len(), top_n_addresses);
+    info!("Extra addresses required for proof ({}): {:?}", extra_addresses.len(), extra_addresses);
+    info!("Accumulated/Last holder balance: {} / {}", accumulated_balance, last_holder_balance);
+    info!(
+        "Required holders ({}), ranked by their on-chain balances after {} preflight round(s): {:?}",
+        guest_input.required_addresses_desc.len(),
+        rounds,
+        guest_input.required_addresses_desc
+    );
+
+    // --- Check ---
+    // The guest's own ranking checks, run on the preflighted balances: a proof that would fail
+    // (e.g. because the Subgraph is stale) is caught here instead of after proving.
+    let ranking = rank_holders(&guest_input, onchain_total_supply, |account| {
+        preflight_balances.get(&account).copied().ok_or(VerificationFailure::BalanceCallFailed(account))
+    })
+    .map_err(|reason| {
+        for line in ordering_diagnostics(&guest_input.required_addresses_desc, &all_subgraph_holders, &preflight_balances) {
+            error!("{}", line);
+        }
+        failure::tag(anyhow::anyhow!("Check failed, the guest would reject this input: {}", reason), Failure::GuestVerification)
+    })?;
+    info!("Check passed: the guest will verify the Top {} over {} holders.", n, ranking.holders.len());
+
+    let (symbol, decimals) = token_metadata(provider, erc20_contract_address, block_number).await;
+    let accounts = accounts::classify(
+        provider,
+        &ranking.holders,
+        block_number,
+        args.detect_proxies,
+        usize::from(args.preflight_concurrency),
+        &rate_limiter,
+    )
+    .await;
+    let ens_names = if chain == ChainName::Mainnet && !args.no_ens {
+        ens::primary_names(provider, &ranking.holders, block_number, usize::from(args.preflight_concurrency), &rate_limiter)
+            .await
+    } else {
+        Default::default()
+    };
+    let token = TokenArtifact {
+        symbol,
+        decimals,
+        total_supply: onchain_total_supply,
+        balances: preflight_balances.into_iter().collect(),
+        accounts,
+        ens_names,
+        labels: ranking
+            .holders
+            .iter()
+            .filter_map(|address| labels.get(address).map(|label| (*address, label.label.clone())))
+            .filter(|(_, label)| !label.is_empty())
+            .collect(),
+    };
+
+    // --- Chunk Inputs ---
+    // For a chunked proof, every chunk gets an EVM input of its own, preflighted in an environment
+    // of its own with only the calls of that chunk: the whole input would put the storage proofs of
+    // every holder into every chunk. Prefetched slots are served from memory again.
+    let chunk_size = chunk_size
+        .map(|size| usize::try_from(size).unwrap_or(usize::MAX))
+        .filter(|&size| size < guest_input.required_addresses_desc.len());
+    let mut chunk_evm_inputs = Vec::new();
+    if let Some(chunk_size) = chunk_size {
+        let chunks: Vec<&[Address]> = guest_input.required_addresses_desc.chunks(chunk_size).collect();
+        let prefetched = args.multicall3 || args.preflight_concurrency > 1;
+        let bar = progress::counter(chunks.len() as u64, "Preflighting chunk inputs");
+        for (index, chunk) in chunks.iter().enumerate() {
+            let mut chunk_env = build_env().await?;
+            // The first chunk also takes the excluded balances out of the supply, see `verify_chunk`.
+            let mut accounts: Vec<Address> = Vec::new();
+            if index == 0 {
+                with_retry_mut(&retry_policy, "totalSupply preflight", &mut chunk_env, move |env| {
+                    Box::pin(async move {
+                        metrics::PREFLIGHT_CALLS.inc();
+                        env.call(erc20_contract_address, IERC20::totalSupplyCall {}).await.map_err(preflight_error)
+                    })
+                })
+                .await
+                .context("Failed to call totalSupply via the Steel environment")?;
+                accounts.extend(&guest_input.excluded_addresses);
+            }
+            accounts.extend(chunk.iter());
+            for account in accounts {
+                if !prefetched {
+                    rate_limiter.wait().await;
+                }
+                with_retry_mut(&retry_policy, "balanceOf preflight", &mut chunk_env, move |env| {
+                    Box::pin(async move {
+                        metrics::PREFLIGHT_CALLS.inc();
+                        env.call(erc20_contract_address, IERC20::balanceOfCall { account }).await.map_err(preflight_error)
+                    })
+                })
+                .await
+                .with_context(|| format!("Failed to preflight the balance of {} for chunk {}", account, index + 1))?;
+            }
+            chunk_evm_inputs.push(chunk_env.into_evm_input().await?);
+            bar.inc(1);
+        }
+        bar.finish_and_clear();
+        info!("Preflighted the EVM inputs of {} chunks of up to {} holders.", chunks.len(), chunk_size);
+    }
+
+    let input_started = Instant::now();
+    let evm_input = env.into_evm_input().await?;
+    let env_duration = env_duration + input_started.elapsed();
+    artifacts.store_preflight(&guest_input, &evm_input)?;
+    if let Some(chunk_size) = chunk_size {
+        artifacts.store_chunk_evm_inputs(chunk_size, &chunk_evm_inputs)?;
+    }
+    artifacts.store_token(&token)?;
+    // Recorded before "preflight", which is only known once the failover loop is done.
+    artifacts.record_timing("build env", env_duration)?;
+    if let Some(archive) = &args.evm_input_archive {
+        let name = format!("{}-{:#x}-{}", guest_input.chain, erc20_contract_address, block_number);
+        ArtifactDir::new(&archive.join(name)).store_preflight(&guest_input, &evm_input)?;
+    }
+    Ok(guest_input)
+}
+
+/// How balances are preflighted; the same for every preflight round.
+#[derive(Clone, Copy)]
+struct BalancePreflight<'a> {
+    args: &'a SelectionArgs,
+    chain: ChainName,
+    token: Address,
+    block_number: u64,
+    retry_policy: RetryPolicy,
+    rate_limiter: &'a RateLimiter,
+}
+
+/// Preflights `balanceOf` of every one of `holders` in `env`, recording the balances in `balances`.
+async fn preflight_holder_balances<N: Network, E: PreflightEnv>(
+    env: &mut E,
+    provider: &PrefetchProvider<N>,
+    holders: &[Address],
+    preflight: BalancePreflight<'_>,
+    balances: &mut HashMap<Address, U256>,
+) -> Result<()> {
+    let BalancePreflight { args, chain, token: erc20_contract_address, block_number, retry_policy, rate_limiter } =
+        preflight;
+    info!("Fetching balances of {} holders from blockchain via risc0-steel...", holders.len());
+
+    // Only the calls the guest makes are preflighted in the Steel environment: every account and
+    // storage slot they touch ends up in the EVM input, and is proven (and hashed) by the guest.
+    // Everything else, Multicall3 included, goes to the provider directly.
+    let prefetched = if args.multicall3 {
+        info!("Using Multicall3 to fetch balances...");
+        // --- Multicall3 Setup ---
+        // The canonical deployment (https://github.com/mds1/multicall) unless --multicall3-address
+        // says otherwise. A missing contract would only fail as undecodable aggregate3 results.
+        let multicall3_address = args.multicall3_address;
+        let multicall3_code = provider
+            .get_code_at(multicall3_address)
+            .number(block_number)
+            .await
+            .with_context(|| format!("Failed to fetch the code of Multicall3 at {}", multicall3_address))?;
+        anyhow::ensure!(
+            !multicall3_code.is_empty(),
+            "No Multicall3 contract at {} on {} at block {}. Set --multicall3-address or drop --multicall3.",
+            multicall3_address,
+            chain,
+            block_number
+        );
+
+        // Each aggregate3 checks that balanceOf succeeds for its holders, and its access list
+        // names the storage they read, which is prefetched for the individual calls below.
+        let chunk_size = usize::try_from(args.multicall_chunk_size).unwrap_or(usize::MAX);
+        let chunks = holders.len().div_ceil(chunk_size);
+        info!(
+            "Preparing to call aggregate3 on Multicall3 contract at {} ({} calls of up to {} balances)",
+            multicall3_address,
+            chunks,
+            chunk_size
+        );
+        let bar = progress::counter(holders.len() as u64, "Prefetching balances via Multicall3");
+        let mut failed_addresses = Vec::new();
+        for (chunk_index, chunk) in holders.chunks(chunk_size).enumerate() {
+            let calls: Vec<IMulticall3::Call3> = chunk
+                .iter()
+                .map(|&addr| {
+                    let balance_of_call = IERC20::balanceOfCall { account: addr };
+                    IMulticall3::Call3 {
+                        target: erc20_contract_address, // The ERC20 token contract
+                        allowFailure: true, // Allow individual calls to fail
+                        callData: balance_of_call.abi_encode().into(),
+                    }
+                })
+                .collect();
+
+            let aggregate_input: Bytes = IMulticall3::aggregate3Call { calls }.abi_encode().into();
+
+            rate_limiter.wait().await;
+            let multicall_results = with_retry(&retry_policy, "aggregate3", || {
+                let tx = N::TransactionRequest::default()
+                    .with_to(multicall3_address)
+                    .with_input(aggregate_input.clone());
+                async move {
+                    let output = provider.call(tx).block(BlockId::number(block_number)).await.map_err(preflight_error)?;
+                    Ok(IMulticall3::aggregate3Call::abi_decode_returns(&output)?)
+                }
+            })
+            .await
+            .with_context(|| {
+                    format!("Failed to call aggregate3 on Multicall3 contract (batch {}/{})", chunk_index + 1, chunks)
+                })?;
+
+            info!(
+                "Multicall3 aggregate3 call {}/{} successful. Processing {} results...",
+                chunk_index + 1,
+                chunks,
+                multicall_results.len()
+            );
+
+            for (result, &holder_address) in multicall_results.iter().zip(chunk) {
+                if !result.success {
+                    error!("balanceOf call failed for address {} in multicall", holder_address);
+                    failed_addresses.push(holder_address);
+                } else if let Err(e) = IERC20::balanceOfCall::abi_decode_returns(&result.returnData) {
+                    error!("Failed to decode balanceOf return data for {}: {:?}", holder_address, e);
+                    failed_addresses.push(holder_address);
+                }
+            }
+
+            let label = format!("aggregate3 batch {}/{}", chunk_index + 1, chunks);
+            match provider
+                .prefetch_call(
+                    multicall3_address,
+                    aggregate_input,
+                    &label,
+                    block_number,
+                    args.preflight_concurrency.into(),
+                    rate_limiter,
+                )
+                .await
+            {
+                Ok(slots) => info!("Prefetched {} storage slots for {}", slots, label),
+                Err(e) => warn!("{:#}", e), // Steel reads the slots itself.
+            }
+            bar.inc(chunk.len() as u64);
+        }
+        bar.finish_and_clear();
+        // The guest calls balanceOf for every required holder, so it would fail on these too.
+        anyhow::ensure!(
+            failed_addresses.is_empty(),
+            "balanceOf failed for {} required holder(s) in Multicall3: {:?}. Aborting before proving, as the guest cannot succeed.",
+            failed_addresses.len(),
+            failed_addresses
+        );
+        true
+    } else {
+        info!("Fetching balances individually (not using Multicall3)...");
+
+        let prefetched = args.preflight_concurrency > 1;
+        if prefetched {
+            let bar = progress::spinner(format!("Prefetching the storage of {} balances", holders.len()));
+            provider
+                .prefetch_balances(
+                    erc20_contract_address,
+                    holders,
+                    block_number,
+                    args.preflight_concurrency.into(),
+                    rate_limiter,
+                )
+                .await;
+            bar.finish_and_clear();
+        }
+        prefetched
+    };
+
+    let bar = progress::counter(holders.len() as u64, "Preflighting balances");
+    for (i, &holder_address) in holders.iter().enumerate() {
+        info!("Fetching balance for address {} ({}/{})", holder_address, i + 1, holders.len());
+        if !prefetched {
+            rate_limiter.wait().await; // Prefetched reads are served from memory.
+        }
+
+        let balance = with_retry_mut(&retry_policy, "balanceOf preflight", env, move |env| {
+            Box::pin(async move {
+                metrics::PREFLIGHT_CALLS.inc();
+                let call = IERC20::balanceOfCall { account: holder_address };
+                env.call(erc20_contract_address, call).await.map_err(preflight_error)
+            })
+        })
+        .await;
+        match balance {
+            Ok(result_balance) => {
+                let balance: U256 = result_balance;
+                info!("Successfully fetched balance for {}: {}", holder_address, balance);
+                balances.insert(holder_address, balance);
+            }
+            Err(e) => {
+                // The state of this call would be missing from the input, and the guest would
+                // fail on it only after the expensive steps. Dropping the holder instead would
+                // break the supply accounting, so abort.
+                bar.finish_and_clear();
+                return Err(e).with_context(|| {
+                    format!("Failed to preflight the balance of {}. Aborting before proving.", holder_address)
+                });
+            }
+        }
+        bar.inc(1);
+    }
+    bar.finish_and_clear();
+    info!("Finished preflighting balances for {} addresses.", holders.len());
+    Ok(())
+}
+
+/// Where the on-chain balances break the descending order the holder source reported, with both
+/// balances side by side. Usually the holder source lags behind the proving block.
+fn ordering_diagnostics(
+    required_addresses_desc: &[Address],
+    source_holders: &[HolderData],
+    onchain_balances: &HashMap<Address, U256>,
+) -> Vec<String> {
+    const MAX_VIOLATIONS: usize = 10;
+    let source_balances: HashMap<Address, U256> =
+        source_holders.iter().map(|holder| (holder.address, holder.balance)).collect();
+    let describe = |rank: usize, address: &Address| {
+        format!(
+            "#{} {} (source: {}, on-chain: {})",
+            rank,
+            address,
+            source_balances.get(address).copied().unwrap_or_default(),
+            onchain_balances.get(address).copied().unwrap_or_default()
+        )
+    };
+
+    let onchain = |address: &Address| onchain_balances.get(address).copied().unwrap_or_default();
+    let violations: Vec<usize> = (1..required_addresses_desc.len())
+        .filter(|&i| onchain(&required_addresses_desc[i]) > onchain(&required_addresses_desc[i - 1]))
+        .collect();
+    if violations.is_empty() {
+        return Vec::new();
+    }
+    let changed = required_addresses_desc
+        .iter()
+        .filter(|address| source_balances.get(*address).copied().unwrap_or_default() != onchain(address))
+        .count();
+
+    let mut lines = vec![format!(
+        "The on-chain balances break the descending order at {} position(s); {} of {} balances differ from the holder source:",
+        violations.len(),
+        changed,
+        required_addresses_desc.len()
+    )];
+    for &i in violations.iter().take(MAX_VIOLATIONS) {
+        lines.push(format!(
+            "  {} holds more than {}",
+            describe(i + 1, &required_addresses_desc[i]),
+            describe(i, &required_addresses_desc[i - 1])
+        ));
+    }
+    if violations.len() > MAX_VIOLATIONS {
+        lines.push(format!("  ... and {} more", violations.len() - MAX_VIOLATIONS));
+    }
+    lines
+}
+
+/// `symbol()` and `decimals()` of the token, for display only. Both are optional in ERC20, so a
+/// failed call is logged and skipped.
+async fn token_metadata<N: Network>(
+    provider: &impl Provider<N>,
+    token: Address,
+    block_number: u64,
+) -> (Option<String>, Option<u8>) {
+    async fn call<N: Network, C: SolCall>(
+        provider: &impl Provider<N>,
+        token: Address,
+        block_number: u64,
+        call: C,
+    ) -> Result<C::Return> {
+        let tx = N::TransactionRequest::default().with_to(token).with_input(call.abi_encode());
+        let output = provider.call(tx).block(BlockId::number(block_number)).await?;
+        Ok(C::abi_decode_returns(&output)?)
+    }
+
+    let symbol = call(provider, token, block_number, IERC20::symbolCall {}).await;
+    let decimals = call(provider, token, block_number, IERC20::decimalsCall {}).await;
+    if let Err(e) = &symbol {
+        warn!("Failed to fetch symbol() of {}: {:#}", token, e);
+    }
+    if let Err(e) = &decimals {
+        warn!("Failed to fetch decimals() of {}, balances are shown in base units: {:#}", token, e);
+    }
+    (symbol.ok(), decimals.ok())
+}
+
+// --- Prove ---
+pub fn prove(backend: &dyn ProverBackend, args: &ProverArgs, artifacts: &ArtifactDir) -> Result<Receipt> {
+    let (guest_input, evm_input) = artifacts.load_preflight()?;
+    check_chunk_options(args.chunk_size, guest_input.commit_hhi, guest_input.commit_merkle_root.is_some())?;
+    check_chunk_prover_options(args)?;
+
+    // The chunks are proven without executing the whole input first: that execution is what
+    // --chunk-size avoids for candidate sets too large for one proof.
+    let candidates = guest_input.required_addresses_desc.len();
+    let chunk_size = args.chunk_size.map(|size| usize::try_from(size).unwrap_or(usize::MAX));
+    if let Some(chunk_size) = chunk_size.filter(|&size| size < candidates) {
+        if args.profile {
+            warn!("--profile is ignored with --chunk-size, which skips the execution; profile with --execute-only");
+        }
+        if !args.yes && std::io::stdin().is_terminal() {
+            let workload = format!("{} chunks of up to {} holders", candidates.div_ceil(chunk_size), chunk_size);
+            confirm_proving(backend, args, &guest_input, &workload)?;
+        }
+        let receipt = prove_chunked(backend, args, artifacts, &guest_input, &evm_input, chunk_size)?;
+        artifacts.store_receipt(&receipt)?;
+        return Ok(receipt);
+    }
+
+    // Executing first is cheap compared to proving: it fails fast on guest errors and tells
+    // how many segments the prover has to work through.
+    let started = Instant::now();
+    let execution = run_executor(&guest_input, &evm_input, args.into(), artifacts)?;
+    let segments = execution.segments;
+    artifacts.record_timing("execute", started.elapsed())?;
+
+    if !args.yes && std::io::stdin().is_terminal() {
+        let workload = format!("{} cycles in {} segments", execution.cycles, segments);
+        confirm_proving(backend, args, &guest_input, &workload)?;
+    }
+
+    info!("Running the {} prover ({:?} receipt)...", backend.name(), args.receipt_kind);
+    let bar = progress::spinner(format!("Proving {} segments", segments));
+    let timer = metrics::PROOF_DURATION.start_timer();
+    let receipt = backend.prove(
+        executor_env_builder(&guest_input, &evm_input, args.segment_limit_po2)?.build()?,
+        TOP_N_HOLDERS_GUEST_ELF,
+        &args.prover_opts(),
+    );
+    let proving_seconds = timer.stop_and_record();
+    bar.finish_and_clear();
+    let receipt = receipt?;
+    info!("Proof generated successfully!");
+    artifacts.record_timing("prove", Duration::from_secs_f64(proving_seconds))?;
+
+    artifacts.store_receipt(&receipt)?;
+    Ok(receipt)
+}
+
+/// Proves the candidates in chunks of `chunk_size`, --chunk-workers at a time, and joins the chunk
+/// receipts with the aggregation guest. Chunk receipts of an earlier, interrupted run are reused.
+fn prove_chunked(
+    backend: &dyn ProverBackend,
+    args: &ProverArgs,
+    artifacts: &ArtifactDir,
+    guest_input: &GuestInput,
+    evm_input: &EvmInput,
+    chunk_size: usize,
+) -> Result<Receipt> {
+    let started = Instant::now();
+    let holders = &guest_input.required_addresses_desc;
+    let chunk_inputs: Vec<GuestInput> = holders
+        .chunks(chunk_size)
+        .enumerate()
+        .map(|(index, chunk)| GuestInput {
+            required_addresses_desc: chunk.to_vec(),
+            chunk: Some(ChunkRange { start: index * chunk_size, total: holders.len() }),
+            ..guest_input.clone()
+        })
+        .collect();
+    let chunk_count = chunk_inputs.len();
+    info!("Proving {} holders in {} chunks of up to {}...", holders.len(), chunk_count, chunk_size);
+    // Each chunk's own EVM input, if the preflight ran with the same --chunk-size.
+    let chunk_evm_inputs = artifacts
+        .load_chunk_evm_inputs(chunk_size, guest_input.chain)?
+        .filter(|evm_inputs| evm_inputs.len() == chunk_count);
+    if chunk_evm_inputs.is_none() {
+        warn!(
+            "No chunk EVM inputs were preflighted for --chunk-size {}, so every chunk carries the whole \
+             EVM input. Preflight with the same --chunk-size for smaller chunk inputs.",
+            chunk_size
+        );
+    }
+
+    let mut chunk_receipts: Vec<Option<Receipt>> = Vec::with_capacity(chunk_count);
+    for (index, chunk_input) in chunk_inputs.iter().enumerate() {
+        let receipt = match artifacts.load_chunk_receipt(index)? {
+            Some(receipt) => {
+                let chunk_evm_input = chunk_evm_inputs.as_ref().map_or(evm_input, |evm_inputs| &evm_inputs[index]);
+                let commitment = chunk_evm_input.commitment(chunk_input.chain, &chunk_input.fork_overrides)?;
+                chunk_receipt_matches(&receipt, chunk_input, &commitment).then_some(receipt)
+            }
+            None => None,
+        };
+        if receipt.is_some() {
+            info!("Reusing the receipt of chunk {}/{}", index + 1, chunk_count);
+        }
+        chunk_receipts.push(receipt);
+    }
+    let pending: Vec<usize> = (0..chunk_count).filter(|&index| chunk_receipts[index].is_none()).collect();
+
+    // --- Workers ---
+    // Each takes the next pending chunk until none is left, or until a chunk failed. The time of
+    // the run is bounded by the slowest worker rather than the sum of the chunks.
+    let workers = usize::from(args.chunk_workers).min(pending.len());
+    if workers > 1 {
+        info!("Proving {} chunks with {} workers", pending.len(), workers);
+    }
+    let next = AtomicUsize::new(0);
+    let proven = Mutex::new(Vec::with_capacity(pending.len()));
+    let failure: Mutex<Option<anyhow::Error>> = Mutex::new(None);
+    let bar = progress::counter(pending.len() as u64, "Proving chunks");
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                if failure.lock().unwrap_or_else(PoisonError::into_inner).is_some() {
+                    return;
+                }
+                let Some(&index) = pending.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                    return;
+                };
+                let chunk_input = &chunk_inputs[index];
+                let range = chunk_input.chunk.expect("chunk inputs have a range");
+                info!(
+                    "Proving chunk {}/{} (holders {}..{})",
+                    index + 1,
+                    chunk_count,
+                    range.start,
+                    range.start + chunk_input.required_addresses_desc.len()
+                );
+                // Assumptions have to be succinct receipts.
+                let chunk_evm_input = chunk_evm_inputs.as_ref().map_or(evm_input, |evm_inputs| &evm_inputs[index]);
+                let receipt = executor_env_builder(chunk_input, chunk_evm_input, args.segment_limit_po2)
+                    .and_then(|mut builder| builder.build())
+                    .and_then(|env| backend.prove(env, TOP_N_HOLDERS_GUEST_ELF, &ReceiptKind::Succinct.prover_opts()))
+                    .and_then(|receipt| artifacts.store_chunk_receipt(index, &receipt).map(|()| receipt))
+                    .with_context(|| format!("Failed to prove chunk {}", index + 1));
+                match receipt {
+                    Ok(receipt) => {
+                        bar.inc(1);
+                        proven.lock().unwrap_or_else(PoisonError::into_inner).push((index, receipt));
+                    }
+                    Err(e) => {
+                        failure.lock().unwrap_or_else(PoisonError::into_inner).get_or_insert(e);
+                        return;
+                    }
+                }
+            });
+        }
+    });
+    bar.finish_and_clear();
+    if let Some(e) = failure.into_inner().unwrap_or_else(PoisonError::into_inner) {
+        return Err(e);
+    }
+    for (index, receipt) in proven.into_inner().unwrap_or_else(PoisonError::into_inner) {
+        chunk_receipts[index] = Some(receipt);
+    }
+    let chunk_receipts: Vec<Receipt> = chunk_receipts.into_iter().map(|receipt| receipt.expect("every chunk is proven")).collect();
+    artifacts.record_timing("prove chunks", started.elapsed())?;
+
+    let started = Instant::now();
+    info!("Joining {} chunk receipts ({:?} receipt)...", chunk_receipts.len(), args.receipt_kind);
+    let mut builder = ExecutorEnv::builder();
+    builder.write(&(guest_input.n as u64))?;
+    builder.write(&TOP_N_HOLDERS_GUEST_ID)?;
+    builder.write(&(chunk_receipts.len() as u32))?;
+    for receipt in chunk_receipts {
+        builder.write_frame(&receipt.journal.bytes);
+        builder.add_assumption(receipt);
+    }
+    let bar = progress::spinner("Proving the aggregation".to_string());
+    let receipt = backend.prove(builder.build()?, TOP_N_HOLDERS_AGGREGATE_ELF, &args.prover_opts());
+    bar.finish_and_clear();
+    let receipt = receipt.context("Failed to prove the aggregation of the chunks")?;
+    info!("Proof generated successfully!");
+    artifacts.record_timing("prove", started.elapsed())?;
+    Ok(receipt)
+}
+
+/// Whether a stored chunk receipt proves `chunk_input` at the block of `commitment`, the commitment
+/// of the chunk's EVM input, as far as its journal tells.
+fn chunk_receipt_matches(receipt: &Receipt, chunk_input: &GuestInput, commitment: &Commitment) -> bool {
+    if receipt.verify(TOP_N_HOLDERS_GUEST_ID).is_err() {
+        return false;
+    }
+    receipt.journal.decode::<ChunkOutput>().is_ok_and(|output| {
+        output.commitment == *commitment
+            && output.chain == chunk_input.chain
+            && Some(output.range) == chunk_input.chunk
+            && output.n == chunk_input.n
+            && output.holders == chunk_input.required_addresses_desc
+            && output.erc20_contract_address == chunk_input.erc20_contract_address
+            && output.excluded_addresses == chunk_input.excluded_addresses
+            && output.fork_overrides == chunk_input.fork_overrides
+            && output.sanctions_list_hash == chunk_input.sanctions_list_hash()
+    })
+}
+
+/// Cost of one guest execution.
+pub struct Execution {
+    pub output: GuestOutput,
+    pub cycles: u64,      // Total cycles, i.e. what the prover works through.
+    pub user_cycles: u64, // Cycles spent by the guest itself.
+    pub segments: usize,
+}
+
+/// How the guest is executed, see `ProverArgs`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExecuteOptions {
+    pub segment_limit_po2: Option<u32>,
+    pub profile: bool, // Under the profiler, storing the profiles in the artifacts.
+}
+
+impl From<&ProverArgs> for ExecuteOptions {
+    fn from(args: &ProverArgs) -> Self {
+        Self { segment_limit_po2: args.segment_limit_po2, profile: args.profile }
+    }
+}
+
+/// Executes the guest on the preflighted input without proving (`--execute-only`), and prints
+/// the journal it would commit.
+pub fn execute(artifacts: &ArtifactDir, options: ExecuteOptions) -> Result<Execution> {
+    let (guest_input, evm_input) = artifacts.load_preflight()?;
+    let execution = run_executor(&guest_input, &evm_input, options, artifacts)?;
+
+    let output = &execution.output;
+    info!("Execution Result (not proven):");
+    info!("Guest Verification Succeeded: {}", output.verification_succeeded);
+    if let Some(failure) = &output.failure {
+        error!("Guest Verification Failure: {}", failure);
+    }
+    if !output.excluded_addresses.is_empty() {
+        info!("Excluded Addresses ({}): {:?}", output.excluded_addresses.len(), output.excluded_addresses);
+    }
+    if !output.fork_overrides.is_empty() {
+        info!("Fork Overrides: {:?}", output.fork_overrides);
+    }
+    let (committed_block, _) = output.commitment.decode_id();
+    info!("Committed Block: {} (hash {})", committed_block, output.commitment.digest);
+    info!("Guest Determined Top {} Addresses: {:?}", output.final_top_n_addresses.len(), output.final_top_n_addresses);
+    summary::print(artifacts, output)?;
+    info!(
+        "Total cycles: {} ({} user cycles) in {} segments",
+        execution.cycles, execution.user_cycles, execution.segments
+    );
+    Ok(execution)
+}
+
+/// Asks before a proof that may take hours (or cost money), e.g. after a mistyped N.
+fn confirm_proving(backend: &dyn ProverBackend, args: &ProverArgs, guest_input: &GuestInput, workload: &str) -> Result<()> {
+    eprintln!();
+    eprintln!("About to prove the Top {} of {}:", guest_input.n, guest_input.erc20_contract_address);
+    eprintln!("  Candidates:  {} ({} excluded)", guest_input.required_addresses_desc.len(), guest_input.excluded_addresses.len());
+    eprintln!("  Workload:    {}", workload);
+    eprintln!("  Backend:     {} ({:?} receipt)", backend.name(), args.receipt_kind);
+    eprint!("Start proving? [y/N] ");
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).context("Failed to read the confirmation")?;
+    anyhow::ensure!(
+        matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"),
+        "Proving cancelled (pass --yes to skip the confirmation)"
+    );
+    Ok(())
+}
+
+fn run_executor(
+    guest_input: &GuestInput,
+    evm_input: &EvmInput,
+    options: ExecuteOptions,
+    artifacts: &ArtifactDir,
+) -> Result<Execution> {
+    info!("Executing the guest with Risk Zero zkVM...");
+    let profile = options.profile.then_some(artifacts);
+    // Only a profiled execution has the guest record its cycles; proofs never do.
+    let guest_input = &GuestInput { profile_cycles: options.profile, ..guest_input.clone() };
+    let mut stdout = Vec::new();
+    let mut builder = executor_env_builder(guest_input, evm_input, options.segment_limit_po2)?;
+    builder.stdout(&mut stdout);
+    if let Some(artifacts) = profile {
+        builder.enable_profiler(artifacts.pprof_path());
+    }
+    let env = builder.build()?;
+    let session = default_executor().execute(env, TOP_N_HOLDERS_GUEST_ELF)?;
+    let execution = Execution {
+        output: session.journal.decode().context("Failed to decode GuestOutput from the execution journal")?,
+        cycles: session.cycles(),
+        user_cycles: session.segments.iter().map(|segment| u64::from(segment.cycles)).sum(),
+        segments: session.segments.len(),
+    };
+    info!("Guest executed: {} cycles in {} segments", execution.cycles, execution.segments);
+    metrics::PROVER_CYCLES.set(execution.cycles as i64);
+    if let Some(artifacts) = profile {
+        let cycle_profile = CycleProfile::from_bytes(&stdout).map_err(anyhow::Error::msg)?;
+        log_cycle_profile(&cycle_profile);
+        artifacts.store_cycle_profile(&cycle_profile)?;
+        let pprof_path = artifacts.pprof_path();
+        anyhow::ensure!(pprof_path.exists(), "The executor did not write the pprof profile to {:?}", pprof_path);
+        info!("Wrote the pprof profile to {:?}", pprof_path);
+    }
+    Ok(execution)
+}
+
+fn log_cycle_profile(profile: &CycleProfile) {
+    let calls = &profile.balance_calls;
+    info!("Guest cycles per phase:");
+    info!("  Input:        {}", profile.input);
+    info!("  Steel setup:  {}", profile.env_setup);
+    info!("  totalSupply:  {}", profile.total_supply);
+    info!("  Ranking:      {} ({} balanceOf calls)", profile.ranking, calls.len());
+    if let (Some(min), Some(max)) = (calls.iter().min(), calls.iter().max()) {
+        let mean = calls.iter().sum::<u64>() / calls.len() as u64;
+        info!("  balanceOf:    {} mean, {} min, {} max", mean, min, max);
+    }
+}
+
+/// Marks preflight errors as transient unless the call reverted, which retrying cannot fix.
+fn preflight_error(err: impl Into<anyhow::Error>) -> anyhow::Error {
+    let err = err.into();
+    let reverted = err.chain().any(|cause| cause.to_string().to_lowercase().contains("revert"));
+    if reverted {
+        err
+    } else {
+        transient(err, None)
+    }
+}
+
+/// The guest reads its input first, as it names the chain and with it the type of the EVM input.
+fn executor_env_builder<'a>(
+    guest_input: &GuestInput,
+    evm_input: &EvmInput,
+    segment_limit_po2: Option<u32>,
+) -> Result<ExecutorEnvBuilder<'a>> {
+    let mut builder = ExecutorEnv::builder();
+    if let Some(po2) = segment_limit_po2 {
+        builder.segment_limit_po2(po2);
+    }
+    builder.write_frame(&guest_input.to_compact());
+    evm_input.write_to(&mut builder)?;
+    Ok(builder)
+}
+
+// --- Verify ---
+
+/// Result of the verify stage.
+pub struct VerifiedProof {
+    pub output: GuestOutput,
+    pub image_id: Digest,       // Of the guest (or the aggregation guest) that produced the journal.
+    pub journal_digest: Digest, // SHA-256 of the journal, as checked by on-chain verifiers.
+}
+
+pub fn verify(artifacts: &ArtifactDir) -> Result<VerifiedProof> {
+    let started = Instant::now();
+    let receipt = artifacts.load_receipt()?;
+
+    let (guest_output, image_id) = verify_receipt(&receipt)?;
+    info!("Receipt verified locally successfully!");
+    let n = guest_output.final_top_n_addresses.len();
+
+    info!("Verification Result (from ZK proof journal):");
+    info!("Guest Verification Succeeded: {}", guest_output.verification_succeeded);
+    if !guest_output.excluded_addresses.is_empty() {
+        info!("Excluded Addresses ({}): {:?}", guest_output.excluded_addresses.len(), guest_output.excluded_addresses);
+        info!("Excluded Set Hash: {}", guest_output.excluded_addresses_hash);
+    }
+    if let Some(sanctions_list_hash) = guest_output.sanctions_list_hash {
+        info!("No ranked holder is on the sanctions list with hash {}", sanctions_list_hash);
+    }
+    if let Some(merkle_root) = guest_output.top_n_merkle_root {
+        info!("Merkle Root of the Top {}: {}", n, merkle_root);
+    }
+    if !guest_output.fork_overrides.is_empty() {
+        info!("Fork Overrides: {:?}", guest_output.fork_overrides);
+    }
+    let (committed_block, _) = guest_output.commitment.decode_id();
+    info!("Committed Block: {} (hash {})", committed_block, guest_output.commitment.digest);
+    info!("Guest Determined Top {} Addresses: {:?}", n, guest_output.final_top_n_addresses);
+    info!("(Proof implies guest correctly fetched balances, sorted, checked total supply, and compared against host's claimed Top {} addresses)", n);
+
+    summary::print(artifacts, &guest_output)?;
+
+    info!("Data for On-Chain Verification:");
+    info!("Image ID: {}", image_id);
+    info!("Journal (Hex): 0x{}", hex::encode(&receipt.journal.bytes));
+
+    if guest_output.verification_succeeded {
+        info!("Conclusion: The ZK proof confirms the guest correctly determined the Top {} holders, verified total supply, and that these match the host's initial claim.", n);
+        info!("The determined Top {} addresses by the guest are: {:?}", n, guest_output.final_top_n_addresses);
+    } else {
+        error!("Conclusion: The ZK proof indicates a discrepancy or failure in guest execution.");
+        match &guest_output.failure {
+            Some(failure) => error!("Reason: {}", failure),
+            None => error!("This could be due to: total supply mismatch, or the guest's determined Top-N differs from the host's claimed Top-N, or other internal guest error."),
+        }
+        if !guest_output.final_top_n_addresses.is_empty() {
+             warn!("Guest's determined Top {} addresses (if available): {:?}", n, guest_output.final_top_n_addresses);
+        } else {
+            warn!("Guest did not determine/output Top-N addresses, or an earlier error occurred (e.g., balance fetch, total supply mismatch).");
+        }
+    }
+
+    artifacts.record_timing("verify", started.elapsed())?;
+    log_timings(artifacts)?;
+
+    Ok(VerifiedProof {
+        output: guest_output,
+        image_id,
+        journal_digest: receipt.journal.digest(),
+    })
+}
+
+/// Verifies a receipt of the guest, or of the aggregation guest over chunk receipts of the guest,
+/// and returns its output and the image ID it was proven for.
+pub fn verify_receipt(receipt: &Receipt) -> Result<(GuestOutput, Digest)> {
+    if let Err(guest_err) = receipt.verify(TOP_N_HOLDERS_GUEST_ID) {
+        if receipt.verify(TOP_N_HOLDERS_AGGREGATE_ID).is_err() {
+            return Err(guest_err.into());
+        }
+        let aggregate: AggregateOutput =
+            receipt.journal.decode().context("Failed to decode AggregateOutput from ZKVM journal")?;
+        anyhow::ensure!(
+            aggregate.chunk_image_id == TOP_N_HOLDERS_GUEST_ID,
+            "The aggregated chunks were proven by another guest ({})",
+            Digest::from(aggregate.chunk_image_id)
+        );
+        return Ok((aggregate.output, Digest::from(TOP_N_HOLDERS_AGGREGATE_ID)));
+    }
+    let output = receipt.journal.decode().context("Failed to decode GuestOutput from ZKVM journal")?;
+    Ok((output, Digest::from(TOP_N_HOLDERS_GUEST_ID)))
+}
+
+/// Logs where the time of the stages went, e.g. `fetch 2m 3s | build env 1s | ... (total 3h 12m)`.
+fn log_timings(artifacts: &ArtifactDir) -> Result<()> {
+    let timings = artifacts.load_timings()?;
+    if timings.is_empty() {
+        return Ok(());
+    }
+    let format = |seconds: f64| humantime::format_duration(Duration::from_secs(seconds.round() as u64)).to_string();
+    let phases: Vec<String> = timings.iter().map(|timing| format!("{} {}", timing.phase, format(timing.seconds))).collect();
+    // "build env" is part of "preflight".
+    let total: f64 = timings.iter().filter(|timing| timing.phase != "build env").map(|timing| timing.seconds).sum();
+    info!("Phase timings: {} (total {})", phases.join(" | "), format(total));
+    Ok(())
+}
+
+// --- Submit ---
+/// The call of `function`, given as a signature like `submit(bytes,bytes)`, with the journal and
+/// the on-chain seal of `receipt`.
+pub fn submit_calldata(function: &str, receipt: &Receipt) -> Result<(Function, Vec<u8>)> {
+    let seal = risc0_ethereum_contracts::encode_seal(receipt)
+        .context("Failed to encode the seal. On-chain verification needs a receipt proven with --receipt-kind groth16")?;
+    let parsed = Function::parse(function).with_context(|| format!("Invalid function signature: {}", function))?;
+    let calldata = parsed
+        .abi_encode_input(&[
+            DynSolValue::Bytes(receipt.journal.bytes.clone()),
+            DynSolValue::Bytes(seal),
+        ])
+        .with_context(|| format!("{} must take the journal and seal as (bytes, bytes)", function))?;
+    Ok((parsed, calldata))
+}
+
+pub async fn submit(args: &SubmitArgs, artifacts: &ArtifactDir) -> Result<()> {
+    let receipt = artifacts.load_receipt()?;
+    verify_receipt(&receipt)?;
+
+    let (function, calldata) = submit_calldata(&args.function, &receipt)?;
+
+    let signer: PrivateKeySigner = args.private_key.parse().context("Invalid --private-key")?;
+    info!("Submitting proof to {} via {} from {}...", args.contract, function.signature(), signer.address());
+    let provider = ProviderBuilder::new().wallet(signer).connect_http(args.rpc_url.clone());
+    let tx = TransactionRequest::default()
+        .with_to(args.contract)
+        .with_input(calldata);
+    let pending = provider
+        .send_transaction(tx)
+        .await
+        .context("Failed to send the submit transaction")?;
+    info!("Transaction sent: {}", pending.tx_hash());
+
+    let tx_receipt = pending.get_receipt().await.context("Failed to fetch the transaction receipt")?;
+    anyhow::ensure!(
+        tx_receipt.status(),
+        "Submit transaction {} reverted",
+        tx_receipt.transaction_hash
+    );
+    info!(
+        "Proof submitted in block {:?} (transaction {})",
+        tx_receipt.block_number,
+        tx_receipt.transaction_hash
+    );
+    Ok(())
+}
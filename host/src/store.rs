@@ -0,0 +1,96 @@
+// --- Artifact Store ---
+// `--artifact-store` uploads the bundle of every verified proof (receipt, raw journal and JSON
+// report, see `Report::bundle`) to an S3-compatible bucket (`s3://bucket/prefix`) or a Google Cloud
+// Storage bucket (`gs://bucket/prefix`), for provers running as cloud jobs whose local disk is
+// gone after the run. The keys are deterministic:
+//
+//     <prefix>/<chain>/<token>/<block>/top-<N>/receipt.json
+//
+// so a rerun overwrites the same objects, and consumers can find a proof without a listing.
+//
+// Credentials come from the environment the way the cloud SDKs read them (AWS_ACCESS_KEY_ID,
+// AWS_REGION, ..., GOOGLE_SERVICE_ACCOUNT, ...). Anything else, like the endpoint of an
+// S3-compatible service, goes into --artifact-store-option, e.g. in the config file:
+//
+//     artifact_store = "s3://proofs/top-n"
+//     artifact_store_option = ["aws_endpoint=https://minio.internal:9000", "aws_region=us-east-1"]
+//
+// An upload that fails is logged; the proof and the local artifacts stand regardless.
+
+use anyhow::{Context, Result};
+use object_store::aws::{AmazonS3Builder, AmazonS3ConfigKey};
+use object_store::gcp::{GoogleCloudStorageBuilder, GoogleConfigKey};
+use object_store::path::Path as ObjectPath;
+use object_store::{ObjectStore, PutPayload};
+use risc0_steel::alloy::primitives::Address;
+use std::str::FromStr;
+use tracing::info;
+use url::Url;
+
+use top_n_holders_core::ChainName;
+
+use crate::artifacts::ArtifactDir;
+use crate::report::Report;
+
+pub struct ArtifactStore {
+    store: Box<dyn ObjectStore>,
+    url: Url,
+    prefix: ObjectPath,
+}
+
+impl ArtifactStore {
+    /// The store at `url`, with builder `options` (`key=value`) on top of the environment.
+    pub fn new(url: Url, options: &[String]) -> Result<Self> {
+        let options = options
+            .iter()
+            .map(|option| option.split_once('=').with_context(|| format!("Expected key=value: {}", option)))
+            .collect::<Result<Vec<_>>>()?;
+        let store: Box<dyn ObjectStore> = match url.scheme() {
+            "s3" | "s3a" => {
+                let mut builder = AmazonS3Builder::from_env().with_url(url.as_str());
+                for (key, value) in options {
+                    let key = AmazonS3ConfigKey::from_str(key.trim())
+                        .with_context(|| format!("Unknown S3 option `{}`", key))?;
+                    builder = builder.with_config(key, value.trim());
+                }
+                Box::new(builder.build().context("Failed to configure the S3 artifact store")?)
+            }
+            "gs" => {
+                let mut builder = GoogleCloudStorageBuilder::from_env().with_url(url.as_str());
+                for (key, value) in options {
+                    let key = GoogleConfigKey::from_str(key.trim())
+                        .with_context(|| format!("Unknown GCS option `{}`", key))?;
+                    builder = builder.with_config(key, value.trim());
+                }
+                Box::new(builder.build().context("Failed to configure the GCS artifact store")?)
+            }
+            scheme => anyhow::bail!("Unsupported artifact store `{}`; expected s3:// or gs://", scheme),
+        };
+        let prefix = ObjectPath::from_url_path(url.path())
+            .with_context(|| format!("Invalid artifact store prefix: {}", url.path()))?;
+        Ok(Self { store, url, prefix })
+    }
+
+    /// Where the bundle of the Top-`n` of `token` at `block` goes.
+    pub fn proof_path(&self, chain: ChainName, token: Address, block: u64, n: usize) -> ObjectPath {
+        [chain.name().to_string(), format!("{:#x}", token), block.to_string(), format!("top-{}", n)]
+            .iter()
+            .fold(self.prefix.clone(), |path, part| path.child(part.as_str()))
+    }
+
+    /// Uploads the bundle of the verified proof in `artifacts`, whose report is `report`, as the
+    /// Top-`n` of its token. Returns the URL of the bundle's directory.
+    pub async fn upload_proof(&self, n: usize, artifacts: &ArtifactDir, report: &Report) -> Result<String> {
+        let dir = self.proof_path(report.token.chain, report.token.address, report.block.number, n);
+        for (name, contents) in report.bundle(artifacts)? {
+            let path = dir.child(name);
+            self.store
+                .put(&path, PutPayload::from(contents))
+                .await
+                .with_context(|| format!("Failed to upload {}", path))?;
+        }
+        let location = format!("{}://{}/{}", self.url.scheme(), self.url.host_str().unwrap_or_default(), dir);
+        info!("Uploaded the proof bundle to {}", location);
+        Ok(location)
+    }
+}
@@ -0,0 +1,620 @@
+// --- Subgraph Access ---
+// GraphQL queries against one or more Subgraph endpoints. Transient failures (429/5xx,
+// timeouts) are retried with backoff; when an endpoint keeps failing or reports an
+// indexing problem, the next configured endpoint is used.
+
+use anyhow::{Context, Result};
+use indicatif::ProgressBar;
+use reqwest::Client as SubgraphReqwestClient;
+use reqwest::{header::RETRY_AFTER, StatusCode};
+use risc0_steel::alloy::primitives::{Address, U256};
+use serde::de::DeserializeOwned;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::checkpoint::{FetchCheckpoint, RangeProgress};
+use crate::mapping::ResponseMapping;
+use crate::{metrics, progress};
+use crate::retry::{transient, with_retry, RetryPolicy};
+
+const PAGE_SIZE: usize = 1000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HolderData {
+    pub address: Address,
+    pub balance: U256,
+}
+
+/// Receives holders page by page as they are fetched.
+pub trait HolderSink {
+    fn push_page(&mut self, holders: Vec<HolderData>);
+}
+
+impl HolderSink for Vec<HolderData> {
+    fn push_page(&mut self, holders: Vec<HolderData>) {
+        self.extend(holders);
+    }
+}
+
+// GraphQlResponse: Top-level GraphQL response. Graph nodes report indexing failures and
+// query errors in `errors`, often together with a partial or missing `data`.
+#[derive(Deserialize, Debug)]
+struct GraphQlResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GraphQlError {
+    message: String,
+}
+
+
+// MetaData: Structure to deserialize the indexing status (`_meta`) of a Subgraph.
+#[derive(Deserialize, Debug)]
+struct MetaData {
+    #[serde(rename = "_meta")]
+    meta: Meta,
+}
+
+#[derive(Deserialize, Debug)]
+struct Meta {
+    block: MetaBlock,
+    #[serde(rename = "hasIndexingErrors", default)]
+    has_indexing_errors: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct MetaBlock {
+    number: u64,
+}
+
+const META_QUERY: &str = "{ _meta { block { number } hasIndexingErrors } }";
+
+/// Subgraph client with ordered failover between endpoints.
+///
+/// The endpoint that answered last stays active, so a healthy fallback is not abandoned
+/// just because the primary recovered.
+pub struct SubgraphClient {
+    http: SubgraphReqwestClient,
+    urls: Vec<String>,
+    active: AtomicUsize,
+    retry_policy: RetryPolicy,
+    keep_zero: bool,                   // Keep holders whose balance is zero.
+    zero_balances_dropped: AtomicUsize, // Zero-balance holders skipped so far.
+    mapping: ResponseMapping,          // Where holder data lives in the schema.
+}
+
+impl SubgraphClient {
+    pub fn new(urls: Vec<String>, timeout: Duration, retry_policy: RetryPolicy) -> Result<Self> {
+        anyhow::ensure!(!urls.is_empty(), "At least one Subgraph URL (--subgraph-url) is required");
+        let http = SubgraphReqwestClient::builder()
+            .timeout(timeout)
+            .build()
+            .context("Failed to build Subgraph HTTP client")?;
+        Ok(Self {
+            http,
+            urls,
+            active: AtomicUsize::new(0),
+            retry_policy,
+            keep_zero: false,
+            zero_balances_dropped: AtomicUsize::new(0),
+            mapping: ResponseMapping::default(),
+        })
+    }
+
+    /// Uses a custom schema mapping instead of `tokenHolders { id balance }`.
+    pub fn response_mapping(mut self, mapping: ResponseMapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
+    /// Keeps zero-balance holders instead of dropping them while parsing pages.
+    /// Subgraphs rarely delete holder entities, so emptied accounts are dropped by default.
+    pub fn keep_zero_balances(mut self, keep: bool) -> Self {
+        self.keep_zero = keep;
+        self
+    }
+
+    /// Runs a GraphQL query, retrying transient errors and then failing over to the next endpoint.
+    pub async fn query<T: DeserializeOwned>(&self, query: &str) -> Result<T> {
+        let mut failures: Vec<String> = Vec::new();
+        let active = self.active.load(Ordering::Relaxed);
+        for offset in 0..self.urls.len() {
+            let index = (active + offset) % self.urls.len();
+            let url = &self.urls[index];
+            let label = format!("Subgraph request to {}", url);
+            match with_retry(&self.retry_policy, &label, || self.query_endpoint(url, query)).await {
+                Ok(data) => {
+                    if index != active && self.active.swap(index, Ordering::Relaxed) != index {
+                        info!("Switched Subgraph endpoint to {}", url);
+                    }
+                    return Ok(data);
+                }
+                Err(e) => {
+                    warn!("Subgraph endpoint {} failed: {:#}", url, e);
+                    failures.push(format!("{}: {:#}", url, e));
+                }
+            }
+        }
+        anyhow::bail!(
+            "All {} Subgraph endpoint(s) failed:\n{}",
+            self.urls.len(),
+            failures.join("\n")
+        )
+    }
+
+    async fn query_endpoint<T: DeserializeOwned>(&self, url: &str, query: &str) -> Result<T> {
+        // Connection problems and timeouts are worth retrying against the same endpoint.
+        let res = self
+            .http
+            .post(url)
+            .json(&serde_json::json!({ "query": query }))
+            .send()
+            .await
+            .context("Failed to send request to Subgraph")
+            .map_err(|e| transient(e, None))?;
+
+        let status = res.status();
+        let retry_after = res
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let body_text = res
+            .text()
+            .await
+            .context("Failed to read Subgraph response body")
+            .map_err(|e| transient(e, None))?;
+
+        if !status.is_success() {
+            let err = anyhow::anyhow!(
+                "Subgraph request failed with status: {}. Response body: {}",
+                status,
+                body_text
+            );
+            let retryable = status == StatusCode::TOO_MANY_REQUESTS
+                || status == StatusCode::REQUEST_TIMEOUT
+                || status.is_server_error();
+            return Err(if retryable { transient(err, retry_after) } else { err });
+        }
+
+        let response_body: GraphQlResponse<T> = serde_json::from_str(&body_text)
+            .with_context(|| format!(
+                "Failed to decode Subgraph JSON response. Status: {}. Body: {}",
+                status,
+                body_text
+            ))?;
+
+        // Errors include indexing failures ("indexing_error", "has only indexed up to block"),
+        // so they are treated as an unhealthy endpoint rather than trusted partial data.
+        if !response_body.errors.is_empty() {
+            let messages: Vec<&str> = response_body.errors.iter().map(|e| e.message.as_str()).collect();
+            anyhow::bail!("Subgraph returned errors: {}", messages.join("; "));
+        }
+
+        response_body.data.context("Subgraph response contains no data")
+    }
+
+    /// Checks how far each endpoint has indexed and activates the first one that is within
+    /// `max_lag` blocks of `target_block` and reports no indexing errors.
+    ///
+    /// Returns the block the holders should be queried at: `target_block`, or the indexed head
+    /// if the endpoint is (acceptably) behind. When no endpoint is fresh enough this fails,
+    /// unless `allow_stale` is set, in which case the most advanced endpoint is used.
+    pub async fn select_fresh_endpoint(&self, target_block: u64, max_lag: u64, allow_stale: bool) -> Result<u64> {
+        let active = self.active.load(Ordering::Relaxed);
+        let mut most_advanced: Option<(usize, u64)> = None;
+        for offset in 0..self.urls.len() {
+            let index = (active + offset) % self.urls.len();
+            let url = &self.urls[index];
+            let label = format!("Subgraph _meta request to {}", url);
+            let meta = match with_retry(&self.retry_policy, &label, || self.query_endpoint::<MetaData>(url, META_QUERY)).await {
+                Ok(data) => data.meta,
+                Err(e) => {
+                    warn!("Subgraph endpoint {} failed: {:#}", url, e);
+                    continue;
+                }
+            };
+
+            let indexed_block = meta.block.number;
+            let lag = target_block.saturating_sub(indexed_block);
+            info!("Subgraph endpoint {} indexed up to block {} (lag: {} blocks)", url, indexed_block, lag);
+            if meta.has_indexing_errors {
+                warn!("Subgraph endpoint {} reports indexing errors", url);
+            } else if lag <= max_lag {
+                self.active.store(index, Ordering::Relaxed);
+                return Ok(indexed_block.min(target_block));
+            }
+            if most_advanced.is_none_or(|(_, best)| indexed_block > best) {
+                most_advanced = Some((index, indexed_block));
+            }
+        }
+
+        let (index, indexed_block) = most_advanced.context("No Subgraph endpoint answered the _meta query")?;
+        let lag = target_block.saturating_sub(indexed_block);
+        if !allow_stale {
+            anyhow::bail!(
+                "Subgraph data is stale: indexed up to block {}, {} blocks behind target block {} (max lag: {}). \
+                 Use --allow-stale to proceed anyway.",
+                indexed_block,
+                lag,
+                target_block,
+                max_lag
+            );
+        }
+        warn!(
+            "Proceeding with stale Subgraph data from {} (block {}, {} blocks behind target block {})",
+            self.urls[index], indexed_block, lag, target_block
+        );
+        self.active.store(index, Ordering::Relaxed);
+        Ok(indexed_block.min(target_block))
+    }
+
+    /// Fetches every holder of `token`, paginating by `id_gt`.
+    ///
+    /// With `concurrency > 1` and plain address ids, the id space is split into [`IdRange`]s
+    /// that are paginated concurrently, so the holders arrive in no particular order.
+    ///
+    /// With `block` set, every page is read at that block (`block: { number: X }`), so all
+    /// pages and endpoints see one consistent state that matches the on-chain verification.
+    ///
+    /// With a `checkpoint`, every page is persisted as it arrives and ranges resume from the
+    /// stored cursor.
+    pub async fn fetch_token_holders(
+        &self,
+        token: Address,
+        block: Option<u64>,
+        concurrency: usize,
+        checkpoint: Option<&FetchCheckpoint>,
+    ) -> Result<Vec<HolderData>> {
+        let holders = Mutex::new(Vec::new());
+        self.stream_token_holders(token, block, concurrency, checkpoint, &holders).await?;
+        Ok(dedup_holders(holders.into_inner().unwrap_or_else(PoisonError::into_inner)))
+    }
+
+    /// Like [`Self::fetch_token_holders`], but hands every page to `sink` as soon as it is
+    /// parsed instead of collecting all holders. Returns the number of holders streamed.
+    pub async fn stream_token_holders<S: HolderSink>(
+        &self,
+        token: Address,
+        block: Option<u64>,
+        concurrency: usize,
+        checkpoint: Option<&FetchCheckpoint>,
+        sink: &Mutex<S>,
+    ) -> Result<usize> {
+        let ranges = if concurrency > 1 { self.id_ranges(token, block).await? } else { vec![IdRange::default()] };
+        info!(
+            "Fetching holders over {} id range(s) with concurrency {}...",
+            ranges.len(),
+            concurrency.max(1)
+        );
+
+        let bar = progress::items("Fetching Subgraph holders", "holders");
+        let counts: Vec<usize> = stream::iter(ranges)
+            .map(|range| self.fetch_id_range(token, block, range, checkpoint, sink, &bar))
+            .buffer_unordered(concurrency.max(1))
+            .try_collect()
+            .await?;
+        bar.finish_and_clear();
+
+        let total: usize = counts.into_iter().sum();
+        if total == 0 {
+            info!("No holders found for this token in the subgraph.");
+        }
+        info!("Fetched total {} holders from Subgraph.", total);
+        let zero_balances_dropped = self.zero_balances_dropped.swap(0, Ordering::Relaxed);
+        if zero_balances_dropped > 0 {
+            info!("Dropped {} zero-balance holders (use --keep-zero to keep them).", zero_balances_dropped);
+        }
+
+        Ok(total)
+    }
+
+    /// The ranges to paginate concurrently: [`IdRange::partition`] if the entity ids are plain
+    /// addresses, judged by the first one, otherwise the whole id space as one range.
+    async fn id_ranges(&self, token: Address, block: Option<u64>) -> Result<Vec<IdRange>> {
+        let page: serde_json::Value = self.query(&self.holders_query(token, block, 1, "", "")).await?;
+        let first_id = self.mapping.map_page(&page)?.into_iter().next().map(|holder| holder.entity_id);
+        match first_id {
+            Some(id) if IdRange::is_address_id(&id) => Ok(IdRange::partition()),
+            Some(id) => {
+                info!("Holder ids like `{}` are not plain addresses; fetching them as one range.", id);
+                Ok(vec![IdRange::default()])
+            }
+            None => Ok(vec![IdRange::default()]),
+        }
+    }
+
+    /// The query for up to `first` holders of `token` in id order, within the id filters.
+    fn holders_query(&self, token: Address, block: Option<u64>, first: usize, lower_filter: &str, upper_filter: &str) -> String {
+        let block_filter = match block {
+            Some(number) => format!("block: {{ number: {} }},", number),
+            None => String::new(),
+        };
+        let token_filter = match self.mapping.token_field.as_str() {
+            "" => String::new(),
+            // Subgraphs often expect lowercase addresses in IDs/filters
+            field => format!(r#"{}: "{}","#, field, format!("{:#x}", token).to_lowercase()),
+        };
+        format!(
+            r#"{{
+              {}(
+                {}
+                first: {},
+                orderBy: id, # Order by ID for consistent pagination
+                orderDirection: asc, # Ascending order for id_gt
+                where: {{ {} {} {} }}
+              ) {{
+                {}
+              }}
+            }}"#,
+            self.mapping.collection,
+            block_filter,
+            first,
+            token_filter,
+            lower_filter,
+            upper_filter,
+            self.mapping.selection()
+        )
+    }
+
+    async fn fetch_id_range<S: HolderSink>(
+        &self,
+        token: Address,
+        block: Option<u64>,
+        range: IdRange,
+        checkpoint: Option<&FetchCheckpoint>,
+        sink: &Mutex<S>,
+        bar: &ProgressBar,
+    ) -> Result<usize> {
+        let progress = match checkpoint {
+            Some(checkpoint) => checkpoint.load_range(&range.key())?,
+            None => RangeProgress::default(),
+        };
+        let mut fetched_count_total = progress.holders.len();
+        bar.inc(fetched_count_total as u64);
+        push_to_sink(sink, progress.holders);
+        if progress.done {
+            info!("Range {} already complete in checkpoint ({} holders)", range, fetched_count_total);
+            return Ok(fetched_count_total);
+        }
+        // Use last_id for pagination instead of skip
+        let mut last_id: Option<String> = progress.last_id; // None until the first page of the range arrives
+
+        loop {
+            // The first page starts at the inclusive lower bound, later pages after the last seen id.
+            let lower_filter = match (&last_id, &range.lower) {
+                (Some(id), _) => format!(r#"id_gt: "{}""#, id),
+                (None, Some(lower)) => format!(r#"id_gte: "{}""#, lower),
+                (None, None) => String::new(),
+            };
+            let upper_filter = match &range.upper {
+                Some(upper) => format!(r#"id_lt: "{}""#, upper),
+                None => String::new(),
+            };
+            let graphql_query_paginated = self.holders_query(token, block, PAGE_SIZE, &lower_filter, &upper_filter);
+
+            let page: serde_json::Value = self.query(&graphql_query_paginated).await?;
+            metrics::SUBGRAPH_PAGES.inc();
+
+            let fetched_holders_page = self.mapping.map_page(&page)?;
+            let fetched_count = fetched_holders_page.len();
+            info!(
+                "Fetched page with {} holder addresses (range {}, last_id='{}')",
+                fetched_count,
+                range,
+                last_id.as_deref().unwrap_or("")
+            );
+
+            if fetched_count == 0 {
+                break;
+            }
+
+            // Process fetched holders and update last_id
+            let page_last_id = fetched_holders_page.last().map(|holder| holder.entity_id.clone()).unwrap_or_default();
+
+            let mut page_holders: Vec<HolderData> = Vec::with_capacity(fetched_count);
+            for holder_response in fetched_holders_page {
+                let holder_address = parse_holder_id(&holder_response.holder, token)?;
+                let holder_balance = holder_response.balance;
+                if holder_balance.is_zero() && !self.keep_zero {
+                    self.zero_balances_dropped.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
+
+                page_holders.push(HolderData {
+                    address: holder_address,
+                    balance: holder_balance,
+                });
+            }
+
+            if let Some(checkpoint) = checkpoint {
+                checkpoint.append_page(&range.key(), &page_last_id, &page_holders)?;
+            }
+            fetched_count_total += page_holders.len();
+            bar.inc(page_holders.len() as u64);
+            push_to_sink(sink, page_holders);
+            last_id = Some(page_last_id); // Update last_id for the next query
+
+            // Break if the fetched count is less than the page size (last page)
+            if fetched_count < PAGE_SIZE { break; }
+        }
+
+        if let Some(checkpoint) = checkpoint {
+            checkpoint.mark_done(&range.key())?;
+        }
+        Ok(fetched_count_total)
+    }
+}
+
+/// Extracts the holder address from a Subgraph entity id.
+///
+/// Handles plain addresses in any case (including EIP-55 checksummed ones) as well as composite
+/// ids such as `<token>-<holder>` that some subgraphs use for per-token holder entities.
+pub fn parse_holder_id(id: &str, token: Address) -> Result<Address> {
+    let parts: Vec<&str> = id.trim().split(['-', ':', '_']).filter(|part| !part.is_empty()).collect();
+    if let [single] = parts.as_slice() {
+        return Address::from_str(single)
+            .with_context(|| format!("Failed to parse holder address from id: {}", id));
+    }
+
+    let addresses: Vec<Address> = parts.iter().filter_map(|part| Address::from_str(part).ok()).collect();
+    // Prefer the component that is not the token itself; the token may hold its own tokens.
+    addresses
+        .iter()
+        .rev()
+        .find(|&&address| address != token)
+        .or(addresses.last())
+        .copied()
+        .with_context(|| format!("Failed to parse holder address from id: {}", id))
+}
+
+/// Merges entries that refer to the same holder (e.g. the same account under differently
+/// shaped ids), keeping the larger balance.
+pub fn dedup_holders(holders: Vec<HolderData>) -> Vec<HolderData> {
+    let fetched = holders.len();
+    let mut balances: HashMap<Address, U256> = HashMap::with_capacity(fetched);
+    for holder in holders {
+        let balance = balances.entry(holder.address).or_default();
+        *balance = (*balance).max(holder.balance);
+    }
+    if balances.len() < fetched {
+        warn!("Merged {} duplicate holder entries.", fetched - balances.len());
+    }
+    balances
+        .into_iter()
+        .map(|(address, balance)| HolderData { address, balance })
+        .collect()
+}
+
+fn push_to_sink<S: HolderSink>(sink: &Mutex<S>, holders: Vec<HolderData>) {
+    if !holders.is_empty() {
+        sink.lock().unwrap_or_else(PoisonError::into_inner).push_page(holders);
+    }
+}
+
+/// A half-open `[lower, upper)` slice of the (string-ordered) holder id space.
+#[derive(Debug, Clone, Default)]
+struct IdRange {
+    lower: Option<String>, // Inclusive; None means unbounded.
+    upper: Option<String>, // Exclusive; None means unbounded.
+}
+
+impl IdRange {
+    /// Splits the id space on the first byte after `0x`, at `0x10`, `0x20`, ..., `0xf0`. The
+    /// bounds are whole bytes: subgraphs with `Bytes` ids reject odd-length hex in `id_gt`/`id_lt`.
+    /// String ids of lowercase addresses order the same way. The outer ranges are unbounded.
+    fn partition() -> Vec<IdRange> {
+        let bounds: Vec<String> = "123456789abcdef".chars().map(|c| format!("0x{}0", c)).collect();
+        let mut ranges = Vec::with_capacity(bounds.len() + 1);
+        let mut lower: Option<String> = None;
+        for bound in bounds {
+            ranges.push(IdRange { lower: lower.take(), upper: Some(bound.clone()) });
+            lower = Some(bound);
+        }
+        ranges.push(IdRange { lower, upper: None });
+        ranges
+    }
+
+    /// Whether `id` is a lowercase `0x` address, the id shape `partition` splits evenly.
+    /// Composite ids (`<token>-<holder>`) would all fall into the range of the token.
+    fn is_address_id(id: &str) -> bool {
+        id.len() == 42
+            && id.starts_with("0x")
+            && id[2..].bytes().all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+    }
+
+    /// File-name friendly identifier of the range, stable across runs.
+    fn key(&self) -> String {
+        format!(
+            "{}-{}",
+            self.lower.as_deref().unwrap_or("min"),
+            self.upper.as_deref().unwrap_or("max")
+        )
+    }
+}
+
+impl fmt::Display for IdRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}, {})",
+            self.lower.as_deref().unwrap_or("-"),
+            self.upper.as_deref().unwrap_or("-")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+    const HOLDER: &str = "0x47ac0fb4f2d84898e4d9e7b4dab3c24507a6d503";
+
+    fn in_range(range: &IdRange, id: &str) -> bool {
+        range.lower.as_deref().is_none_or(|lower| id >= lower) && range.upper.as_deref().is_none_or(|upper| id < upper)
+    }
+
+    #[test]
+    fn partition_bounds_are_whole_contiguous_bytes() {
+        let ranges = IdRange::partition();
+        assert_eq!(ranges.len(), 16);
+        assert_eq!(ranges[0].lower, None);
+        assert_eq!(ranges[15].upper, None);
+        for pair in ranges.windows(2) {
+            assert_eq!(pair[0].upper, pair[1].lower);
+            let bound = pair[0].upper.as_deref().unwrap();
+            assert_eq!(bound.len(), 4, "{}", bound);
+            assert!(hex::decode(&bound[2..]).is_ok(), "{}", bound);
+        }
+    }
+
+    #[test]
+    fn every_address_id_falls_into_one_range() {
+        for first in 0..=255u8 {
+            let id = format!("0x{:02x}{}", first, "00".repeat(19));
+            assert!(IdRange::is_address_id(&id));
+            let matching = IdRange::partition().iter().filter(|range| in_range(range, &id)).count();
+            assert_eq!(matching, 1, "{}", id);
+        }
+    }
+
+    #[test]
+    fn only_lowercase_addresses_are_partitioned() {
+        assert!(IdRange::is_address_id(HOLDER));
+        assert!(!IdRange::is_address_id(&HOLDER.to_uppercase().replace("0X", "0x")));
+        assert!(!IdRange::is_address_id(&format!("{}-{}", TOKEN, HOLDER)));
+        assert!(!IdRange::is_address_id("0x47ac"));
+    }
+
+    #[test]
+    fn parses_holder_ids() {
+        let token = Address::from_str(TOKEN).unwrap();
+        let holder = Address::from_str(HOLDER).unwrap();
+        assert_eq!(parse_holder_id(HOLDER, token).unwrap(), holder);
+        assert_eq!(parse_holder_id(&holder.to_checksum(None), token).unwrap(), holder);
+        assert_eq!(parse_holder_id(&format!(" {} ", HOLDER), token).unwrap(), holder);
+        assert_eq!(parse_holder_id(&format!("{}-{}", TOKEN, HOLDER), token).unwrap(), holder);
+        assert_eq!(parse_holder_id(&format!("{}:{}", HOLDER, TOKEN), token).unwrap(), holder);
+        assert_eq!(parse_holder_id(&format!("{}_{}", TOKEN, TOKEN), token).unwrap(), token);
+    }
+
+    #[test]
+    fn rejects_ids_without_an_address() {
+        let token = Address::from_str(TOKEN).unwrap();
+        assert!(parse_holder_id("", token).is_err());
+        assert!(parse_holder_id("0x47ac", token).is_err());
+        assert!(parse_holder_id("holder-42", token).is_err());
+    }
+}
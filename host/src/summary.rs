@@ -0,0 +1,84 @@
+// --- Result Summary ---
+// The verified Top-N as a table on stdout, with balances in token units, their share of the
+// total supply, whether each holder is an EOA or a contract, its label and ENS name (mainnet). The
+// balances are the preflighted ones from `token.json`, i.e. those the guest read at the proven
+// block.
+
+use alloy::primitives::utils::format_units;
+use anyhow::Result;
+use risc0_steel::alloy::primitives::U256;
+
+use top_n_holders_core::GuestOutput;
+
+use crate::accounts::AccountInfo;
+use crate::artifacts::ArtifactDir;
+
+/// Prints the table, unless the artifacts predate `token.json`.
+pub fn print(artifacts: &ArtifactDir, output: &GuestOutput) -> Result<()> {
+    let Some(token) = artifacts.load_token()? else {
+        return Ok(());
+    };
+    let symbol = token.symbol.as_deref().unwrap_or("");
+    let amount = |balance: U256| format!("{} {}", format_amount(balance, token.decimals), symbol).trim_end().to_string();
+
+    println!();
+    println!(
+        "Top {} holders of {} (total supply: {})",
+        output.final_top_n_addresses.len(),
+        token.symbol.as_deref().unwrap_or("the token"),
+        amount(token.total_supply)
+    );
+    println!("{:>4}  {:<42}  {:>32}  {:>8}  {:<8}  {:<24}  {}", "#", "Address", "Balance", "Supply", "Type", "Label", "ENS");
+    for (i, address) in output.final_top_n_addresses.iter().enumerate() {
+        let balance = token.balances.get(address).copied().unwrap_or_default();
+        println!(
+            "{:>4}  {:<42}  {:>32}  {:>8}  {:<8}  {:<24}  {}",
+            i + 1,
+            address.to_string(),
+            amount(balance),
+            share(balance, token.total_supply),
+            token.accounts.get(address).map_or("-", AccountInfo::tag),
+            token.labels.get(address).map_or("", String::as_str),
+            token.ens_names.get(address).map_or("", String::as_str)
+        );
+    }
+    println!();
+    Ok(())
+}
+
+/// `balance` in token units, or in base units if the decimals are unknown.
+pub(crate) fn format_amount(balance: U256, decimals: Option<u8>) -> String {
+    let amount = match decimals {
+        Some(decimals) => format_units(balance, decimals).unwrap_or_else(|_| balance.to_string()),
+        None => return balance.to_string(),
+    };
+    // "1.500000000000000000" -> "1.5", "2.000000000000000000" -> "2"
+    if amount.contains('.') {
+        amount.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        amount
+    }
+}
+
+/// `balance` as a percentage of `total_supply`, with two decimals.
+fn share(balance: U256, total_supply: U256) -> String {
+    match percent(balance, total_supply, 2) {
+        Some(percent) => format!("{}%", percent),
+        None => "-".to_string(),
+    }
+}
+
+/// `balance` as a percentage of `total_supply` with `precision` decimals (truncated), or `None`
+/// for a zero supply.
+pub(crate) fn percent(balance: U256, total_supply: U256, precision: u32) -> Option<String> {
+    if total_supply.is_zero() {
+        return None;
+    }
+    let scale = 10u64.pow(precision);
+    let scaled = balance * U256::from(100 * scale) / total_supply;
+    let scaled = scaled.saturating_to::<u64>();
+    Some(match precision {
+        0 => scaled.to_string(),
+        _ => format!("{}.{:0width$}", scaled / scale, scaled % scale, width = precision as usize),
+    })
+}
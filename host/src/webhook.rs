@@ -0,0 +1,83 @@
+// --- Webhook Notifications ---
+// `--webhook-url` receives a JSON POST for every token a run finishes, successful or not, so
+// that alerting and downstream automation don't have to poll the artifacts.
+
+use anyhow::{Context, Result};
+use risc0_steel::alloy::primitives::Address;
+use serde::Serialize;
+use std::time::Duration;
+use tracing::{info, warn};
+use url::Url;
+
+use top_n_holders_core::{ChainName, VerificationFailure};
+
+use crate::stages::VerifiedProof;
+
+#[derive(Serialize, Debug)]
+pub struct ProofNotification {
+    pub token: Address,
+    pub chain_spec: ChainName,
+    pub block: u64,
+    pub n: usize,
+    pub success: bool,                  // Proven, and the guest's checks passed.
+    pub journal_digest: Option<String>, // Hex SHA-256 of the journal, if a proof was produced.
+    pub top_n_addresses: Vec<Address>,
+    pub failure: Option<VerificationFailure>, // Why the guest rejected the ranking, if it did.
+    pub error: Option<String>,
+}
+
+impl ProofNotification {
+    pub fn new(token: Address, chain_spec: ChainName, block: u64, n: usize, result: &Result<VerifiedProof>) -> Self {
+        let mut notification = Self {
+            token,
+            chain_spec,
+            block,
+            n,
+            success: false,
+            journal_digest: None,
+            top_n_addresses: Vec::new(),
+            failure: None,
+            error: None,
+        };
+        match result {
+            Ok(proof) => {
+                notification.success = proof.output.verification_succeeded;
+                notification.journal_digest = Some(format!("0x{}", proof.journal_digest));
+                notification.top_n_addresses = proof.output.final_top_n_addresses.clone();
+                notification.failure = proof.output.failure.clone();
+            }
+            Err(e) => notification.error = Some(format!("{:#}", e)),
+        }
+        notification
+    }
+}
+
+pub struct Webhook {
+    http: reqwest::Client,
+    url: Url,
+}
+
+impl Webhook {
+    pub fn new(url: Url) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .context("Failed to build webhook HTTP client")?;
+        Ok(Self { http, url })
+    }
+
+    /// Posts a notification. Failures are only logged; they never fail the run.
+    pub async fn notify(&self, notification: &ProofNotification) {
+        let sent = self
+            .http
+            .post(self.url.clone())
+            .json(notification)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        match sent {
+            Ok(_) => info!("Notified webhook about {} (success: {})", notification.token, notification.success),
+            Err(e) => warn!("Failed to notify webhook {}: {}", self.url, e),
+        }
+    }
+}
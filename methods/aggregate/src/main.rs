@@ -0,0 +1,69 @@
+#![no_main]
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use risc0_zkvm::guest::env;
+use top_n_holders_core::{aggregate_chunks, address_set_hash, AggregateOutput, ChunkOutput, GuestOutput};
+
+risc0_zkvm::guest::entry!(main);
+
+// Logs only in guests built with the `log` feature (host feature `guest-log`). Otherwise the
+// arguments are type-checked but never formatted, so proofs spend no cycles on log messages.
+macro_rules! log {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "log") {
+            env::log(&alloc::format!($($arg)*));
+        }
+    };
+}
+
+// Joins the receipts of a chunked proof into one, see "Chunked Proofs" in `top_n_holders_core`.
+// The host adds the chunk receipts as assumptions; `env::verify` makes this proof conditional on
+// them, and the prover resolves them into the final receipt.
+fn main() {
+    let n: u64 = env::read();
+    let chunk_image_id: [u32; 8] = env::read();
+    let chunk_count: u32 = env::read();
+
+    // The journals come as frames, which are much cheaper to read than serde-encoded bytes.
+    let chunks: Vec<ChunkOutput> = (0..chunk_count)
+        .map(|_| {
+            let journal = env::read_frame();
+            env::verify(chunk_image_id, &journal).expect("Failed to verify a chunk receipt");
+            let words: Vec<u32> = journal
+                .chunks_exact(4)
+                .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+                .collect();
+            risc0_zkvm::serde::from_slice(&words).expect("Failed to decode a chunk journal")
+        })
+        .collect();
+    log!("INFO: Verified {} chunk receipts.", chunks.len());
+
+    let ranking = aggregate_chunks(n as usize, &chunks);
+    let first = chunks.into_iter().next().expect("At least one chunk");
+    let (top_desc_holders, failure) = match ranking {
+        Ok(top_desc_holders) => (top_desc_holders, None),
+        Err(failure) => {
+            log!("ERROR: Verification failed: {}", failure);
+            (Vec::new(), Some(failure))
+        }
+    };
+    env::commit(&AggregateOutput {
+        output: GuestOutput {
+            verification_succeeded: failure.is_none(),
+            final_top_n_addresses: top_desc_holders,
+            commitment: first.commitment,
+            excluded_addresses_hash: address_set_hash(&first.excluded_addresses),
+            sanctions_list_hash: first.sanctions_list_hash,
+            excluded_addresses: first.excluded_addresses,
+            failure,
+            fork_overrides: first.fork_overrides,
+            top_n_hhi: None, // The chunks commit no balances to compute it from.
+            top_n_merkle_root: None, // Nor the Merkle root.
+        },
+        chunk_image_id,
+    });
+}
@@ -1,3 +1,22 @@
+use std::collections::HashMap;
+
+use risc0_build::GuestOptionsBuilder;
+
 fn main() {
-    risc0_build::embed_methods();
-}
\ No newline at end of file
+    // Guest features follow the features of this crate.
+    let mut features = Vec::new();
+    if std::env::var_os("CARGO_FEATURE_OP").is_some() {
+        features.push("op".to_string());
+    }
+    if std::env::var_os("CARGO_FEATURE_GUEST_LOG").is_some() {
+        features.push("log".to_string());
+    }
+    let options = GuestOptionsBuilder::default()
+        .features(features)
+        .build()
+        .expect("valid guest options");
+    risc0_build::embed_methods_with_options(HashMap::from([
+        ("top-n-holders-guest", options.clone()),
+        ("top-n-holders-aggregate", options),
+    ]));
+}
@@ -3,124 +3,173 @@
 
 extern crate alloc;
 
-use alloc::vec::Vec;
-use serde::{Deserialize, Serialize};
-
-use top_n_holders_core::{GuestInput, GuestOutput};
-
-use alloy_primitives::{Address, U256};
-use alloy_sol_types::{sol};
+use top_n_holders_core::{
+    chain_spec, address_set_hash, rank_holders, verify_chunk, with_fork_overrides, ChunkOutput, CycleProfile, GuestInput, GuestOutput,
+    VerificationFailure, IERC20,
+};
 
 // --- Risc0 Steel Imports ---
 
-use risc0_steel::{
-    ethereum::{
-        ETH_MAINNET_CHAIN_SPEC,
-        ETH_SEPOLIA_CHAIN_SPEC,
-    },
-    Contract,
-};
+use risc0_steel::Contract;
 use risc0_steel::ethereum::EthEvmInput;
 use risc0_zkvm::guest::env;
 
 risc0_zkvm::guest::entry!(main);
 
-// --- Alloy setup for Contract Calls (used by steel) ---
-sol!(
-    interface IERC20 {
-        function balanceOf(address account) external view returns (uint256);
-        function totalSupply() external view returns (uint256);
-    }
-);
+// Logs only in guests built with the `log` feature (host feature `guest-log`). Otherwise the
+// arguments are type-checked but never formatted, so proofs spend no cycles on log messages.
+macro_rules! log {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "log") {
+            env::log(&alloc::format!($($arg)*));
+        }
+    };
+}
 
-// Define the structure for holder data, used internally after fetching balances
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-struct HolderData {
-    address: Address,
-    balance: U256,
+// The verification for one Steel environment; a macro, since the Ethereum and the OP Stack
+// environments are different types. Commits the output and, with `profile_cycles`, records the
+// cycles of each phase in `$profile`.
+macro_rules! verify_ranking {
+    ($guest_input:expr, $steel_evm_env:expr, $profile:ident) => {{
+        let guest_input: GuestInput = $guest_input;
+        let profiling = guest_input.profile_cycles;
+        // Zero unless profiling, so that proofs spend no syscalls on the profile.
+        let cycle_count = || if profiling { env::cycle_count() } else { 0 };
+        $profile.input = cycle_count();
+        let steel_evm_env = $steel_evm_env;
+        $profile.env_setup = cycle_count() - $profile.input;
+
+        let erc20_contract = Contract::new(guest_input.erc20_contract_address, &steel_evm_env);
+        let total_supply = || {
+            erc20_contract
+                .call_builder(&IERC20::totalSupplyCall {})
+                .try_call()
+                .map_err(|_| VerificationFailure::TotalSupplyCallFailed)
+        };
+        let mut balance_of = |account| {
+            let call_started = cycle_count();
+            let balance = erc20_contract
+                .call_builder(&IERC20::balanceOfCall { account })
+                .try_call()
+                .map_err(|_| VerificationFailure::BalanceCallFailed(account));
+            if profiling {
+                $profile.balance_calls.push(cycle_count() - call_started);
+            }
+            balance
+        };
+
+        if let Some(range) = guest_input.chunk {
+            // --- A chunk of the list, see `top_n_holders_core::verify_chunk` ---
+            log!(
+                "INFO: Verifying holders {}..{} of {}...",
+                range.start,
+                range.start + guest_input.required_addresses_desc.len(),
+                range.total
+            );
+            let phase_started = cycle_count();
+            let result = verify_chunk(&guest_input, total_supply, &mut balance_of);
+            $profile.ranking = cycle_count() - phase_started;
+            if let Err(failure) = &result {
+                log!("ERROR: Verification failed: {}", failure);
+            }
+            env::commit(&ChunkOutput {
+                commitment: steel_evm_env.commitment().clone(),
+                erc20_contract_address: guest_input.erc20_contract_address,
+                chain: guest_input.chain,
+                sanctions_list_hash: guest_input.sanctions_list_hash(),
+                excluded_addresses: guest_input.excluded_addresses,
+                fork_overrides: guest_input.fork_overrides,
+                n: guest_input.n,
+                range,
+                holders: guest_input.required_addresses_desc,
+                result,
+            });
+        } else {
+            // --- 1. Fetch total supply ---
+            let phase_started = cycle_count();
+            let ranking = match total_supply() {
+                Ok(total_supply_result) => {
+                    $profile.total_supply = cycle_count() - phase_started;
+                    log!("INFO: Fetched total supply: {}", total_supply_result);
+
+                    // --- 2. Verify the ranking ---
+                    // The same checks the host runs for `--check`, see `top_n_holders_core::rank_holders`.
+                    log!(
+                        "INFO: Verifying {} holders ({} excluded)...",
+                        guest_input.required_addresses_desc.len(),
+                        guest_input.excluded_addresses.len()
+                    );
+                    let phase_started = cycle_count();
+                    let ranking = rank_holders(&guest_input, total_supply_result, &mut balance_of);
+                    $profile.ranking = cycle_count() - phase_started;
+                    ranking
+                }
+                Err(failure) => Err(failure),
+            };
+
+            // A rejected input is committed with its reason, so that the host can tell why.
+            let (top_desc_holders, top_n_hhi, top_n_merkle_root, failure) = match ranking {
+                Ok(ranking) => {
+                    let top_n_hhi = guest_input.commit_hhi.then(|| ranking.hhi_ppm());
+                    let top_n_merkle_root = guest_input.commit_merkle_root.map(|leaves| ranking.merkle_root(leaves));
+                    (ranking.holders, top_n_hhi, top_n_merkle_root, None)
+                }
+                Err(failure) => {
+                    log!("ERROR: Verification failed: {}", failure);
+                    (alloc::vec::Vec::new(), None, None, Some(failure))
+                }
+            };
+            env::commit(&GuestOutput {
+                verification_succeeded: failure.is_none(),
+                final_top_n_addresses: top_desc_holders, // Commit the determined top N
+                commitment: steel_evm_env.commitment().clone(), // Binds the result to the proven block
+                excluded_addresses_hash: address_set_hash(&guest_input.excluded_addresses),
+                sanctions_list_hash: guest_input.sanctions_list_hash(),
+                excluded_addresses: guest_input.excluded_addresses,
+                failure,
+                fork_overrides: guest_input.fork_overrides,
+                top_n_hhi,
+                top_n_merkle_root,
+            });
+        }
+    }};
 }
 
 fn main() {
-    // Read the input data passed from the host
-    let input: EthEvmInput = env::read();
-    let guest_input: GuestInput = env::read();
-    env::log("INFO: Guest program started. Input received.");
+    // Read the input data passed from the host. The guest input comes first: its chain decides
+    // the type of the EVM input. It comes in the compact encoding, which is much cheaper to read
+    // than the zkVM's serde encoding; inputs of a version this guest does not know fail to decode.
+    let guest_input = GuestInput::from_compact(&env::read_frame()).expect("Failed to decode the guest input");
+    log!("INFO: Guest program started. Input received.");
+    let profile_cycles = guest_input.profile_cycles;
+    let mut profile = CycleProfile::default();
 
     // --- 0. Initialize Steel Environment ---
-
-    env::log(&alloc::format!("INFO: Setting up EthEvmEnv for chain: {}", guest_input.chain_spec_name));
-    let steel_evm_env = match guest_input.chain_spec_name.to_lowercase().as_str() {
-        "mainnet" => input.into_env(&ETH_MAINNET_CHAIN_SPEC),
-        "sepolia" => input.into_env(&ETH_SEPOLIA_CHAIN_SPEC),
-        "gnosis" => input.into_env(&top_n_holders_core::GNOSIS_MAINNET_CHAIN_SPEC),
-        _ => panic!("Chain spec not supported: {}", guest_input.chain_spec_name),
-    };
-    env::log("INFO: EthEvmEnv configured.");
-
-    // --- 0.5. Verifying inputs ---
-    env::log(&alloc::format!("INFO: Verifying input data..."));
-    assert!(!guest_input.required_addresses_desc.is_empty(), "Holders list is empty");
-    assert!(guest_input.n > 0, "N must be greater than 0");
-    assert!(guest_input.n <= guest_input.required_addresses_desc.len(), "N exceeds number of holders");
-
-    // --- 1. Fetch Balances for the required holders ---
-    env::log(&alloc::format!("INFO: Fetching balances for {} holders...", guest_input.required_addresses_desc.len()));
-    let erc20_contract = Contract::new(guest_input.erc20_contract_address, &steel_evm_env);
-
-    // --- 1. Fetch total supply ---
-    let call = IERC20::totalSupplyCall {};
-    let total_supply_result = erc20_contract.call_builder(&call).call();
-    env::log(&alloc::format!("INFO: Fetched total supply: {}", total_supply_result));
-
-    // --- 1.5. Verify the total supply ---
-    let mut latest_balance: Option<U256> = None;
-    let mut top_holders_accumulated: U256 = U256::ZERO;
-    let mut i = 0;
-
-    // The holders array is sorted from the highest holder balance to the lowest one.
-    let mut top_desc_holders: Vec<Address> = Vec::new();
-    for holder_address in &guest_input.required_addresses_desc {
-        let call = IERC20::balanceOfCall { account: *holder_address };
-        let current_balance_result = erc20_contract.call_builder(&call).call();
-
-        // Check if the balance is gte than the latest balance
-
-        if let Some(prev_balance) = latest_balance {
-            env::log(&alloc::format!("DEBUG: Current balance: {}, Latest balance: {}", current_balance_result, prev_balance));
-            assert!(current_balance_result <= prev_balance, "Balance is not lower than or equal to the latest balance");
-        }
-        latest_balance = Some(current_balance_result);
-        top_holders_accumulated += current_balance_result;
-        top_desc_holders.push(*holder_address);
-        i += 1;
-
-        // for ex. total supply is 100.
-        //
-        // A has 45, cumulative 45
-        // B has 25, cumulative 70
-        // C has 14, cumulative 84
-        // D has 6, cumulative 90
-        // E has 6, cumulative 96
-        // F has 2, cumulative 98
-        if i > guest_input.n {
-            let supply_remainder: U256 = total_supply_result - top_holders_accumulated;
-            assert!(supply_remainder > U256::ZERO, "Top N holders exceed total supply");
-
-            // 100 - 84 = 16; sr16 > lb14, false
-            // 100 - 90 = 10; sr10 > lb6, false
-            // 100 - 96 = 4; sr4 < lb6, true
-            env::log(&alloc::format!("DEBUG: Supply remainder: {}, latest balance: {}", supply_remainder, latest_balance.unwrap()));
-            if supply_remainder < latest_balance.unwrap() {
-                break;
-            }
+    log!("INFO: Setting up the Steel environment for chain: {}", guest_input.chain);
+    #[cfg(feature = "op")]
+    if let Some(spec) = top_n_holders_core::op_chain_spec(guest_input.chain) {
+        assert!(guest_input.fork_overrides.is_empty(), "Fork overrides are not supported on OP Stack chains");
+        let input: risc0_op_steel::OpEvmInput = env::read();
+        verify_ranking!(guest_input, input.into_env(spec), profile);
+        if profile_cycles {
+            env::write_slice(&profile.to_bytes());
         }
+        log!("INFO: Commit complete. Exiting guest.");
+        return;
     }
-
-    // --- 6. Commit the result to the journal ---
-    let output = GuestOutput {
-        verification_succeeded: true,
-        final_top_n_addresses: top_desc_holders, // Commit the determined top N
-    };
-    env::commit(&output);
-    env::log("INFO: Commit complete. Exiting guest.");
-}
\ No newline at end of file
+    let spec = chain_spec(guest_input.chain)
+        .unwrap_or_else(|| panic!("Chain spec not supported: {}", guest_input.chain));
+    // Overridden forks change the chain spec, and with it the configuration the commitment binds.
+    let spec = with_fork_overrides(spec, &guest_input.fork_overrides).unwrap_or_else(|reason| panic!("{}", reason));
+    if !guest_input.fork_overrides.is_empty() {
+        log!("INFO: Applied fork overrides: {:?}", guest_input.fork_overrides);
+    }
+    let input: EthEvmInput = env::read();
+    // --- 3. Verify and commit the result to the journal ---
+    verify_ranking!(guest_input, input.into_env(&spec), profile);
+    // For the host's `--profile`; not part of the journal.
+    if profile_cycles {
+        env::write_slice(&profile.to_bytes());
+    }
+    log!("INFO: Commit complete. Exiting guest.");
+}